@@ -1345,7 +1345,8 @@ fn explain_captures(
                                 );
                                 for error in errors {
                                     if let FulfillmentErrorCode::SelectionError(
-                                        SelectionError::Unimplemented,
+                                        SelectionError::Unimplemented
+                                        | SelectionError::ImplObligationFailed { .. },
                                     ) = error.code
                                         && let ty::PredicateKind::Clause(ty::ClauseKind::Trait(
                                             pred,
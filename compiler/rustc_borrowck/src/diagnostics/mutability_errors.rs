@@ -1284,7 +1284,8 @@ fn suggest_make_local_mut(&self, err: &mut Diag<'_>, local: Local, name: Symbol)
                                 // The type doesn't implement Clone because of unmet obligations.
                                 for error in errors {
                                     if let traits::FulfillmentErrorCode::SelectionError(
-                                        traits::SelectionError::Unimplemented,
+                                        traits::SelectionError::Unimplemented
+                                        | traits::SelectionError::ImplObligationFailed { .. },
                                     ) = error.code
                                         && let ty::PredicateKind::Clause(ty::ClauseKind::Trait(
                                             pred,
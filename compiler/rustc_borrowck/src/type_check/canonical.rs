@@ -1,14 +1,17 @@
-use std::fmt;
+use std::{fmt, mem};
 
 use rustc_errors::ErrorGuaranteed;
 use rustc_infer::infer::canonical::Canonical;
 use rustc_middle::bug;
 use rustc_middle::mir::ConstraintCategory;
+use rustc_middle::traits::query::NoSolution;
 use rustc_middle::ty::{self, ToPredicate, Ty, TyCtxt, TypeFoldable};
 use rustc_span::def_id::DefId;
 use rustc_span::Span;
+use rustc_trait_selection::solve::{deeply_normalize_with_fulfillment_ctxt, FulfillmentCtxt};
+use rustc_trait_selection::traits::query::type_op::custom::CustomTypeOp;
 use rustc_trait_selection::traits::query::type_op::{self, TypeOpOutput};
-use rustc_trait_selection::traits::ObligationCause;
+use rustc_trait_selection::traits::{ObligationCause, TraitEngine};
 
 use crate::diagnostics::ToUniverseInfo;
 
@@ -157,6 +160,10 @@ pub(super) fn normalize_with_category<T>(
     where
         T: type_op::normalize::Normalizable<'tcx> + fmt::Display + Copy + 'tcx,
     {
+        if self.infcx.next_trait_solver() {
+            return self.normalize_with_category_next_solver(value, location, category);
+        }
+
         let param_env = self.param_env;
         let result: Result<_, ErrorGuaranteed> = self.fully_perform_op(
             location.to_locations(),
@@ -166,6 +173,74 @@ pub(super) fn normalize_with_category<T>(
         result.unwrap_or(value)
     }
 
+    /// The `Normalize` type op does not normalize anything with the new solver,
+    /// so we instead deeply normalize `value` using a fulfillment context, similar
+    /// to HIR typeck. Aliases whose normalization is still ambiguous are replaced
+    /// with inference variables and the goals constraining them are deferred until
+    /// `prove_deferred_normalization_obligations` is called at the end of MIR typeck.
+    fn normalize_with_category_next_solver<T>(
+        &mut self,
+        value: T,
+        location: impl NormalizeLocation,
+        category: ConstraintCategory<'tcx>,
+    ) -> T
+    where
+        T: type_op::normalize::Normalizable<'tcx> + fmt::Display + Copy + 'tcx,
+    {
+        let locations = location.to_locations();
+        let span = locations.span(self.body);
+        let param_env = self.param_env;
+        let result: Result<_, ErrorGuaranteed> = self.fully_perform_op(
+            locations,
+            category,
+            CustomTypeOp::new(
+                |ocx| {
+                    let cause = ObligationCause::dummy_with_span(span);
+                    let mut fulfill_cx = FulfillmentCtxt::new(ocx.infcx);
+                    let value = deeply_normalize_with_fulfillment_ctxt(
+                        ocx.infcx.at(&cause, param_env),
+                        value,
+                        &mut fulfill_cx,
+                    )
+                    .map_err(|_| NoSolution)?;
+                    Ok((value, fulfill_cx.pending_obligations()))
+                },
+                "normalize",
+            ),
+        );
+
+        match result {
+            Ok((value, deferred)) => {
+                self.deferred_normalization_obligations.extend(
+                    deferred.into_iter().map(|obligation| (obligation, locations, category)),
+                );
+                value
+            }
+            Err(_) => value,
+        }
+    }
+
+    /// Proves the goals deferred by `normalize_with_category_next_solver`. By now
+    /// the rest of the body has been type checked, so the inference variables
+    /// they depend on should have been constrained.
+    pub(super) fn prove_deferred_normalization_obligations(&mut self) {
+        for (obligation, locations, category) in
+            mem::take(&mut self.deferred_normalization_obligations)
+        {
+            let _: Result<_, ErrorGuaranteed> = self.fully_perform_op(
+                locations,
+                category,
+                CustomTypeOp::new(
+                    |ocx| {
+                        ocx.register_obligation(obligation);
+                        Ok(())
+                    },
+                    "prove_deferred_normalization_obligation",
+                ),
+            );
+        }
+    }
+
     #[instrument(skip(self), level = "debug")]
     pub(super) fn ascribe_user_type(
         &mut self,
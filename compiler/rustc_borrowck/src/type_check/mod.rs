@@ -200,6 +200,8 @@ pub(crate) fn type_check<'mir, 'tcx>(
         use_polonius,
     );
 
+    checker.prove_deferred_normalization_obligations();
+
     translate_outlives_facts(&mut checker);
     let opaque_type_values = infcx.take_opaque_types();
 
@@ -877,6 +879,10 @@ struct TypeChecker<'a, 'tcx> {
     implicit_region_bound: ty::Region<'tcx>,
     reported_errors: FxIndexSet<(Ty<'tcx>, Span)>,
     borrowck_context: &'a mut BorrowCheckContext<'a, 'tcx>,
+    /// Only used with the new solver: goals from normalizing types in the MIR
+    /// which were still ambiguous. See `normalize_with_category_next_solver`.
+    deferred_normalization_obligations:
+        Vec<(PredicateObligation<'tcx>, Locations, ConstraintCategory<'tcx>)>,
 }
 
 struct BorrowCheckContext<'a, 'tcx> {
@@ -1039,6 +1045,7 @@ fn new(
             implicit_region_bound,
             borrowck_context,
             reported_errors: Default::default(),
+            deferred_normalization_obligations: Vec::new(),
         };
 
         // FIXME(-Znext-solver): A bit dubious that we're only registering
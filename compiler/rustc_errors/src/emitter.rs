@@ -178,6 +178,23 @@ fn right(&self, line_len: usize) -> usize {
 
 pub type DynEmitter = dyn Emitter + DynSend;
 
+/// A machine-readable description of an ambiguity or overflow error of the trait
+/// solver, emitted in addition to the error itself. See `-Zjson-ambiguity-reports`.
+pub struct AmbiguityReport {
+    /// The span of the ambiguous obligation.
+    pub span: Span,
+    /// Whether the goal is ambiguous because evaluating it overflowed.
+    pub overflow: bool,
+    /// The canonical form of the ambiguous goal.
+    pub goal: String,
+    /// The candidates which may apply to the goal, together with
+    /// the span of their definition, if there is one.
+    pub candidate_sources: Vec<(String, Option<Span>)>,
+    /// The inference variables which are still unconstrained in the goals causing
+    /// the ambiguity, together with the span they've been created for.
+    pub blocking_infer_vars: Vec<(String, Span)>,
+}
+
 /// Emitter trait for emitting errors.
 pub trait Emitter: Translate {
     /// Emit a structured diagnostic.
@@ -191,6 +208,10 @@ fn emit_artifact_notification(&mut self, _path: &Path, _artifact_type: &str) {}
     /// Currently only supported for the JSON format.
     fn emit_future_breakage_report(&mut self, _diags: Vec<DiagInner>) {}
 
+    /// Emit a report about an ambiguity error of the trait solver.
+    /// Currently only supported for the JSON format.
+    fn emit_ambiguity_report(&mut self, _report: &AmbiguityReport) {}
+
     /// Emit list of unused externs.
     /// Currently only supported for the JSON format.
     fn emit_unused_externs(
@@ -10,7 +10,7 @@
 // FIXME: spec the JSON output properly.
 
 use crate::emitter::{
-    should_show_source_code, ColorConfig, Destination, Emitter, HumanEmitter,
+    should_show_source_code, AmbiguityReport, ColorConfig, Destination, Emitter, HumanEmitter,
     HumanReadableErrorType,
 };
 use crate::registry::Registry;
@@ -102,6 +102,7 @@ enum EmitTyped<'a> {
     Artifact(ArtifactNotification<'a>),
     FutureIncompat(FutureIncompatReport<'a>),
     UnusedExtern(UnusedExterns<'a>),
+    AmbiguityReport(AmbiguityReportData<'a>),
 }
 
 impl Translate for JsonEmitter {
@@ -161,6 +162,34 @@ fn emit_unused_externs(&mut self, lint_level: rustc_lint_defs::Level, unused_ext
         }
     }
 
+    fn emit_ambiguity_report(&mut self, report: &AmbiguityReport) {
+        let span = |span, is_primary| {
+            DiagnosticSpan::from_span_etc(span, is_primary, None, None, &*self)
+        };
+        let data = AmbiguityReportData {
+            span: span(report.span, true),
+            overflow: report.overflow,
+            goal: &report.goal,
+            candidate_sources: report
+                .candidate_sources
+                .iter()
+                .map(|(source, def_span)| AmbiguityCandidate {
+                    source,
+                    span: def_span.map(|def_span| span(def_span, false)),
+                })
+                .collect(),
+            blocking_infer_vars: report
+                .blocking_infer_vars
+                .iter()
+                .map(|(var, origin)| BlockingInferVar { var, span: span(*origin, false) })
+                .collect(),
+        };
+        let result = self.emit(EmitTyped::AmbiguityReport(data));
+        if let Err(e) = result {
+            panic!("failed to print ambiguity report: {e:?}");
+        }
+    }
+
     fn source_map(&self) -> Option<&Lrc<SourceMap>> {
         Some(&self.sm)
     }
@@ -278,6 +307,36 @@ struct UnusedExterns<'a> {
     unused_extern_names: &'a [&'a str],
 }
 
+#[derive(Serialize)]
+struct AmbiguityReportData<'a> {
+    /// The span of the ambiguous obligation.
+    span: DiagnosticSpan,
+    /// Whether the ambiguity is caused by overflow.
+    overflow: bool,
+    /// The canonical form of the ambiguous goal.
+    goal: &'a str,
+    /// The candidates which may apply to the goal.
+    candidate_sources: Vec<AmbiguityCandidate<'a>>,
+    /// The inference variables which have to be constrained for the goal to make progress.
+    blocking_infer_vars: Vec<BlockingInferVar<'a>>,
+}
+
+#[derive(Serialize)]
+struct AmbiguityCandidate<'a> {
+    /// A description of the candidate, e.g. the impl it uses.
+    source: &'a str,
+    /// Where the candidate has been defined, if anywhere.
+    span: Option<DiagnosticSpan>,
+}
+
+#[derive(Serialize)]
+struct BlockingInferVar<'a> {
+    /// The inference variable, e.g. `?0t`.
+    var: &'a str,
+    /// The span the inference variable has been created for.
+    span: DiagnosticSpan,
+}
+
 impl Diagnostic {
     /// Converts from `rustc_errors::DiagInner` to `Diagnostic`.
     fn from_errors_diagnostic(diag: crate::DiagInner, je: &JsonEmitter) -> Diagnostic {
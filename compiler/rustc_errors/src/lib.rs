@@ -51,7 +51,7 @@
 // See https://github.com/rust-lang/rust/pull/115393.
 pub use termcolor::{Color, ColorSpec, WriteColor};
 
-use emitter::{is_case_difference, DynEmitter, Emitter};
+use emitter::{is_case_difference, AmbiguityReport, DynEmitter, Emitter};
 use registry::Registry;
 use rustc_data_structures::fx::{FxHashSet, FxIndexMap, FxIndexSet};
 use rustc_data_structures::stable_hasher::{Hash128, StableHasher};
@@ -518,6 +518,9 @@ pub enum StashKey {
     /// Query cycle detected, stashing in favor of a better error.
     Cycle,
     UndeterminedMacroResolution,
+    /// A fulfillment error collected by an early pass, which a later, more precise
+    /// check may enrich or supersede. See `TyCtxt::stashed_fulfillment_errors`.
+    FulfillmentError,
 }
 
 fn default_track_diagnostic<R>(diag: DiagInner, f: &mut dyn FnMut(DiagInner) -> R) -> R {
@@ -1008,6 +1011,10 @@ pub fn emit_artifact_notification(&self, path: &Path, artifact_type: &str) {
         self.inner.borrow_mut().emitter.emit_artifact_notification(path, artifact_type);
     }
 
+    pub fn emit_ambiguity_report(&self, report: &AmbiguityReport) {
+        self.inner.borrow_mut().emitter.emit_ambiguity_report(report);
+    }
+
     pub fn emit_future_breakage_report(&self) {
         let mut inner = self.inner.borrow_mut();
         let diags = std::mem::take(&mut inner.future_breakage_diagnostics);
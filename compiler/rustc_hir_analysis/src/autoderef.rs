@@ -24,6 +24,10 @@ struct AutoderefSnapshot<'tcx> {
     steps: Vec<(Ty<'tcx>, AutoderefKind)>,
     cur_ty: Ty<'tcx>,
     obligations: Vec<traits::PredicateObligation<'tcx>>,
+    /// For each of the `obligations` which has been registered while normalizing
+    /// the `Deref::Target` of an overloaded deref, its index together with the
+    /// `<ty as Deref>` obligation of that step.
+    parents: Vec<(usize, traits::PredicateObligation<'tcx>)>,
 }
 
 pub struct Autoderef<'a, 'tcx> {
@@ -121,6 +125,7 @@ pub fn new(
                 steps: vec![],
                 cur_ty: infcx.resolve_vars_if_possible(base_ty),
                 obligations: vec![],
+                parents: vec![],
                 at_start: true,
                 reached_recursion_limit: false,
             },
@@ -157,7 +162,10 @@ fn overloaded_deref_ty(&mut self, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
             [ty],
         ))?;
         debug!("overloaded_deref_ty({:?}) = ({:?}, {:?})", ty, normalized_ty, obligations);
-        self.state.obligations.extend(obligations);
+        for nested in obligations {
+            self.state.parents.push((self.state.obligations.len(), obligation.clone()));
+            self.state.obligations.push(nested);
+        }
 
         Some(self.infcx.resolve_vars_if_possible(normalized_ty))
     }
@@ -209,6 +217,24 @@ pub fn into_obligations(self) -> Vec<traits::PredicateObligation<'tcx>> {
         self.state.obligations
     }
 
+    /// Like [`Autoderef::into_obligations`], but also returns the `<ty as Deref>`
+    /// obligation of the overloaded deref step which caused each obligation to be
+    /// registered, if any.
+    pub fn into_obligations_with_parents(
+        self,
+    ) -> Vec<(traits::PredicateObligation<'tcx>, Option<traits::PredicateObligation<'tcx>>)> {
+        let mut parents = self.state.parents.into_iter().peekable();
+        self.state
+            .obligations
+            .into_iter()
+            .enumerate()
+            .map(|(i, obligation)| {
+                let parent = parents.next_if(|&(idx, _)| idx == i).map(|(_, parent)| parent);
+                (obligation, parent)
+            })
+            .collect()
+    }
+
     pub fn current_obligations(&self) -> Vec<traits::PredicateObligation<'tcx>> {
         self.state.obligations.clone()
     }
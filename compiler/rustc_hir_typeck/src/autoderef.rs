@@ -24,6 +24,18 @@ pub fn try_overloaded_deref(
         self.try_overloaded_place_op(span, base_ty, &[], PlaceOp::Deref)
     }
 
+    /// Registers the obligations of `autoderef`. Obligations which have been registered
+    /// while normalizing the target of an overloaded deref are nested under the
+    /// `Deref` obligation of that step, so errors for them point to the deref.
+    pub fn register_autoderef_obligations(&self, autoderef: Autoderef<'a, 'tcx>) {
+        for (obligation, parent) in autoderef.into_obligations_with_parents() {
+            match parent {
+                Some(parent) => self.register_nested_predicate(obligation, &parent),
+                None => self.register_predicate(obligation),
+            }
+        }
+    }
+
     /// Returns the adjustment steps.
     pub fn adjust_steps(&self, autoderef: &Autoderef<'a, 'tcx>) -> Vec<Adjustment<'tcx>> {
         self.register_infer_ok_obligations(self.adjust_steps_as_infer_ok(autoderef))
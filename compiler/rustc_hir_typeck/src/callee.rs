@@ -24,6 +24,7 @@
 use rustc_span::Span;
 use rustc_target::spec::abi;
 use rustc_trait_selection::infer::InferCtxtExt as _;
+use rustc_trait_selection::solve::InferCtxtSelectExt as _;
 use rustc_trait_selection::traits::error_reporting::DefIdOrName;
 use rustc_trait_selection::traits::query::evaluate_obligation::InferCtxtExt as _;
 
@@ -90,7 +91,7 @@ pub fn check_call(
         while result.is_none() && autoderef.next().is_some() {
             result = self.try_overloaded_call_step(call_expr, callee_expr, arg_exprs, &autoderef);
         }
-        self.register_predicates(autoderef.into_obligations());
+        self.register_autoderef_obligations(autoderef);
 
         let output = match result {
             None => {
@@ -461,13 +462,25 @@ fn confirm_builtin_call(
                             predicate,
                         );
                         let result = self.evaluate_obligation(&obligation);
-                        self.dcx()
+                        let mut err = self
+                            .dcx()
                             .struct_span_err(
                                 callee_expr.span,
                                 format!("evaluate({predicate:?}) = {result:?}"),
                             )
-                            .with_span_label(predicate_span, "predicate")
-                            .emit();
+                            .with_span_label(predicate_span, "predicate");
+                        // With the new solver, also explain how the candidates were selected.
+                        if self.next_trait_solver()
+                            && let Some(trait_pred) = predicate.as_trait_clause()
+                        {
+                            let obligation = obligation.with(self.tcx, trait_pred);
+                            let explanations =
+                                self.explain_selection_in_new_trait_solver(&obligation);
+                            for explanation in explanations {
+                                err.note(explanation.describe(self.tcx));
+                            }
+                        }
+                        err.emit();
                     }
                 }
                 (fn_sig, Some(def_id))
@@ -572,19 +585,15 @@ fn confirm_builtin_call(
                             ),
                         ));
 
-                        self.register_predicate(traits::Obligation::new(
-                            self.tcx,
+                        self.register_projection_equality(
+                            ty::AliasTy::new(
+                                self.tcx,
+                                fn_once_output_def_id,
+                                [arg_ty.into(), fn_sig.inputs()[0].into(), const_param],
+                            ),
+                            fn_sig.output().into(),
                             self.misc(span),
-                            self.param_env,
-                            ty::ProjectionPredicate {
-                                projection_ty: ty::AliasTy::new(
-                                    self.tcx,
-                                    fn_once_output_def_id,
-                                    [arg_ty.into(), fn_sig.inputs()[0].into(), const_param],
-                                ),
-                                term: fn_sig.output().into(),
-                            },
-                        ));
+                        );
 
                         self.select_obligations_where_possible(|_| {});
                     } else if idx == 0 && !self.tcx.is_const_fn_raw(def_id) {
@@ -735,7 +735,7 @@ fn merge_supplied_sig_with_expectation(
                 expected_sigs.liberated_sig.inputs(), // `liberated_sig` is E'.
             ) {
                 // Check that E' = S'.
-                let cause = self.misc(hir_ty.span);
+                let cause = self.cause(hir_ty.span, traits::ClosureSignature(expr_def_id));
                 let InferOk { value: (), obligations } = self.at(&cause, self.param_env).eq(
                     DefineOpaqueTypes::Yes,
                     *expected_ty,
@@ -749,7 +749,7 @@ fn merge_supplied_sig_with_expectation(
                 BoundRegionConversionTime::FnCall,
                 supplied_sig.output(),
             );
-            let cause = &self.misc(decl.output.span());
+            let cause = &self.cause(decl.output.span(), traits::ClosureSignature(expr_def_id));
             let InferOk { value: (), obligations } = self.at(cause, self.param_env).eq(
                 DefineOpaqueTypes::Yes,
                 expected_sigs.liberated_sig.output(),
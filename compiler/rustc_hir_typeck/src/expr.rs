@@ -2358,7 +2358,7 @@ fn check_field(
                             let adjustments = self.adjust_steps(&autoderef);
                             if field.vis.is_accessible_from(def_scope, self.tcx) {
                                 self.apply_adjustments(base, adjustments);
-                                self.register_predicates(autoderef.into_obligations());
+                                self.register_autoderef_obligations(autoderef);
 
                                 self.tcx.check_stability(
                                     field.did,
@@ -2381,7 +2381,7 @@ fn check_field(
                             if let Some(&field_ty) = tys.get(index) {
                                 let adjustments = self.adjust_steps(&autoderef);
                                 self.apply_adjustments(base, adjustments);
-                                self.register_predicates(autoderef.into_obligations());
+                                self.register_autoderef_obligations(autoderef);
 
                                 self.write_field_index(
                                     expr.hir_id,
@@ -436,6 +436,15 @@ fn calculate_diverging_fallback(
                         // compiler/rustc_trait_selection/src/traits/relationships.rs.
                         debug!("fallback to () - found trait and projection: {:?}", diverging_vid);
                         fallback_to(self.tcx.types.unit);
+                    } else if found_infer_var_info.output
+                        && self.next_trait_solver()
+                        && self.fallback_to_unit_resolves_goals(diverging_ty)
+                    {
+                        // The new solver often stalls goals on nested inference variables,
+                        // so `self_in_trait` is incomplete. We instead ask the fulfillment
+                        // context whether falling back to `()` resolves any pending goal.
+                        debug!("fallback to () - resolves pending goals: {:?}", diverging_vid);
+                        fallback_to(self.tcx.types.unit);
                     } else if can_reach_non_diverging {
                         debug!("fallback to () - reached non-diverging: {:?}", diverging_vid);
                         fallback_to(self.tcx.types.unit);
@@ -463,6 +472,27 @@ fn calculate_diverging_fallback(
         diverging_fallback
     }
 
+    /// Whether falling back to `()` causes a pending goal to hold which does not hold
+    /// when falling back to `!`, e.g. `?D: Trait` if `Trait` is only implemented for `()`.
+    fn fallback_to_unit_resolves_goals(&self, diverging_ty: Ty<'tcx>) -> bool {
+        let fulfillment_cx = self.fulfillment_cx.borrow();
+        let resolved_by_unit = fulfillment_cx.obligations_resolved_by_fallback(
+            self,
+            diverging_ty,
+            self.tcx.types.unit,
+        );
+        if resolved_by_unit.is_empty() {
+            return false;
+        }
+
+        let resolved_by_never = fulfillment_cx.obligations_resolved_by_fallback(
+            self,
+            diverging_ty,
+            self.tcx.types.never,
+        );
+        resolved_by_unit.iter().any(|obligation| !resolved_by_never.contains(obligation))
+    }
+
     /// Returns a graph whose nodes are (unresolved) inference variables and where
     /// an edge `?A -> ?B` indicates that the variable `?A` is coerced to `?B`.
     fn create_coercion_graph(&self) -> VecGraph<ty::TyVid, true> {
@@ -20,7 +20,7 @@
 };
 use rustc_infer::infer::canonical::{Canonical, OriginalQueryValues, QueryResponse};
 use rustc_infer::infer::error_reporting::TypeAnnotationNeeded::E0282;
-use rustc_infer::infer::{DefineOpaqueTypes, InferResult};
+use rustc_infer::infer::{DefineOpaqueTypes, InferResult, TyOrConstInferVar};
 use rustc_middle::ty::adjustment::{Adjust, Adjustment, AutoBorrow, AutoBorrowMutability};
 use rustc_middle::ty::error::TypeError;
 use rustc_middle::ty::fold::TypeFoldable;
@@ -407,6 +407,23 @@ pub fn register_bound(
         }
     }
 
+    pub fn register_projection_equality(
+        &self,
+        alias: ty::AliasTy<'tcx>,
+        term: ty::Term<'tcx>,
+        cause: traits::ObligationCause<'tcx>,
+    ) {
+        if !term.references_error() {
+            self.fulfillment_cx.borrow_mut().register_projection_equality(
+                self,
+                self.param_env,
+                alias,
+                term,
+                cause,
+            );
+        }
+    }
+
     pub fn lower_ty(&self, hir_ty: &hir::Ty<'tcx>) -> LoweredTy<'tcx> {
         let ty = self.lowerer().lower_ty(hir_ty);
         self.register_wf_obligation(ty.into(), hir_ty.span, traits::WellFormed(None));
@@ -576,8 +593,9 @@ pub(crate) fn resolve_coroutine_interiors(&self) {
             let ty::Infer(ty::InferTy::TyVar(_)) = interior.kind() else {
                 span_bug!(span, "coroutine interior witness not infer: {:?}", interior.kind())
             };
+            let cause = self.cause(span, ObligationCauseCode::CoroutineInterior(expr_def_id));
             let ok = self
-                .at(&self.misc(span), self.param_env)
+                .at(&cause, self.param_env)
                 // Will never define opaque types, as all we do is instantiate a type variable.
                 .eq(DefineOpaqueTypes::Yes, interior, witness)
                 .expect("Failed to unify coroutine interior type");
@@ -1447,6 +1465,15 @@ pub fn try_structurally_resolve_type(&self, sp: Span, ty: Ty<'tcx>) -> Ty<'tcx>
         if self.next_trait_solver()
             && let ty::Alias(..) = ty.kind()
         {
+            let cache_key = (self.param_env, ty);
+            let cached = self.structurally_normalized_types.borrow().get(&cache_key).cloned();
+            if let Some((normalized_ty, stalled_on)) = cached {
+                if !stalled_on.iter().any(|&var| self.ty_or_const_infer_var_changed(var)) {
+                    return normalized_ty;
+                }
+                self.structurally_normalized_types.borrow_mut().remove(&cache_key);
+            }
+
             // We need to use a separate variable here as otherwise the temporary for
             // `self.fulfillment_cx.borrow_mut()` is alive in the `Err` branch, resulting
             // in a reentrant borrow, causing an ICE.
@@ -1454,7 +1481,21 @@ pub fn try_structurally_resolve_type(&self, sp: Span, ty: Ty<'tcx>) -> Ty<'tcx>
                 .at(&self.misc(sp), self.param_env)
                 .structurally_normalize(ty, &mut **self.fulfillment_cx.borrow_mut());
             match result {
-                Ok(normalized_ty) => normalized_ty,
+                Ok(normalized_ty) => {
+                    // Inference variables created inside of a snapshot may get
+                    // rolled back, so we must not cache results mentioning them.
+                    if self.num_open_snapshots() == 0 {
+                        let stalled_on = self
+                            .resolve_vars_if_possible(normalized_ty)
+                            .walk()
+                            .filter_map(TyOrConstInferVar::maybe_from_generic_arg)
+                            .collect();
+                        self.structurally_normalized_types
+                            .borrow_mut()
+                            .insert(cache_key, (normalized_ty, stalled_on));
+                    }
+                    normalized_ty
+                }
                 Err(errors) => {
                     let guar = self.err_ctxt().report_fulfillment_errors(errors);
                     return Ty::new_error(self.tcx, guar);
@@ -114,7 +114,7 @@ fn config(&self) -> InspectConfig {
         // Using an intentionally low depth to minimize the chance of future
         // breaking changes in case we adapt the approach later on. This also
         // avoids any hangs for exponentially growing proof trees.
-        InspectConfig { max_depth: 5 }
+        InspectConfig { max_depth: 5, ..Default::default() }
     }
 
     fn visit_goal(&mut self, inspect_goal: &InspectGoal<'_, 'tcx>) {
@@ -1702,7 +1702,8 @@ pub(crate) fn note_type_is_not_clone(
                     }
                     for error in errors {
                         if let traits::FulfillmentErrorCode::SelectionError(
-                            traits::SelectionError::Unimplemented,
+                            traits::SelectionError::Unimplemented
+                            | traits::SelectionError::ImplObligationFailed { .. },
                         ) = error.code
                             && let ty::PredicateKind::Clause(ty::ClauseKind::Trait(pred)) =
                                 error.obligation.predicate.kind().skip_binder()
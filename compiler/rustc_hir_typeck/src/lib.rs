@@ -88,6 +88,10 @@ fn used_trait_imports(tcx: TyCtxt<'_>, def_id: LocalDefId) -> &UnordSet<LocalDef
     &tcx.typeck(def_id).used_trait_imports
 }
 
+fn used_param_env_clauses(tcx: TyCtxt<'_>, def_id: LocalDefId) -> &UnordSet<usize> {
+    &tcx.typeck(def_id).used_param_env_clauses
+}
+
 fn typeck<'tcx>(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> &ty::TypeckResults<'tcx> {
     let fallback = move || tcx.type_of(def_id.to_def_id()).instantiate_identity();
     typeck_with_fallback(tcx, def_id, fallback, None)
@@ -414,5 +418,11 @@ fn fatally_break_rust(tcx: TyCtxt<'_>, span: Span) -> ! {
 
 pub fn provide(providers: &mut Providers) {
     method::provide(providers);
-    *providers = Providers { typeck, diagnostic_only_typeck, used_trait_imports, ..*providers };
+    *providers = Providers {
+        typeck,
+        diagnostic_only_typeck,
+        used_trait_imports,
+        used_param_env_clauses,
+        ..*providers
+    };
 }
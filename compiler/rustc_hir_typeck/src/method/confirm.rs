@@ -232,7 +232,7 @@ fn adjust_self_ty(
             None => {}
         }
 
-        self.register_predicates(autoderef.into_obligations());
+        self.register_autoderef_obligations(autoderef);
 
         // Write out the final adjustments.
         if !self.skip_record_for_diagnostics {
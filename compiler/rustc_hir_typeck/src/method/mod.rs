@@ -48,8 +48,9 @@ pub enum MethodError<'tcx> {
     // Did not find an applicable method, but we did find various near-misses that may work.
     NoMatch(NoMatchData<'tcx>),
 
-    // Multiple methods might apply.
-    Ambiguity(Vec<CandidateSource>),
+    // Multiple methods might apply. With the new solver, the second argument contains the goals
+    // which keep some of these candidates from definitely applying.
+    Ambiguity(Vec<CandidateSource>, Vec<(CandidateSource, ty::Predicate<'tcx>)>),
 
     // Found an applicable method, but it is not visible. The third argument contains a list of
     // not-in-scope traits which may work.
@@ -227,7 +228,7 @@ pub fn lookup_method(
                 Ok(ref new_pick) if pick.differs_from(new_pick) => {
                     vec![new_pick.item.container_id(self.tcx)]
                 }
-                Err(Ambiguity(ref sources)) => sources
+                Err(Ambiguity(ref sources, _)) => sources
                     .iter()
                     .filter_map(|source| {
                         match *source {
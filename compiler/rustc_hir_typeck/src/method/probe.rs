@@ -31,6 +31,7 @@
 };
 use rustc_span::symbol::sym;
 use rustc_span::{symbol::Ident, Span, Symbol, DUMMY_SP};
+use rustc_trait_selection::solve::inspect::ProofTreeInferCtxtExt;
 use rustc_trait_selection::traits::query::evaluate_obligation::InferCtxtExt;
 use rustc_trait_selection::traits::query::method_autoderef::MethodAutoderefBadTy;
 use rustc_trait_selection::traits::query::method_autoderef::{
@@ -117,6 +118,11 @@ enum ProbeResult {
     NoMatch,
     BadReturnType,
     Match,
+    /// The candidate may apply, but some of its obligations are still
+    /// ambiguous. This is only used with the new solver. Candidates which
+    /// definitely apply are preferred over ambiguous ones, which are
+    /// otherwise treated like `Match`.
+    Ambiguous,
 }
 
 /// When adjusting a receiver we often want to do one of
@@ -958,7 +964,7 @@ fn pick(mut self) -> PickResult<'tcx> {
 
         let out_of_scope_traits = match self.pick_core() {
             Some(Ok(p)) => vec![p.item.container_id(self.tcx)],
-            Some(Err(MethodError::Ambiguity(v))) => v
+            Some(Err(MethodError::Ambiguity(v, _))) => v
                 .into_iter()
                 .map(|source| match source {
                     CandidateSource::Trait(id) => id,
@@ -1177,13 +1183,21 @@ fn consider_candidates(
         let mut applicable_candidates: Vec<_> = candidates
             .iter()
             .map(|probe| {
-                (probe, self.consider_probe(self_ty, probe, possibly_unsatisfied_predicates))
+                (probe, self.consider_probe(self_ty, probe, possibly_unsatisfied_predicates, None))
             })
             .filter(|&(_, status)| status != ProbeResult::NoMatch)
             .collect();
 
         debug!("applicable_candidates: {:?}", applicable_candidates);
 
+        // Prefer the candidates which definitely apply over the ones which only
+        // may apply, as the latter are likely to not apply once we know more.
+        if applicable_candidates.len() > 1
+            && applicable_candidates.iter().any(|&(_, status)| status == ProbeResult::Match)
+        {
+            applicable_candidates.retain(|&(_, status)| status != ProbeResult::Ambiguous);
+        }
+
         if applicable_candidates.len() > 1 {
             if let Some(pick) =
                 self.collapse_candidates_to_trait_pick(self_ty, &applicable_candidates)
@@ -1206,17 +1220,49 @@ fn consider_candidates(
 
         if applicable_candidates.len() > 1 {
             let sources = candidates.iter().map(|p| self.candidate_source(p, self_ty)).collect();
-            return Some(Err(MethodError::Ambiguity(sources)));
+            let blocking_predicates =
+                self.blocking_predicates_of_ambiguous_candidates(self_ty, &applicable_candidates);
+            return Some(Err(MethodError::Ambiguity(sources, blocking_predicates)));
         }
 
         applicable_candidates.pop().map(|(probe, status)| match status {
-            ProbeResult::Match => {
+            ProbeResult::Match | ProbeResult::Ambiguous => {
                 Ok(probe
                     .to_unadjusted_pick(self_ty, unstable_candidates.cloned().unwrap_or_default()))
             }
             ProbeResult::NoMatch | ProbeResult::BadReturnType => Err(MethodError::BadReturnType),
         })
     }
+
+    /// Returns the goals which keep the ambiguous candidates in `applicable_candidates`
+    /// from definitely applying. This is only used to explain ambiguity errors, so we
+    /// only compute these goals by probing the candidates again once we know that
+    /// we're going to emit an error.
+    fn blocking_predicates_of_ambiguous_candidates(
+        &self,
+        self_ty: Ty<'tcx>,
+        applicable_candidates: &[(&Candidate<'tcx>, ProbeResult)],
+    ) -> Vec<(CandidateSource, ty::Predicate<'tcx>)> {
+        let mut blocking_predicates = vec![];
+        for &(candidate, status) in applicable_candidates {
+            if status != ProbeResult::Ambiguous {
+                continue;
+            }
+
+            let mut candidate_blocking_predicates = vec![];
+            self.consider_probe(
+                self_ty,
+                candidate,
+                &mut vec![],
+                Some(&mut candidate_blocking_predicates),
+            );
+            let source = self.candidate_source(candidate, self_ty);
+            blocking_predicates.extend(
+                candidate_blocking_predicates.into_iter().map(|predicate| (source, predicate)),
+            );
+        }
+        blocking_predicates
+    }
 }
 
 impl<'tcx> Pick<'tcx> {
@@ -1356,6 +1402,7 @@ fn consider_probe(
             Option<ty::Predicate<'tcx>>,
             Option<ObligationCause<'tcx>>,
         )>,
+        blocking_predicates: Option<&mut Vec<ty::Predicate<'tcx>>>,
     ) -> ProbeResult {
         debug!("consider_probe: self_ty={:?} probe={:?}", self_ty, probe);
 
@@ -1567,6 +1614,30 @@ fn consider_probe(
                 result = ProbeResult::NoMatch;
             }
 
+            // With the new solver, the obligations which are still pending at this
+            // point are ambiguous. We still consider the candidate to be applicable,
+            // but remember that it may not apply when reporting ambiguity errors.
+            if let ProbeResult::Match = result
+                && self.next_trait_solver()
+            {
+                let pending_obligations = ocx.into_pending_obligations();
+                if !pending_obligations.is_empty() {
+                    result = ProbeResult::Ambiguous;
+                }
+
+                if let Some(blocking_predicates) = blocking_predicates {
+                    for obligation in pending_obligations {
+                        let (_, blocking_goals) = self
+                            .evaluate_goal_with_blocking_goals(obligation.into(), self.span);
+                        blocking_predicates.extend(
+                            blocking_goals
+                                .into_iter()
+                                .map(|goal| self.resolve_vars_if_possible(goal.predicate)),
+                        );
+                    }
+                }
+            }
+
             result
         })
     }
@@ -225,7 +225,7 @@ pub fn report_method_error(
                 );
             }
 
-            MethodError::Ambiguity(mut sources) => {
+            MethodError::Ambiguity(mut sources, blocking_predicates) => {
                 let mut err = struct_span_code_err!(
                     self.dcx(),
                     item_name.span,
@@ -244,6 +244,7 @@ pub fn report_method_error(
                     &mut sources,
                     Some(sugg_span),
                 );
+                self.note_blocking_predicates_on_ambiguity(&mut err, &sources, blocking_predicates);
                 err.emit();
             }
 
@@ -1573,6 +1574,28 @@ pub(crate) fn confusable_method_name(
         }
         None
     }
+
+    /// Explains why candidates which the new solver considers to be ambiguous may not
+    /// actually apply. `sources` has to be sorted the same way as the candidate notes.
+    fn note_blocking_predicates_on_ambiguity(
+        &self,
+        err: &mut Diag<'_>,
+        sources: &[CandidateSource],
+        blocking_predicates: Vec<(CandidateSource, ty::Predicate<'tcx>)>,
+    ) {
+        let mut noted = FxIndexSet::default();
+        for (source, predicate) in blocking_predicates {
+            let Some(idx) = sources.iter().position(|s| *s == source) else { continue };
+            if noted.insert((idx, predicate)) {
+                err.note(format!(
+                    "candidate #{} may not apply because it is not yet known whether `{}` holds",
+                    idx + 1,
+                    predicate,
+                ));
+            }
+        }
+    }
+
     fn note_candidates_on_method_error(
         &self,
         rcvr_ty: Ty<'tcx>,
@@ -3006,7 +3029,7 @@ fn suggest_traits_to_import(
                         }
                         break;
                     }
-                    Err(MethodError::Ambiguity(_)) => {
+                    Err(MethodError::Ambiguity(..)) => {
                         // If the method is defined (but ambiguous) for the receiver we have, it is also
                         // likely we haven't `use`d it. It may be possible that if we `Box`/`Pin`/etc.
                         // the receiver, then it might disambiguate this method, but I think these
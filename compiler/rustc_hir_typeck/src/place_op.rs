@@ -60,7 +60,7 @@ pub(super) fn lookup_indexing(
         while result.is_none() && autoderef.next().is_some() {
             result = self.try_index_step(expr, base_expr, &autoderef, idx_ty, index_expr);
         }
-        self.register_predicates(autoderef.into_obligations());
+        self.register_autoderef_obligations(autoderef);
         result
     }
 
@@ -4,7 +4,7 @@
 use rustc_hir as hir;
 use rustc_hir::def_id::LocalDefId;
 use rustc_hir::{HirId, HirIdMap};
-use rustc_infer::infer::{InferCtxt, InferOk, TyCtxtInferExt};
+use rustc_infer::infer::{InferCtxt, InferOk, TyCtxtInferExt, TyOrConstInferVar};
 use rustc_middle::ty::visit::TypeVisitableExt;
 use rustc_middle::ty::{self, Ty, TyCtxt};
 use rustc_span::def_id::LocalDefIdMap;
@@ -64,6 +64,17 @@ pub(crate) struct TypeckRootCtxt<'tcx> {
     pub(super) diverging_type_vars: RefCell<UnordSet<Ty<'tcx>>>,
 
     pub(super) infer_var_info: RefCell<UnordMap<ty::TyVid, ty::InferVarInfo>>,
+
+    /// Caches the result of structurally normalizing aliases with the new solver,
+    /// see `FnCtxt::try_structurally_resolve_type`.
+    ///
+    /// The aliases are resolved before looking them up, so resolving an inference
+    /// variable of an alias results in a different key. Each entry also stores the
+    /// inference variables of the normalized type. An entry is invalidated once one
+    /// of these variables gets resolved, as normalizing the alias again may then
+    /// result in a more structurally resolved type.
+    pub(super) structurally_normalized_types:
+        RefCell<UnordMap<(ty::ParamEnv<'tcx>, Ty<'tcx>), (Ty<'tcx>, Vec<TyOrConstInferVar>)>>,
 }
 
 impl<'tcx> Deref for TypeckRootCtxt<'tcx> {
@@ -79,10 +90,17 @@ pub fn new(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> Self {
 
         let infcx = tcx.infer_ctxt().ignoring_regions().with_opaque_type_inference(def_id).build();
         let typeck_results = RefCell::new(ty::TypeckResults::new(hir_owner));
+        let mut fulfillment_cx = <dyn TraitEngine<'_>>::new(&infcx);
+        if tcx.sess.opts.unstable_opts.track_used_param_env_clauses {
+            fulfillment_cx.track_used_param_env_clauses();
+        }
+        if tcx.sess.opts.unstable_opts.solver_metadata_cache {
+            fulfillment_cx.track_proven_global_goals();
+        }
 
         TypeckRootCtxt {
             typeck_results,
-            fulfillment_cx: RefCell::new(<dyn TraitEngine<'_>>::new(&infcx)),
+            fulfillment_cx: RefCell::new(fulfillment_cx),
             infcx,
             locals: RefCell::new(Default::default()),
             deferred_sized_obligations: RefCell::new(Vec::new()),
@@ -93,6 +111,7 @@ pub fn new(tcx: TyCtxt<'tcx>, def_id: LocalDefId) -> Self {
             deferred_coroutine_interiors: RefCell::new(Vec::new()),
             diverging_type_vars: RefCell::new(Default::default()),
             infer_var_info: RefCell::new(Default::default()),
+            structurally_normalized_types: RefCell::new(Default::default()),
         }
     }
 
@@ -107,6 +126,27 @@ pub(super) fn register_predicate(&self, obligation: traits::PredicateObligation<
         self.fulfillment_cx.borrow_mut().register_predicate_obligation(self, obligation);
     }
 
+    /// Registers `obligation` as having been created while processing `parent`,
+    /// see `TraitEngine::register_nested_predicate_obligation`.
+    #[instrument(level = "debug", skip(self))]
+    pub(super) fn register_nested_predicate(
+        &self,
+        obligation: traits::PredicateObligation<'tcx>,
+        parent: &traits::PredicateObligation<'tcx>,
+    ) {
+        if obligation.has_escaping_bound_vars() {
+            span_bug!(obligation.cause.span, "escaping bound vars in predicate {:?}", obligation);
+        }
+
+        self.update_infer_var_info(&obligation);
+
+        self.fulfillment_cx.borrow_mut().register_nested_predicate_obligation(
+            self,
+            obligation,
+            parent,
+        );
+    }
+
     pub(super) fn register_predicates<I>(&self, obligations: I)
     where
         I: IntoIterator<Item = traits::PredicateObligation<'tcx>>,
@@ -79,6 +79,18 @@ pub fn resolve_type_vars_in_body(
         debug!("used_trait_imports({:?}) = {:?}", item_def_id, used_trait_imports);
         wbcx.typeck_results.used_trait_imports = used_trait_imports;
 
+        wbcx.typeck_results.used_param_env_clauses = self
+            .fulfillment_cx
+            .borrow()
+            .used_param_env_clauses()
+            .into_iter()
+            .filter(|(obligation, _)| obligation.param_env == self.param_env)
+            .flat_map(|(_, clauses)| clauses)
+            .collect();
+
+        wbcx.typeck_results.proven_global_goals =
+            self.fulfillment_cx.borrow().proven_global_goals();
+
         wbcx.typeck_results.treat_byte_string_as_slice =
             mem::take(&mut self.typeck_results.borrow_mut().treat_byte_string_as_slice);
 
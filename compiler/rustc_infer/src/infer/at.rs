@@ -90,6 +90,8 @@ pub fn fork_with_intercrate(&self, intercrate: bool) -> Self {
             intercrate,
             next_trait_solver: self.next_trait_solver,
             obligation_inspector: self.obligation_inspector.clone(),
+            overflow_inspector: self.overflow_inspector.clone(),
+            evaluation_timing_inspector: self.evaluation_timing_inspector.clone(),
         }
     }
 }
@@ -12,7 +12,9 @@
 pub use ValuePairs::*;
 
 use crate::traits::{
-    self, ObligationCause, ObligationInspector, PredicateObligations, TraitEngine, TraitEngineExt,
+    self, EvaluationTimingInspector, ObligationCause, ObligationInspector, OverflowInspector,
+    PredicateObligations,
+    TraitEngine, TraitEngineExt,
 };
 use error_reporting::TypeErrCtxt;
 use free_regions::RegionRelations;
@@ -333,6 +335,10 @@ pub struct InferCtxt<'tcx> {
     next_trait_solver: bool,
 
     pub obligation_inspector: Cell<Option<ObligationInspector<'tcx>>>,
+
+    pub overflow_inspector: Cell<Option<OverflowInspector<'tcx>>>,
+
+    pub evaluation_timing_inspector: Cell<Option<EvaluationTimingInspector<'tcx>>>,
 }
 
 impl<'tcx> ty::InferCtxtLike for InferCtxt<'tcx> {
@@ -720,6 +726,8 @@ pub fn build(&mut self) -> InferCtxt<'tcx> {
             intercrate,
             next_trait_solver,
             obligation_inspector: Cell::new(None),
+            overflow_inspector: Cell::new(None),
+            evaluation_timing_inspector: Cell::new(None),
         }
     }
 }
@@ -1699,6 +1707,26 @@ pub fn attach_obligation_inspector(&self, inspector: ObligationInspector<'tcx>)
         );
         self.obligation_inspector.set(Some(inspector));
     }
+
+    /// Attach a callback to be invoked on each root obligation which overflows
+    /// during fulfillment in the new trait solver.
+    pub fn attach_overflow_inspector(&self, inspector: OverflowInspector<'tcx>) {
+        debug_assert!(
+            self.overflow_inspector.get().is_none(),
+            "shouldn't override a set overflow inspector"
+        );
+        self.overflow_inspector.set(Some(inspector));
+    }
+
+    /// Attach a callback to be invoked with the wall-clock duration of each
+    /// root obligation evaluated during fulfillment in the new trait solver.
+    pub fn attach_evaluation_timing_inspector(&self, inspector: EvaluationTimingInspector<'tcx>) {
+        debug_assert!(
+            self.evaluation_timing_inspector.get().is_none(),
+            "shouldn't override a set evaluation timing inspector"
+        );
+        self.evaluation_timing_inspector.set(Some(inspector));
+    }
 }
 
 impl<'tcx> TypeErrCtxt<'_, 'tcx> {
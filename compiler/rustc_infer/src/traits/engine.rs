@@ -1,11 +1,14 @@
+use crate::infer::canonical::{CanonicalVarValues, QueryOutlivesConstraint};
 use crate::infer::InferCtxt;
 use crate::traits::Obligation;
 use rustc_hir::def_id::DefId;
 use rustc_macros::extension;
 use rustc_middle::ty::{self, ToPredicate, Ty};
+use rustc_span::Span;
 
+use super::solve::Goal;
 use super::FulfillmentError;
-use super::{ObligationCause, PredicateObligation};
+use super::{CanonicalObligations, ObligationCause, PredicateObligation};
 
 pub trait TraitEngine<'tcx>: 'tcx {
     /// Requires that `ty` must implement the trait with `def_id` in
@@ -31,19 +34,134 @@ fn register_bound(
         );
     }
 
+    /// Requires that the associated type `alias` is equal to `term`
+    /// in the given environment, e.g. `<T as Iterator>::Item == u32`.
+    fn register_projection_equality(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+        alias: ty::AliasTy<'tcx>,
+        term: ty::Term<'tcx>,
+        cause: ObligationCause<'tcx>,
+    ) {
+        let projection = ty::ProjectionPredicate { projection_ty: alias, term };
+        self.register_predicate_obligation(
+            infcx,
+            Obligation {
+                cause,
+                recursion_depth: 0,
+                param_env,
+                predicate: ty::Binder::dummy(projection).to_predicate(infcx.tcx),
+            },
+        );
+    }
+
     fn register_predicate_obligation(
         &mut self,
         infcx: &InferCtxt<'tcx>,
         obligation: PredicateObligation<'tcx>,
     );
 
+    /// Registers an obligation which has been created while processing `parent`,
+    /// e.g. when normalizing the predicate of `parent`. Fulfillment contexts may
+    /// use this to report a more meaningful `root_obligation` on errors.
+    fn register_nested_predicate_obligation(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: PredicateObligation<'tcx>,
+        _parent: &PredicateObligation<'tcx>,
+    ) {
+        self.register_predicate_obligation(infcx, obligation);
+    }
+
+    /// Registers goals which all share the same `cause`, e.g. the goals emitted
+    /// by a type relation. Unlike calling `register_predicate_obligation` for each
+    /// of them, this allows fulfillment contexts to avoid constructing an obligation
+    /// for each goal up front.
+    fn register_goals(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        cause: &ObligationCause<'tcx>,
+        goals: Vec<Goal<'tcx, ty::Predicate<'tcx>>>,
+    ) {
+        for goal in goals {
+            self.register_predicate_obligation(
+                infcx,
+                Obligation::new(infcx.tcx, cause.clone(), goal.param_env, goal.predicate),
+            );
+        }
+    }
+
     #[must_use]
     fn select_where_possible(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>>;
 
     fn collect_remaining_errors(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>>;
 
+    /// Returns the errors `collect_remaining_errors` would currently return
+    /// without removing the pending obligations from this context. This does
+    /// not constrain any inference variables.
+    fn peek_remaining_errors(&self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>>;
+
     fn pending_obligations(&self) -> Vec<PredicateObligation<'tcx>>;
 
+    /// Returns the outlives constraints which were added to the inference context
+    /// while proving the obligations registered in this context, each together with
+    /// the root obligation it is attributable to. These constraints are only checked
+    /// once regions are resolved.
+    ///
+    /// This is only tracked by the new solver and is intended for diagnostics.
+    /// It is empty unless enabled via `TraitEngine::track_outlives_constraints`.
+    fn outlives_constraints_by_root(
+        &self,
+    ) -> Vec<(PredicateObligation<'tcx>, QueryOutlivesConstraint<'tcx>)> {
+        vec![]
+    }
+
+    /// Enables tracking the outlives constraints of the obligations of this
+    /// context, see `TraitEngine::outlives_constraints_by_root`. This has to be
+    /// called before selecting any obligations.
+    fn track_outlives_constraints(&mut self) {}
+
+    /// Enables tracking the where-clauses of the param-env used to prove the
+    /// obligations of this context, see `TraitEngine::used_param_env_clauses`.
+    ///
+    /// This is only supported by the new solver and is intended for lints.
+    fn track_used_param_env_clauses(&mut self) {}
+
+    /// Returns each root obligation proven so far together with the indices into the
+    /// `caller_bounds` of its param-env which were used to prove it. This is empty
+    /// unless enabled via `TraitEngine::track_used_param_env_clauses`.
+    fn used_param_env_clauses(&self) -> Vec<(PredicateObligation<'tcx>, Vec<usize>)> {
+        vec![]
+    }
+
+    /// Enables tracking the fully concrete goals proven by this context which hold
+    /// without any constraints, see `TraitEngine::proven_global_goals`.
+    ///
+    /// This is only supported by the new solver and is used by `-Zsolver-metadata-cache`.
+    fn track_proven_global_goals(&mut self) {}
+
+    /// Returns the fully concrete goals proven by this context which have not already
+    /// been proven by an upstream crate, in the order in which they have been proven.
+    /// This is empty unless enabled via `TraitEngine::track_proven_global_goals`.
+    fn proven_global_goals(&self) -> Vec<ty::Predicate<'tcx>> {
+        vec![]
+    }
+
+    /// Returns the pending obligations which are stalled on the inference variable `var`
+    /// and would hold if it were to fall back to `fallback_ty`, e.g. `()` or `i32`. This
+    /// is used by type variable fallback and does not constrain any inference variables.
+    ///
+    /// This is only supported by the new solver.
+    fn obligations_resolved_by_fallback(
+        &self,
+        _infcx: &InferCtxt<'tcx>,
+        _var: Ty<'tcx>,
+        _fallback_ty: Ty<'tcx>,
+    ) -> Vec<PredicateObligation<'tcx>> {
+        vec![]
+    }
+
     /// Among all pending obligations, collect those are stalled on a inference variable which has
     /// changed since the last call to `select_where_possible`. Those obligations are marked as
     /// successful and returned.
@@ -74,4 +192,42 @@ fn select_all_or_error(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentErr
 
         self.collect_remaining_errors(infcx)
     }
+
+    /// Returns the pending obligations of this context in canonical form, so that
+    /// they can be moved to a fresh `InferCtxt` with the same `tcx` by using
+    /// `TraitEngineExt::register_canonical_obligations`. This resolves all inference
+    /// variables as far as possible, including the ones mentioned by the causes.
+    ///
+    /// This does not remove the obligations from this context, which should
+    /// therefore not be used afterwards.
+    fn canonical_pending_obligations(&self, infcx: &InferCtxt<'tcx>) -> CanonicalObligations<'tcx> {
+        let obligations = self
+            .pending_obligations()
+            .into_iter()
+            .map(|Obligation { cause, param_env, predicate, recursion_depth: _ }| {
+                (cause, Goal { param_env, predicate })
+            })
+            .collect();
+        infcx.canonicalize_response(obligations)
+    }
+
+    /// Registers obligations which have been taken from a different `InferCtxt` via
+    /// `TraitEngineExt::canonical_pending_obligations`, instantiating their canonical
+    /// variables with fresh inference variables of `infcx`. Returns these variables
+    /// so that callers can relate them to the types of the original context.
+    fn register_canonical_obligations(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        span: Span,
+        obligations: &CanonicalObligations<'tcx>,
+    ) -> CanonicalVarValues<'tcx> {
+        let (obligations, var_values) = infcx.instantiate_canonical(span, obligations);
+        for (cause, goal) in obligations {
+            self.register_predicate_obligation(
+                infcx,
+                Obligation::new(infcx.tcx, cause, goal.param_env, goal.predicate),
+            );
+        }
+        var_values
+    }
 }
@@ -10,17 +10,20 @@
 
 use std::cmp;
 use std::hash::{Hash, Hasher};
+use std::time::Duration;
 
 use hir::def_id::LocalDefId;
+use rustc_data_structures::sync::Lrc;
 use rustc_hir as hir;
 use rustc_middle::traits::query::NoSolution;
-use rustc_middle::traits::solve::Certainty;
+use rustc_middle::traits::solve::{inspect, AmbiguityCause, Certainty};
 use rustc_middle::ty::error::{ExpectedFound, TypeError};
 use rustc_middle::ty::{self, Const, ToPredicate, Ty, TyCtxt};
 use rustc_span::Span;
 
 pub use self::ImplSource::*;
 pub use self::SelectionError::*;
+use crate::infer::canonical::Canonical;
 use crate::infer::InferCtxt;
 
 pub use self::engine::{TraitEngine, TraitEngineExt};
@@ -117,6 +120,12 @@ pub fn derived_cause(
 
 pub type PredicateObligations<'tcx> = Vec<PredicateObligation<'tcx>>;
 
+/// Obligations in canonical form, used to move them between inference contexts,
+/// see `TraitEngineExt::canonical_pending_obligations`. Unlike the predicates of
+/// a `PredicateObligation`, the causes are canonicalized as well.
+pub type CanonicalObligations<'tcx> =
+    Canonical<'tcx, Vec<(ObligationCause<'tcx>, solve::Goal<'tcx, ty::Predicate<'tcx>>)>>;
+
 pub type Selection<'tcx> = ImplSource<'tcx, PredicateObligation<'tcx>>;
 
 /// A callback that can be provided to `inspect_typeck`. Invoked on evaluation
@@ -124,13 +133,60 @@ pub fn derived_cause(
 pub type ObligationInspector<'tcx> =
     fn(&InferCtxt<'tcx>, &PredicateObligation<'tcx>, Result<Certainty, NoSolution>);
 
+/// A callback invoked when the new trait solver gives up on a root obligation
+/// because evaluating it made progress the given number of times without proving it.
+pub type OverflowInspector<'tcx> = fn(&InferCtxt<'tcx>, &PredicateObligation<'tcx>, usize);
+
+/// A callback invoked with the result of each root obligation evaluated during
+/// fulfillment in the new trait solver, together with the wall-clock duration
+/// of that evaluation.
+pub type EvaluationTimingInspector<'tcx> =
+    fn(&InferCtxt<'tcx>, &PredicateObligation<'tcx>, Result<Certainty, NoSolution>, Duration);
+
 pub struct FulfillmentError<'tcx> {
     pub obligation: PredicateObligation<'tcx>,
     pub code: FulfillmentErrorCode<'tcx>,
     /// Diagnostics only: the 'root' obligation which resulted in
     /// the failure to process `obligation`. This is the obligation
-    /// that was initially passed to `register_predicate_obligation`
+    /// that was initially passed to `register_predicate_obligation`,
+    /// or, if it has been registered via `register_nested_predicate_obligation`,
+    /// the most relevant of its ancestors.
     pub root_obligation: PredicateObligation<'tcx>,
+    /// Diagnostics only: the proof tree of the registered obligation which
+    /// failed. This is only set by the new solver and allows error reporting
+    /// to inspect how that obligation failed without having to evaluate it again.
+    pub proof_tree: Option<Lrc<inspect::GoalEvaluation<'tcx>>>,
+    /// Diagnostics only: for ambiguity errors, the inference variables which
+    /// are still unconstrained in the goals causing the ambiguity. This is only
+    /// set by the new solver and is used to blame the right binding.
+    pub blocking_infer_vars: Vec<Ty<'tcx>>,
+    /// For ambiguity errors, the construct which caused the ambiguity. This is
+    /// only set by the new solver, e.g. to handle ambiguity caused by opaque types
+    /// whose hidden type has not been defined yet differently.
+    pub ambiguity_cause: Option<AmbiguityCause>,
+    /// Diagnostics only: the predicate of another error with the same root cause
+    /// which was returned together with this one. This error is redundant and is
+    /// not reported if that error gets reported. This is only set by the new solver.
+    pub redundant_with: Option<ty::Predicate<'tcx>>,
+    /// Diagnostics only: for overflow errors, the chain of nested goals which led
+    /// to the overflow. This is only set by the new solver.
+    pub overflow_chain: Option<OverflowChain<'tcx>>,
+    /// Diagnostics only: for overflow errors, whether we gave up as the goal was too
+    /// complex to solve within the step limit of `-Zsolver-step-limit`, instead of
+    /// exceeding the recursion limit. This is only set by the new solver.
+    pub reached_step_limit: bool,
+}
+
+/// The chain of nested goals which led to an overflow in the new solver,
+/// see `FulfillmentError::overflow_chain`.
+#[derive(Clone, Debug)]
+pub struct OverflowChain<'tcx> {
+    /// The goals on the path from the root goal towards the goal which overflowed,
+    /// starting with the root goal.
+    pub goals: Vec<ty::Predicate<'tcx>>,
+    /// Whether the path has been cut short, i.e. it continues after the last
+    /// goal in `goals`.
+    pub truncated: bool,
 }
 
 #[derive(Clone)]
@@ -204,7 +260,17 @@ pub fn new(
         code: FulfillmentErrorCode<'tcx>,
         root_obligation: PredicateObligation<'tcx>,
     ) -> FulfillmentError<'tcx> {
-        FulfillmentError { obligation, code, root_obligation }
+        FulfillmentError {
+            obligation,
+            code,
+            root_obligation,
+            proof_tree: None,
+            blocking_infer_vars: vec![],
+            ambiguity_cause: None,
+            redundant_with: None,
+            overflow_chain: None,
+            reached_step_limit: false,
+        }
     }
 
     pub fn is_true_error(&self) -> bool {
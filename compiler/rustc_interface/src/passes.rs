@@ -770,6 +770,8 @@ fn run_required_analyses(tcx: TyCtxt<'_>) {
 /// passes on the crate.
 fn analysis(tcx: TyCtxt<'_>, (): ()) -> Result<()> {
     run_required_analyses(tcx);
+    rustc_trait_selection::solve::goal_summary::emit_goal_summary(tcx);
+    rustc_trait_selection::solve::span_profile::emit_span_profile(tcx);
 
     let sess = tcx.sess;
 
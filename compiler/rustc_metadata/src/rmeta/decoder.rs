@@ -1490,6 +1490,13 @@ fn exported_symbols(
         tcx.arena.alloc_from_iter(self.root.exported_symbols.decode((self, tcx)))
     }
 
+    fn get_proven_global_goals_in_new_solver(
+        self,
+        tcx: TyCtxt<'tcx>,
+    ) -> &'tcx [ty::Predicate<'tcx>] {
+        tcx.arena.alloc_from_iter(self.root.proven_global_goals_in_new_solver.decode((self, tcx)))
+    }
+
     fn get_macro(self, id: DefIndex, sess: &Session) -> ast::MacroDef {
         match self.def_kind(id) {
             DefKind::Macro(_) => {
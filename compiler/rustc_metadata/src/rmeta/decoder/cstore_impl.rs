@@ -357,6 +357,7 @@ fn into_args(self) -> (DefId, SimplifiedType) {
 
     used_crate_source => { Lrc::clone(&cdata.source) }
     debugger_visualizers => { cdata.get_debugger_visualizers() }
+    proven_global_goals_in_new_solver => { cdata.get_proven_global_goals_in_new_solver(tcx) }
 
     exported_symbols => {
         let syms = cdata.exported_symbols(tcx);
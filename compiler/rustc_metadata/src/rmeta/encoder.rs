@@ -676,6 +676,9 @@ macro_rules! stat {
             self.encode_exported_symbols(tcx.exported_symbols(LOCAL_CRATE))
         });
 
+        let proven_global_goals_in_new_solver =
+            stat!("proven-global-goals", || self.encode_proven_global_goals_in_new_solver());
+
         // Encode the hygiene data.
         // IMPORTANT: this *must* be the last thing that we encode (other than `SourceMap`). The
         // process of encoding other items (e.g. `optimized_mir`) may cause us to load data from
@@ -733,6 +736,7 @@ macro_rules! stat {
                 impls,
                 incoherent_impls,
                 exported_symbols,
+                proven_global_goals_in_new_solver,
                 interpret_alloc_index,
                 tables,
                 syntax_contexts,
@@ -2097,6 +2101,14 @@ fn encode_exported_symbols(
         )
     }
 
+    fn encode_proven_global_goals_in_new_solver(&mut self) -> LazyArray<ty::Predicate<'static>> {
+        empty_proc_macro!(self);
+        if !self.tcx.sess.opts.unstable_opts.solver_metadata_cache {
+            return LazyArray::default();
+        }
+        self.lazy_array(self.tcx.proven_global_goals_in_new_solver(LOCAL_CRATE).iter())
+    }
+
     fn encode_dylib_dependency_formats(&mut self) -> LazyArray<Option<LinkagePreference>> {
         empty_proc_macro!(self);
         let formats = self.tcx.dependency_formats(());
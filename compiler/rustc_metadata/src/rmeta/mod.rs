@@ -278,6 +278,7 @@ pub(crate) struct CrateRoot {
     debugger_visualizers: LazyArray<DebuggerVisualizerFile>,
 
     exported_symbols: LazyArray<(ExportedSymbol<'static>, SymbolExportInfo)>,
+    proven_global_goals_in_new_solver: LazyArray<ty::Predicate<'static>>,
 
     syntax_contexts: SyntaxContextTable,
     expn_data: ExpnDataTable,
@@ -4,7 +4,7 @@
 use rustc_macros::{Diagnostic, Subdiagnostic};
 use rustc_span::{Span, Symbol};
 
-use crate::ty::Ty;
+use crate::ty::{self, Ty};
 
 #[derive(Diagnostic)]
 #[diag(middle_drop_check_overflow, code = E0320)]
@@ -147,5 +147,21 @@ pub struct ErroneousConstant {
     pub span: Span,
 }
 
+/// The structure of a fulfillment error whose diagnostic has been stashed with
+/// [`rustc_errors::StashKey::FulfillmentError`] instead of being emitted, so that
+/// the pass stealing it doesn't have to recover it from the rendered diagnostic.
+///
+/// All of these are fully resolved and free of inference variables.
+#[derive(Clone, Debug)]
+pub struct StashedFulfillmentError<'tcx> {
+    /// The predicate which failed to hold.
+    pub predicate: ty::Predicate<'tcx>,
+    /// The predicate of the root obligation whose proof required `predicate`.
+    pub root_predicate: ty::Predicate<'tcx>,
+    /// The parent trait predicates of the derived obligation causes leading to
+    /// `predicate`, starting with the innermost one.
+    pub derived_chain: Vec<ty::PolyTraitPredicate<'tcx>>,
+}
+
 /// Used by `rustc_const_eval`
 pub use crate::fluent_generated::middle_adjust_for_foreign_abi_error;
@@ -124,6 +124,10 @@ impl EraseType for Result<ty::GenericArg<'_>, traits::query::NoSolution> {
     type Result = [u8; size_of::<Result<ty::GenericArg<'static>, traits::query::NoSolution>>()];
 }
 
+impl EraseType for traits::solve::QueryResult<'_> {
+    type Result = [u8; size_of::<traits::solve::QueryResult<'static>>()];
+}
+
 impl EraseType for Result<bool, &ty::layout::LayoutError<'_>> {
     type Result = [u8; size_of::<Result<bool, &'static ty::layout::LayoutError<'static>>>()];
 }
@@ -39,6 +39,7 @@
     DropckConstraint, DropckOutlivesResult, MethodAutoderefStepsResult, NormalizationResult,
     OutlivesBound,
 };
+use crate::traits::solve;
 use crate::traits::specialization_graph;
 use crate::traits::{
     CodegenObligationError, EvaluationResult, ImplSource, ObjectSafetyViolation, ObligationCause,
@@ -978,6 +979,13 @@
         cache_on_disk_if { true }
     }
 
+    /// The where-clauses used while type-checking the body `key`, as indices into
+    /// the `caller_bounds` of its param-env. This is only tracked with the new
+    /// solver and `-Ztrack-used-param-env-clauses` and is empty otherwise.
+    query used_param_env_clauses(key: LocalDefId) -> &'tcx UnordSet<usize> {
+        desc { |tcx| "finding the where-clauses used by `{}`", tcx.def_path_str(key) }
+    }
+
     query coherent_trait(def_id: DefId) -> Result<(), ErrorGuaranteed> {
         desc { |tcx| "coherence checking all impls of trait `{}`", tcx.def_path_str(def_id) }
         ensure_forwards_result_if_red
@@ -2016,6 +2024,56 @@
         desc { "evaluating trait selection obligation `{}`", goal.value.value }
     }
 
+    /// Evaluates a canonical goal in the new trait solver using a new search graph.
+    ///
+    /// Do not call this query directly: invoke `infcx.evaluate_root_goal()` instead,
+    /// which only uses this query if no proof tree has been requested, the goal is
+    /// evaluated outside of coherence, and its evaluation cannot be cancelled.
+    ///
+    /// Like the other canonical trait system queries, e.g. `evaluate_obligation`, the
+    /// result is not cached on disk. Responses refer to interned external constraints
+    /// which cannot be decoded, and recomputing a root goal is cheap compared to the
+    /// bodies depending on it. What matters for incremental compilation is that the
+    /// result gets its own dep-node: if an input of the goal changes without changing
+    /// its result, e.g. when adding an unrelated impl of the same trait, the bodies
+    /// which depend on the goal can still be marked green.
+    query evaluate_root_canonical_goal_in_new_solver(
+        goal: solve::CanonicalInput<'tcx>
+    ) -> solve::QueryResult<'tcx> {
+        desc { "evaluating `{}` in the new trait solver", goal.value.goal.predicate }
+    }
+
+    /// Additional impls considered by the new trait solver for goals of the given trait,
+    /// which are not part of `trait_impls_of`.
+    ///
+    /// This is always empty in rustc itself. External drivers, e.g. verification tools,
+    /// may override this query to contribute candidates for specific traits. These are
+    /// considered like ordinary impl candidates, so they show up in proof trees and in
+    /// the causes of derived obligations.
+    query extra_impls_for_new_solver(trait_def_id: DefId) -> &'tcx [DefId] {
+        desc { |tcx|
+            "collecting extra impls of `{}` for the new trait solver",
+            tcx.def_path_str(trait_def_id)
+        }
+    }
+
+    /// The fully concrete goals proven by the new trait solver while type-checking the
+    /// bodies of the given crate. This is only stored in the crate metadata with
+    /// `-Zsolver-metadata-cache`.
+    ///
+    /// For the local crate, this is collected from the `TypeckResults` of each body, see
+    /// `TypeckResults::proven_global_goals`.
+    query proven_global_goals_in_new_solver(cnum: CrateNum) -> &'tcx [ty::Predicate<'tcx>] {
+        desc { "getting the goals proven by the new trait solver" }
+        separate_provide_extern
+    }
+
+    /// The fully concrete goals proven by the new trait solver in any upstream crate.
+    query upstream_proven_global_goals_in_new_solver(_: ()) -> &'tcx UnordSet<ty::Predicate<'tcx>> {
+        arena_cache
+        desc { "collecting the goals proven by the new trait solver in upstream crates" }
+    }
+
     /// Do not call this query directly: part of the `Eq` type-op
     query type_op_ascribe_user_type(
         goal: CanonicalTypeOpAscribeUserTypeGoal<'tcx>
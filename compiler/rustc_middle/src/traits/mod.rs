@@ -132,6 +132,22 @@ pub fn misc(span: Span, body_id: LocalDefId) -> ObligationCause<'tcx> {
         ObligationCause::new(span, body_id, MiscObligation)
     }
 
+    /// Returns a cause for `span` and `body_id` which shares the cause code of `self`.
+    /// Unlike `ObligationCause::new(span, body_id, self.code().clone())`, this does
+    /// not clone the cause code.
+    #[inline]
+    pub fn with_span_and_body_id(&self, span: Span, body_id: LocalDefId) -> ObligationCause<'tcx> {
+        ObligationCause { span, body_id, code: self.code.clone() }
+    }
+
+    /// Makes `self` share the cause code of `other` if both cause codes are equal,
+    /// so that we do not keep two separate copies of the same cause code chain.
+    pub fn share_code_with(&mut self, other: &ObligationCause<'tcx>) {
+        if self.code == other.code {
+            self.code = other.code.clone();
+        }
+    }
+
     #[inline(always)]
     pub fn dummy() -> ObligationCause<'tcx> {
         ObligationCause::dummy_with_span(DUMMY_SP)
@@ -306,6 +322,11 @@ pub enum ObligationCauseCode<'tcx> {
     SizedClosureCapture(LocalDefId),
     /// Types live across coroutine yields must be `Sized`.
     SizedCoroutineInterior(LocalDefId),
+    /// Goals arising while inferring the signature of the given closure
+    /// from its expected signature.
+    ClosureSignature(LocalDefId),
+    /// Goals arising while computing the interior of the given coroutine.
+    CoroutineInterior(LocalDefId),
     /// `[expr; N]` requires `type_of(expr): Copy`.
     RepeatElementCopy {
         /// If element is a `const fn` or const ctor we display a help message suggesting
@@ -342,6 +363,43 @@ pub enum ObligationCauseCode<'tcx> {
     /// Derived obligation for WF goals.
     WellFormedDerivedObligation(DerivedObligationCause<'tcx>),
 
+    /// Derived obligation requiring the tail field of a struct or tuple to be
+    /// unsized when unsizing the struct or tuple itself. The parent predicate
+    /// is the `Unsize` predicate of the struct or tuple.
+    UnsizeTailField(DerivedObligationCause<'tcx>),
+
+    /// Derived obligation requiring a type to provide the metadata of the trait
+    /// object it is unsized to. The parent predicate is the `Unsize` predicate.
+    UnsizeMetadata(DerivedObligationCause<'tcx>),
+
+    /// Derived obligation requiring a bound of a trait alias to hold. The parent
+    /// predicate is the predicate using the trait alias.
+    TraitAliasBound(DerivedObligationCause<'tcx>),
+
+    /// Derived obligation on the builtin impl of a supertrait for an object type,
+    /// e.g. a where-clause of `SuperTrait` when proving `dyn Trait: SuperTrait`.
+    /// Only used by the new solver.
+    ObjectCandidateBound(Box<ObjectCandidateBoundCause<'tcx>>),
+
+    /// Obligation from instantiating the bound variables of a higher-ranked
+    /// predicate with placeholders. Only used by the new solver.
+    InstantiatedHigherRanked {
+        /// The higher-ranked predicate, e.g. `for<'a> T: Trait<'a>`.
+        higher_ranked_predicate: ty::Predicate<'tcx>,
+        /// The cause of the higher-ranked predicate.
+        parent_code: InternedObligationCauseCode<'tcx>,
+    },
+
+    /// A `Coerce` goal which failed as the coercion from `source` to `target`
+    /// would have required the given adjustment. Only used by the new solver.
+    CoerceGoal {
+        step: CoercionStep,
+        source: Ty<'tcx>,
+        target: Ty<'tcx>,
+        /// The cause of the `Coerce` goal.
+        parent_code: InternedObligationCauseCode<'tcx>,
+    },
+
     FunctionArgumentObligation {
         /// The node of the relevant argument in the function call.
         arg_hir_id: HirId,
@@ -477,6 +535,33 @@ pub enum IsConstable {
     IsConstable,
 }
 
+/// The adjustment required by a failing `Coerce` goal. As `Coerce` goals are only
+/// emitted when coercing between two inference variables, they are always proven via
+/// subtyping, which fails once the types are known to require an actual coercion.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, HashStable, TyEncodable, TyDecodable)]
+pub enum CoercionStep {
+    /// Dereferencing a reference, e.g. `&String` to `&str`.
+    Deref,
+    /// Unsizing the pointee of a pointer, e.g. `&[u8; 4]` to `&[u8]`.
+    Unsize,
+    /// Casting a pointer, e.g. `&T` to `*const T` or a function item to a function pointer.
+    PointerCast,
+}
+
+impl CoercionStep {
+    pub fn descr(self) -> &'static str {
+        match self {
+            CoercionStep::Deref => "dereference",
+            CoercionStep::Unsize => "unsize",
+            CoercionStep::PointerCast => "cast",
+        }
+    }
+}
+
+crate::TrivialTypeTraversalAndLiftImpls! {
+    CoercionStep,
+}
+
 /// The 'location' at which we try to perform HIR-based wf checking.
 /// This information is used to obtain an `hir::Ty`, which
 /// we can walk in order to obtain precise spans for any
@@ -513,6 +598,15 @@ pub struct ImplDerivedObligationCause<'tcx> {
     pub span: Span,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, HashStable, TyEncodable, TyDecodable)]
+#[derive(TypeVisitable, TypeFoldable)]
+pub struct ObjectCandidateBoundCause<'tcx> {
+    pub derived: DerivedObligationCause<'tcx>,
+    /// The supertraits elaborated from the principal trait of the object type to get
+    /// to the trait of the parent predicate, starting with the principal trait itself.
+    pub supertrait_path: Vec<ty::PolyTraitRef<'tcx>>,
+}
+
 impl<'tcx> ObligationCauseCode<'tcx> {
     /// Returns the base obligation, ignoring derived obligations.
     pub fn peel_derives(&self) -> &Self {
@@ -540,9 +634,15 @@ pub fn peel_derives_with_predicate(&self) -> (&Self, Option<ty::PolyTraitPredica
 
     pub fn parent(&self) -> Option<(&Self, Option<ty::PolyTraitPredicate<'tcx>>)> {
         match self {
-            FunctionArgumentObligation { parent_code, .. } => Some((parent_code, None)),
+            FunctionArgumentObligation { parent_code, .. }
+            | InstantiatedHigherRanked { parent_code, .. }
+            | CoerceGoal { parent_code, .. } => Some((parent_code, None)),
             BuiltinDerivedObligation(derived)
             | WellFormedDerivedObligation(derived)
+            | UnsizeTailField(derived)
+            | UnsizeMetadata(derived)
+            | TraitAliasBound(derived)
+            | ObjectCandidateBound(box ObjectCandidateBoundCause { derived, .. })
             | ImplDerivedObligation(box ImplDerivedObligationCause { derived, .. }) => {
                 Some((&derived.parent_code, Some(derived.parent_trait_pred)))
             }
@@ -613,6 +713,12 @@ pub struct DerivedObligationCause<'tcx> {
 pub enum SelectionError<'tcx> {
     /// The trait is not implemented.
     Unimplemented,
+    /// The impl `impl_def_id` applies, but its where-clause with index `clause_idx`
+    /// in its `predicates_of` does not hold. The failing obligation is that where-clause.
+    ImplObligationFailed { impl_def_id: DefId, clause_idx: usize },
+    /// The trait is not implemented as there's an explicit negative impl,
+    /// e.g. `impl !Send for Foo`, which applies to the goal.
+    ExplicitNegativeImpl(DefId),
     /// After a closure impl has selected, its "outputs" were evaluated
     /// (which for closures includes the "input" type params) and they
     /// didn't resolve. See `confirm_poly_trait_refs` for more.
@@ -621,6 +727,13 @@ pub enum SelectionError<'tcx> {
     TraitNotObjectSafe(DefId),
     /// A given constant couldn't be evaluated.
     NotConstEvaluatable(NotConstEvaluatable),
+    /// A constant argument does not have the type required by its parameter.
+    /// `evaluated` is the value of `ct` if it could be evaluated.
+    ConstArgHasWrongType {
+        ct: ty::Const<'tcx>,
+        evaluated: Option<ty::Const<'tcx>>,
+        expected_ty: Ty<'tcx>,
+    },
     /// Exceeded the recursion depth during type projection.
     Overflow(OverflowError),
     /// Computing an opaque type's hidden type caused an error (e.g. a cycle error).
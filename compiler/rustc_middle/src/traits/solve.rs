@@ -1,4 +1,5 @@
 use rustc_ast_ir::try_visit;
+use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::intern::Interned;
 use rustc_macros::{HashStable, TypeFoldable, TypeVisitable};
 use rustc_span::def_id::DefId;
@@ -16,7 +17,7 @@
 mod cache;
 pub mod inspect;
 
-pub use cache::{CacheData, EvaluationCache};
+pub use cache::{CacheData, EvaluationCache, EvaluationCacheStats};
 
 /// A goal is a statement, i.e. `predicate`, we want to prove
 /// given some assumptions, i.e. `param_env`.
@@ -59,7 +60,7 @@ pub enum Certainty {
 }
 
 impl Certainty {
-    pub const AMBIGUOUS: Certainty = Certainty::Maybe(MaybeCause::Ambiguity);
+    pub const AMBIGUOUS: Certainty = Certainty::ambiguous(AmbiguityCause::Unknown);
 
     /// Use this function to merge the certainty of multiple nested subgoals.
     ///
@@ -82,36 +83,126 @@ pub fn unify_with(self, other: Certainty) -> Certainty {
         }
     }
 
+    pub const fn ambiguous(cause: AmbiguityCause) -> Certainty {
+        Certainty::Maybe(MaybeCause::Ambiguity(cause))
+    }
+
     pub const fn overflow(suggest_increasing_limit: bool) -> Certainty {
-        Certainty::Maybe(MaybeCause::Overflow { suggest_increasing_limit })
+        Certainty::Maybe(MaybeCause::Overflow {
+            suggest_increasing_limit,
+            reached_step_limit: false,
+        })
+    }
+
+    /// The result of goals which have not been computed as their root goal
+    /// exceeded the step limit of `-Zsolver-step-limit`.
+    pub const fn step_limit_overflow() -> Certainty {
+        Certainty::Maybe(MaybeCause::Overflow {
+            suggest_increasing_limit: false,
+            reached_step_limit: true,
+        })
     }
 }
 
+/// The outcome of evaluating a root goal, as recorded by `-Zdump-solver-goal-summary`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GoalOutcome {
+    Holds,
+    Ambiguous,
+    Overflow,
+    NoSolution,
+}
+
+impl GoalOutcome {
+    pub fn from_result(result: Result<Certainty, NoSolution>) -> GoalOutcome {
+        match result {
+            Ok(Certainty::Yes) => GoalOutcome::Holds,
+            Ok(Certainty::Maybe(MaybeCause::Ambiguity(_))) => GoalOutcome::Ambiguous,
+            Ok(Certainty::Maybe(MaybeCause::Overflow { .. })) => GoalOutcome::Overflow,
+            Err(NoSolution) => GoalOutcome::NoSolution,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GoalOutcome::Holds => "holds",
+            GoalOutcome::Ambiguous => "ambiguous",
+            GoalOutcome::Overflow => "overflow",
+            GoalOutcome::NoSolution => "no-solution",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<GoalOutcome> {
+        Some(match s {
+            "holds" => GoalOutcome::Holds,
+            "ambiguous" => GoalOutcome::Ambiguous,
+            "overflow" => GoalOutcome::Overflow,
+            "no-solution" => GoalOutcome::NoSolution,
+            _ => return None,
+        })
+    }
+}
+
+/// The goals of a solver session replayed with `-Zreplay-solver-session`, keyed by the
+/// path of their body and their hash, together with their last recorded outcome, if any.
+pub type ReplayedSolverSession = FxHashMap<(String, String), Option<GoalOutcome>>;
+
 /// Why we failed to evaluate a goal.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, HashStable, TypeFoldable, TypeVisitable)]
 pub enum MaybeCause {
     /// We failed due to ambiguity. This ambiguity can either
     /// be a true ambiguity, i.e. there are multiple different answers,
     /// or we hit a case where we just don't bother, e.g. `?x: Trait` goals.
-    Ambiguity,
+    Ambiguity(AmbiguityCause),
     /// We gave up due to an overflow, most often by hitting the recursion limit.
-    Overflow { suggest_increasing_limit: bool },
+    ///
+    /// `reached_step_limit` is set if we instead gave up as the root goal required
+    /// computing more goals than allowed by `-Zsolver-step-limit`.
+    Overflow { suggest_increasing_limit: bool, reached_step_limit: bool },
 }
 
 impl MaybeCause {
     fn unify_with(self, other: MaybeCause) -> MaybeCause {
         match (self, other) {
-            (MaybeCause::Ambiguity, MaybeCause::Ambiguity) => MaybeCause::Ambiguity,
-            (MaybeCause::Ambiguity, MaybeCause::Overflow { .. }) => other,
-            (MaybeCause::Overflow { .. }, MaybeCause::Ambiguity) => self,
+            (MaybeCause::Ambiguity(a), MaybeCause::Ambiguity(b)) => {
+                MaybeCause::Ambiguity(if a == b { a } else { AmbiguityCause::Unknown })
+            }
+            (MaybeCause::Ambiguity(_), MaybeCause::Overflow { .. }) => other,
+            (MaybeCause::Overflow { .. }, MaybeCause::Ambiguity(_)) => self,
             (
-                MaybeCause::Overflow { suggest_increasing_limit: a },
-                MaybeCause::Overflow { suggest_increasing_limit: b },
-            ) => MaybeCause::Overflow { suggest_increasing_limit: a || b },
+                MaybeCause::Overflow { suggest_increasing_limit: a, reached_step_limit: a_step },
+                MaybeCause::Overflow { suggest_increasing_limit: b, reached_step_limit: b_step },
+            ) => MaybeCause::Overflow {
+                suggest_increasing_limit: a || b,
+                reached_step_limit: a_step || b_step,
+            },
         }
     }
 }
 
+/// The construct which caused a goal to be ambiguous.
+///
+/// If the nested goals of a goal are ambiguous for different reasons,
+/// this is [`AmbiguityCause::Unknown`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, HashStable, TypeFoldable, TypeVisitable)]
+pub enum AmbiguityCause {
+    /// We do not know the precise cause of the ambiguity.
+    Unknown,
+    /// A type the goal structurally depends on, most often its self type,
+    /// is a not yet resolved inference variable.
+    UnresolvedType,
+    /// A constant has not yet been inferred or cannot be evaluated yet.
+    UnresolvedConst,
+    /// Multiple candidates apply and we were unable to merge their responses.
+    MultipleCandidates,
+    /// The hidden type of an opaque type has not yet been defined.
+    UndefinedOpaque,
+    /// The goal may only hold due to a reservation impl, e.g. `impl<T> From<!> for T`.
+    /// These impls are only considered during coherence, where they are ambiguous so
+    /// that replacing them with a real impl later on is not a breaking change.
+    ReservationImpl(DefId),
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, HashStable, TypeFoldable, TypeVisitable)]
 pub struct QueryInput<'tcx, T> {
     pub goal: Goal<'tcx, T>,
@@ -275,6 +366,37 @@ pub enum GoalSource {
     ImplWhereBound,
     /// Instantiating a higher-ranked goal and re-proving it.
     InstantiateHigherRanked,
+    /// A nested goal required for a `WellFormed` goal to hold, e.g. a where-bound
+    /// of the type whose well-formedness is being proven.
+    WellFormed,
+    /// The `Unsize` goal for the tail field of a struct or tuple, required when
+    /// unsizing that struct or tuple, e.g. `[u8; 4]: Unsize<[u8]>` when proving
+    /// `Wrapper<[u8; 4]>: Unsize<Wrapper<[u8]>>`.
+    ///
+    /// This is a where-bound of the builtin `Unsize` impl and should be treated
+    /// like [`GoalSource::ImplWhereBound`]. It is only tracked separately to
+    /// point at the offending field in diagnostics.
+    UnsizeTailField,
+    /// A goal required for the source type to provide the metadata of a trait
+    /// object it is unsized to, i.e. the bounds of the trait object and `Sized`.
+    ///
+    /// Like [`GoalSource::UnsizeTailField`], this should otherwise be treated
+    /// like [`GoalSource::ImplWhereBound`].
+    UnsizeMetadata,
+    /// A supertrait or item bound of the trait implemented by the object candidate of
+    /// a goal like `dyn Trait: SuperTrait`, which the object type has to implement for
+    /// the candidate to apply.
+    ///
+    /// This should be treated like [`GoalSource::ImplWhereBound`]. It is only tracked
+    /// separately so that diagnostics can mention the supertraits elaborated from the
+    /// principal trait of the object type.
+    ObjectBound,
+    /// A bound of a trait alias, which has to hold for a type to implement that
+    /// trait alias, e.g. `T: Debug` when proving `T: Alias` with `trait Alias = Debug;`.
+    ///
+    /// This should be treated like [`GoalSource::Misc`]. It is only tracked separately
+    /// so that diagnostics can look through the trait alias.
+    TraitAliasBound,
 }
 
 /// Possible ways the given goal can be proven.
@@ -1,10 +1,13 @@
 use super::{inspect, CanonicalInput, QueryResult};
-use crate::ty::TyCtxt;
+use crate::ty::{self, Ty, TyCtxt, TypeSuperVisitable, TypeVisitable, TypeVisitor};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::sync::Lock;
+use rustc_hir::def_id::DefId;
 use rustc_query_system::cache::WithDepNode;
 use rustc_query_system::dep_graph::DepNodeIndex;
 use rustc_session::Limit;
+use std::ops::ControlFlow;
+
 /// The trait solver cache used by `-Znext-solver`.
 ///
 /// FIXME(@lcnr): link to some official documentation of how
@@ -20,6 +23,8 @@ pub struct CacheData<'tcx> {
     pub proof_tree: Option<&'tcx [inspect::GoalEvaluationStep<'tcx>]>,
     pub reached_depth: usize,
     pub encountered_overflow: bool,
+    /// The number of goals computed when evaluating this goal, including itself.
+    pub steps: usize,
 }
 
 impl<'tcx> EvaluationCache<'tcx> {
@@ -31,13 +36,14 @@ pub fn insert(
         proof_tree: Option<&'tcx [inspect::GoalEvaluationStep<'tcx>]>,
         reached_depth: usize,
         encountered_overflow: bool,
+        steps: usize,
         cycle_participants: FxHashSet<CanonicalInput<'tcx>>,
         dep_node: DepNodeIndex,
         result: QueryResult<'tcx>,
     ) {
         let mut map = self.map.borrow_mut();
         let entry = map.entry(key).or_default();
-        let data = WithDepNode::new(dep_node, QueryData { result, proof_tree });
+        let data = WithDepNode::new(dep_node, QueryData { result, proof_tree, steps });
         entry.cycle_participants.extend(cycle_participants);
         if encountered_overflow {
             entry.with_overflow.insert(reached_depth, data);
@@ -47,9 +53,8 @@ pub fn insert(
 
         if cfg!(debug_assertions) {
             drop(map);
-            if Some(CacheData { result, proof_tree, reached_depth, encountered_overflow })
-                != self.get(tcx, key, |_| false, Limit(reached_depth))
-            {
+            let data = CacheData { result, proof_tree, reached_depth, encountered_overflow, steps };
+            if Some(data) != self.get(tcx, key, |_| false, Limit(reached_depth)) {
                 bug!("unable to retrieve inserted element from cache: {key:?}");
             }
         }
@@ -75,26 +80,175 @@ pub fn get(
 
         if let Some(ref success) = entry.success {
             if available_depth.value_within_limit(success.reached_depth) {
-                let QueryData { result, proof_tree } = success.data.get(tcx);
+                let QueryData { result, proof_tree, steps } = success.data.get(tcx);
                 return Some(CacheData {
                     result,
                     proof_tree,
                     reached_depth: success.reached_depth,
                     encountered_overflow: false,
+                    steps,
                 });
             }
         }
 
         entry.with_overflow.get(&available_depth.0).map(|e| {
-            let QueryData { result, proof_tree } = e.get(tcx);
+            let QueryData { result, proof_tree, steps } = e.get(tcx);
             CacheData {
                 result,
                 proof_tree,
                 reached_depth: available_depth.0,
                 encountered_overflow: true,
+                steps,
             }
         })
     }
+
+    /// Removes all entries from the cache and frees its memory.
+    ///
+    /// Removing entries never changes the result of the trait solver, it only
+    /// causes the affected goals to be evaluated again.
+    pub fn clear(&self) {
+        *self.map.borrow_mut() = Default::default();
+    }
+
+    /// Only keeps the entries whose goal satisfies `keep`.
+    pub fn retain(&self, mut keep: impl FnMut(CanonicalInput<'tcx>) -> bool) {
+        self.map.borrow_mut().retain(|&key, _| keep(key));
+    }
+
+    /// Shrinks the capacity of the cache as much as possible.
+    pub fn shrink_to_fit(&self) {
+        let mut map = self.map.borrow_mut();
+        map.shrink_to_fit();
+        for entry in map.values_mut() {
+            entry.cycle_participants.shrink_to_fit();
+            entry.with_overflow.shrink_to_fit();
+        }
+    }
+
+    pub fn stats(&self) -> EvaluationCacheStats {
+        let map = self.map.borrow();
+        let mut stats = EvaluationCacheStats { entries: map.len(), ..Default::default() };
+        for entry in map.values() {
+            stats.successful_results += entry.success.is_some() as usize;
+            stats.overflow_results += entry.with_overflow.len();
+            stats.cycle_participants += entry.cycle_participants.len();
+        }
+        stats
+    }
+}
+
+/// Statistics about the size of an [`EvaluationCache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvaluationCacheStats {
+    /// The number of cached goals.
+    pub entries: usize,
+    /// The number of results which did not encounter overflow.
+    pub successful_results: usize,
+    /// The number of results which encountered overflow, there may be
+    /// multiple such results for a goal with different recursion limits.
+    pub overflow_results: usize,
+    /// The total number of cycle participants stored for the cached goals.
+    pub cycle_participants: usize,
+}
+
+impl std::ops::Add for EvaluationCacheStats {
+    type Output = EvaluationCacheStats;
+
+    fn add(self, other: EvaluationCacheStats) -> EvaluationCacheStats {
+        EvaluationCacheStats {
+            entries: self.entries + other.entries,
+            successful_results: self.successful_results + other.successful_results,
+            overflow_results: self.overflow_results + other.overflow_results,
+            cycle_participants: self.cycle_participants + other.cycle_participants,
+        }
+    }
+}
+
+/// Methods to manage the global caches of the new trait solver. These are
+/// intended for drivers which keep a `TyCtxt` alive for a long time.
+impl<'tcx> TyCtxt<'tcx> {
+    fn for_each_new_solver_evaluation_cache(self, mut f: impl FnMut(&EvaluationCache<'tcx>)) {
+        f(&self.new_solver_evaluation_cache);
+        f(&self.new_solver_coherence_evaluation_cache);
+    }
+
+    /// Removes all entries from the evaluation caches of the new trait solver.
+    pub fn clear_new_solver_evaluation_caches(self) {
+        self.for_each_new_solver_evaluation_cache(EvaluationCache::clear);
+    }
+
+    /// Removes the entries from the evaluation caches of the new trait solver
+    /// whose goal mentions `def_id`, e.g. after the item `def_id` changed.
+    pub fn remove_new_solver_cache_entries_mentioning(self, def_id: DefId) {
+        self.for_each_new_solver_evaluation_cache(|cache| {
+            cache.retain(|key| {
+                key.value.goal.visit_with(&mut MentionsDefId { def_id }).is_continue()
+            })
+        });
+    }
+
+    /// Shrinks the capacity of the evaluation caches of the new trait solver.
+    pub fn shrink_new_solver_evaluation_caches(self) {
+        self.for_each_new_solver_evaluation_cache(EvaluationCache::shrink_to_fit);
+    }
+
+    /// Returns the combined size of the evaluation caches of the new trait solver.
+    pub fn new_solver_evaluation_cache_stats(self) -> EvaluationCacheStats {
+        let mut stats = EvaluationCacheStats::default();
+        self.for_each_new_solver_evaluation_cache(|cache| stats = stats + cache.stats());
+        stats
+    }
+}
+
+/// Breaks if the visited value mentions `def_id`, e.g. as the `DefId` of an
+/// ADT, an alias, or the trait of a trait predicate.
+struct MentionsDefId {
+    def_id: DefId,
+}
+
+impl<'tcx> TypeVisitor<TyCtxt<'tcx>> for MentionsDefId {
+    type Result = ControlFlow<()>;
+
+    fn visit_ty(&mut self, ty: Ty<'tcx>) -> Self::Result {
+        let def_id = match *ty.kind() {
+            ty::Adt(adt_def, _) => Some(adt_def.did()),
+            ty::Foreign(def_id)
+            | ty::FnDef(def_id, _)
+            | ty::Closure(def_id, _)
+            | ty::CoroutineClosure(def_id, _)
+            | ty::Coroutine(def_id, _)
+            | ty::CoroutineWitness(def_id, _) => Some(def_id),
+            ty::Alias(_, alias) => Some(alias.def_id),
+            _ => None,
+        };
+        if def_id == Some(self.def_id) { ControlFlow::Break(()) } else { ty.super_visit_with(self) }
+    }
+
+    fn visit_const(&mut self, ct: ty::Const<'tcx>) -> Self::Result {
+        if let ty::ConstKind::Unevaluated(uv) = ct.kind()
+            && uv.def == self.def_id
+        {
+            return ControlFlow::Break(());
+        }
+        ct.super_visit_with(self)
+    }
+
+    fn visit_predicate(&mut self, predicate: ty::Predicate<'tcx>) -> Self::Result {
+        let def_id = match predicate.kind().skip_binder() {
+            ty::PredicateKind::Clause(ty::ClauseKind::Trait(pred)) => Some(pred.def_id()),
+            ty::PredicateKind::Clause(ty::ClauseKind::Projection(pred)) => {
+                Some(pred.projection_ty.def_id)
+            }
+            ty::PredicateKind::NormalizesTo(pred) => Some(pred.alias.def_id),
+            ty::PredicateKind::ObjectSafe(def_id) => Some(def_id),
+            _ => None,
+        };
+        if def_id == Some(self.def_id) {
+            return ControlFlow::Break(());
+        }
+        predicate.super_visit_with(self)
+    }
 }
 
 struct Success<'tcx> {
@@ -106,6 +260,7 @@ struct Success<'tcx> {
 pub struct QueryData<'tcx> {
     pub result: QueryResult<'tcx>,
     pub proof_tree: Option<&'tcx [inspect::GoalEvaluationStep<'tcx>]>,
+    pub steps: usize,
 }
 
 /// The cache entry for a goal `CanonicalInput`.
@@ -24,10 +24,15 @@
 };
 use crate::{infer::canonical::CanonicalVarValues, ty};
 use format::ProofTreeFormatter;
+use stable_format::StableProofTreeFormatter;
 use rustc_macros::{TypeFoldable, TypeVisitable};
+use rustc_span::def_id::DefId;
 use std::fmt::{Debug, Write};
 
 mod format;
+mod stable_format;
+
+pub use stable_format::STABLE_FORMAT_VERSION;
 
 /// Some `data` together with information about how they relate to the input
 /// of the canonical query.
@@ -48,27 +53,27 @@ pub struct State<'tcx, T> {
 /// canonicalized goal. We use this to map any [CanonicalState]
 /// from the local `InferCtxt` of the solver query to
 /// the `InferCtxt` of the caller.
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone)]
 pub enum GoalEvaluationKind<'tcx> {
     Root { orig_values: Vec<ty::GenericArg<'tcx>> },
     Nested,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone)]
 pub struct GoalEvaluation<'tcx> {
     pub uncanonicalized_goal: Goal<'tcx, ty::Predicate<'tcx>>,
     pub kind: GoalEvaluationKind<'tcx>,
     pub evaluation: CanonicalGoalEvaluation<'tcx>,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub struct CanonicalGoalEvaluation<'tcx> {
     pub goal: CanonicalInput<'tcx>,
     pub kind: CanonicalGoalEvaluationKind<'tcx>,
     pub result: QueryResult<'tcx>,
 }
 
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Clone)]
 pub enum CanonicalGoalEvaluationKind<'tcx> {
     Overflow,
     CycleInStack,
@@ -81,6 +86,14 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
+impl<'tcx> GoalEvaluation<'tcx> {
+    /// Formats this proof tree using the compact format which is kept
+    /// stable across changes to the solver, see [STABLE_FORMAT_VERSION].
+    pub fn format_stable(&self, tcx: ty::TyCtxt<'tcx>) -> String {
+        StableProofTreeFormatter::format(tcx, self)
+    }
+}
+
 #[derive(Eq, PartialEq)]
 pub struct AddedGoalsEvaluation<'tcx> {
     pub evaluations: Vec<Vec<GoalEvaluation<'tcx>>>,
@@ -123,6 +136,16 @@ pub enum ProbeStep<'tcx> {
     /// used whenever there are multiple candidates to prove the
     /// current goalby .
     NestedProbe(Probe<'tcx>),
+    /// An opaque type was constrained to a hidden type, either by defining it
+    /// while normalizing the opaque or by applying the response of a nested goal.
+    RecordOpaqueTypeConstraint(CanonicalState<'tcx, (ty::OpaqueTypeKey<'tcx>, ty::Ty<'tcx>)>),
+    /// The bound variables of a higher-ranked goal got instantiated with placeholders
+    /// in the newly created `universe`. Universes are local to the `InferCtxt` of the
+    /// current goal, in which the universes of its canonical input are the lowest ones.
+    EnterUniverse {
+        universe: ty::UniverseIndex,
+        bound_vars: &'tcx ty::List<ty::BoundVariableKind>,
+    },
     /// A call to `EvalCtxt::evaluate_added_goals_make_canonical_response` with
     /// `Certainty` was made. This is the certainty passed in, so it's not unified
     /// with the certainty of the `try_evaluate_added_goals` that is done within;
@@ -153,4 +176,14 @@ pub enum ProbeKind<'tcx> {
     UpcastProjectionCompatibility,
     /// Try to unify an opaque type with an existing key in the storage.
     OpaqueTypeStorageLookup { result: QueryResult<'tcx> },
+    /// Checking whether an explicit negative impl, e.g. `impl !Send for Foo`,
+    /// applies to a positive trait goal. If it does, the goal can never hold.
+    ExplicitNegativeImpl { impl_def_id: DefId, result: QueryResult<'tcx> },
+    /// The impl candidates of a trait goal have been discarded as the goal also holds
+    /// via a global where-bound, i.e. one which does not mention any generic parameters.
+    /// `param_env_index` is the index of that where-bound in the `param_env`.
+    ///
+    /// This probe does not contain any steps, it only records that the goal relies
+    /// on the preference for where-bounds, see `-Zsolver-global-where-bounds`.
+    ShadowedByGlobalWhereBound { param_env_index: usize },
 }
@@ -118,6 +118,12 @@ pub(super) fn format_probe(&mut self, probe: &Probe<'_>) -> std::fmt::Result {
             ProbeKind::TraitCandidate { source, result } => {
                 write!(self.f, "CANDIDATE {source:?}: {result:?}")
             }
+            ProbeKind::ExplicitNegativeImpl { impl_def_id, result } => {
+                write!(self.f, "EXPLICIT NEGATIVE IMPL {impl_def_id:?}: {result:?}")
+            }
+            ProbeKind::ShadowedByGlobalWhereBound { param_env_index } => {
+                write!(self.f, "IMPLS SHADOWED BY GLOBAL WHERE-BOUND {param_env_index}:")
+            }
         }?;
 
         self.nested(|this| {
@@ -128,11 +134,22 @@ pub(super) fn format_probe(&mut self, probe: &Probe<'_>) -> std::fmt::Result {
                             GoalSource::Misc => "misc",
                             GoalSource::ImplWhereBound => "impl where-bound",
                             GoalSource::InstantiateHigherRanked => "higher-ranked goal",
+                            GoalSource::WellFormed => "well-formed",
+                            GoalSource::UnsizeTailField => "unsize tail field",
+                            GoalSource::UnsizeMetadata => "unsize metadata",
+                            GoalSource::ObjectBound => "object bound",
+                            GoalSource::TraitAliasBound => "trait alias bound",
                         };
                         writeln!(this.f, "ADDED GOAL ({source}): {goal:?}")?
                     }
                     ProbeStep::EvaluateGoals(eval) => this.format_added_goals_evaluation(eval)?,
                     ProbeStep::NestedProbe(probe) => this.format_probe(probe)?,
+                    ProbeStep::RecordOpaqueTypeConstraint(constraint) => {
+                        writeln!(this.f, "RECORDED OPAQUE TYPE CONSTRAINT: {constraint:?}")?
+                    }
+                    ProbeStep::EnterUniverse { universe, bound_vars } => {
+                        writeln!(this.f, "ENTERED UNIVERSE {universe:?} FOR {bound_vars:?}")?
+                    }
                     ProbeStep::MakeCanonicalResponse { shallow_certainty } => {
                         writeln!(this.f, "EVALUATE GOALS AND MAKE RESPONSE: {shallow_certainty:?}")?
                     }
@@ -0,0 +1,170 @@
+//! A compact textual format for proof trees, used by
+//! `-Zdump-solver-proof-tree-format=stable`.
+//!
+//! Unlike the `Debug` output of proof trees, this format only contains the
+//! evaluated goals, the candidates used to prove them, their results and the
+//! opaque types constrained while proving them. It intentionally omits solver
+//! internals which frequently change, and renumbers inference variables and
+//! placeholders in the order of their first occurrence. This makes it suitable
+//! for UI test snapshots.
+//!
+//! The output starts with a version header. Any change to the format has to
+//! bump [`STABLE_FORMAT_VERSION`].
+
+use super::*;
+use crate::traits::solve::MaybeCause;
+use crate::ty::TyCtxt;
+use rustc_data_structures::fx::FxHashMap;
+
+pub const STABLE_FORMAT_VERSION: u32 = 2;
+
+pub(super) struct StableProofTreeFormatter<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    out: String,
+    depth: usize,
+}
+
+impl<'tcx> StableProofTreeFormatter<'tcx> {
+    pub(super) fn format(tcx: TyCtxt<'tcx>, eval: &GoalEvaluation<'tcx>) -> String {
+        let mut this = StableProofTreeFormatter { tcx, out: String::new(), depth: 0 };
+        this.line(format_args!("proof-tree v{STABLE_FORMAT_VERSION}"));
+        this.format_goal_evaluation(eval);
+        normalize_variables(&this.out)
+    }
+
+    fn line(&mut self, args: std::fmt::Arguments<'_>) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        // Writing to a `String` cannot fail.
+        let _ = self.out.write_fmt(args);
+        self.out.push('\n');
+    }
+
+    fn nested(&mut self, func: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        func(self);
+        self.depth -= 1;
+    }
+
+    fn format_goal_evaluation(&mut self, eval: &GoalEvaluation<'tcx>) {
+        let predicate = eval.uncanonicalized_goal.predicate;
+        let result = format_result(&eval.evaluation.result);
+        match &eval.evaluation.kind {
+            CanonicalGoalEvaluationKind::Overflow => {
+                self.line(format_args!("goal {predicate:?} = {result} (overflow)"))
+            }
+            CanonicalGoalEvaluationKind::CycleInStack => {
+                self.line(format_args!("goal {predicate:?} = {result} (cycle)"))
+            }
+            CanonicalGoalEvaluationKind::ProvisionalCacheHit => {
+                self.line(format_args!("goal {predicate:?} = {result} (provisional cache hit)"))
+            }
+            CanonicalGoalEvaluationKind::Evaluation { revisions } => {
+                self.line(format_args!("goal {predicate:?} = {result}"));
+                // Only the final revision is relevant for the result of the goal,
+                // earlier ones are an implementation detail of cycle handling.
+                if let Some(step) = revisions.last() {
+                    self.nested(|this| this.format_probe_steps(&step.evaluation));
+                }
+            }
+        }
+    }
+
+    fn format_probe(&mut self, probe: &Probe<'tcx>) {
+        match probe.kind {
+            ProbeKind::TraitCandidate { source, result } => {
+                let source = self.format_candidate_source(source);
+                self.line(format_args!("candidate {source} = {}", format_result(&result)));
+                self.nested(|this| this.format_probe_steps(probe));
+            }
+            ProbeKind::ExplicitNegativeImpl { impl_def_id, result } => {
+                let path = self.tcx.def_path_str(impl_def_id);
+                self.line(format_args!("negative impl {path} = {}", format_result(&result)));
+                self.nested(|this| this.format_probe_steps(probe));
+            }
+            ProbeKind::ShadowedByGlobalWhereBound { param_env_index } => {
+                self.line(format_args!("impls shadowed by global where-bound #{param_env_index}"));
+            }
+            // All other probes are internal to the solver, so we only
+            // print their nested goals and candidates.
+            ProbeKind::Root { .. }
+            | ProbeKind::TryNormalizeNonRigid { .. }
+            | ProbeKind::NormalizedSelfTyAssembly
+            | ProbeKind::UnsizeAssembly
+            | ProbeKind::UpcastProjectionCompatibility
+            | ProbeKind::OpaqueTypeStorageLookup { .. } => self.format_probe_steps(probe),
+        }
+    }
+
+    fn format_probe_steps(&mut self, probe: &Probe<'tcx>) {
+        for step in &probe.steps {
+            match step {
+                ProbeStep::NestedProbe(probe) => self.format_probe(probe),
+                ProbeStep::EvaluateGoals(eval) => {
+                    for goal_evaluation in eval.evaluations.iter().flatten() {
+                        self.format_goal_evaluation(goal_evaluation);
+                    }
+                }
+                ProbeStep::RecordOpaqueTypeConstraint(constraint) => {
+                    let (opaque_type_key, hidden_ty) = constraint.value.data;
+                    let path = self.tcx.def_path_str(opaque_type_key.def_id);
+                    self.line(format_args!("opaque {path} := {hidden_ty:?}"));
+                }
+                ProbeStep::AddGoal(..)
+                | ProbeStep::EnterUniverse { .. }
+                | ProbeStep::MakeCanonicalResponse { .. } => {}
+            }
+        }
+    }
+
+    fn format_candidate_source(&self, source: CandidateSource) -> String {
+        match source {
+            CandidateSource::Impl(def_id) => format!("impl {}", self.tcx.def_path_str(def_id)),
+            CandidateSource::BuiltinImpl(source) => format!("builtin {source:?}"),
+            CandidateSource::ParamEnv(idx) => format!("where-clause #{idx}"),
+            CandidateSource::AliasBound => "alias-bound".to_owned(),
+        }
+    }
+}
+
+fn format_result(result: &QueryResult<'_>) -> &'static str {
+    match result {
+        Ok(response) => match response.value.certainty {
+            Certainty::Yes => "yes",
+            Certainty::Maybe(MaybeCause::Ambiguity(_)) => "ambiguous",
+            Certainty::Maybe(MaybeCause::Overflow { .. }) => "overflow",
+        },
+        Err(NoSolution) => "no",
+    }
+}
+
+/// Renumbers inference variables, e.g. `?3t` or `'?7`, and the universes
+/// of placeholders, e.g. `!2_0`, in the order in which they first occur.
+fn normalize_variables(s: &str) -> String {
+    let mut vars = FxHashMap::default();
+    let mut universes = FxHashMap::default();
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        out.push(c);
+        let map = match c {
+            '?' => &mut vars,
+            '!' => &mut universes,
+            _ => continue,
+        };
+        let mut end = start + 1;
+        while let Some(&(i, d)) = chars.peek()
+            && d.is_ascii_digit()
+        {
+            end = i + 1;
+            chars.next();
+        }
+        if end > start + 1 {
+            let next = map.len();
+            let idx = *map.entry(&s[start + 1..end]).or_insert(next);
+            let _ = write!(out, "{idx}");
+        }
+    }
+    out
+}
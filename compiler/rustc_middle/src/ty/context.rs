@@ -6,6 +6,7 @@
 
 use crate::arena::Arena;
 use crate::dep_graph::{DepGraph, DepKindStruct};
+use crate::error::StashedFulfillmentError;
 use crate::infer::canonical::{CanonicalParamEnvCache, CanonicalVarInfo, CanonicalVarInfos};
 use crate::lint::lint_level;
 use crate::metadata::ModChild;
@@ -35,11 +36,11 @@
 use rustc_ast::{self as ast, attr};
 use rustc_data_structures::defer;
 use rustc_data_structures::fingerprint::Fingerprint;
-use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxIndexMap};
 use rustc_data_structures::intern::Interned;
 use rustc_data_structures::profiling::SelfProfilerRef;
 use rustc_data_structures::sharded::{IntoPointer, ShardedHashMap};
-use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_data_structures::stable_hasher::{Hash64, HashStable, StableHasher};
 use rustc_data_structures::steal::Steal;
 use rustc_data_structures::sync::{self, FreezeReadGuard, Lock, Lrc, RwLock, WorkerLocal};
 #[cfg(parallel_compiler)]
@@ -47,6 +48,7 @@
 use rustc_data_structures::unord::UnordSet;
 use rustc_errors::{
     Applicability, Diag, DiagCtxt, DiagMessage, ErrorGuaranteed, LintDiagnostic, MultiSpan,
+    StashKey,
 };
 use rustc_hir as hir;
 use rustc_hir::def::DefKind;
@@ -81,6 +83,7 @@
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::{Bound, Deref};
+use std::time::Duration;
 
 #[allow(rustc::usage_of_ty_tykind)]
 impl<'tcx> Interner for TyCtxt<'tcx> {
@@ -735,6 +738,28 @@ pub struct GlobalCtxt<'tcx> {
     /// Caches the results of goal evaluation in the new solver.
     pub new_solver_evaluation_cache: solve::EvaluationCache<'tcx>,
     pub new_solver_coherence_evaluation_cache: solve::EvaluationCache<'tcx>,
+    /// The outcome of the root goals evaluated by the new solver in each body, keyed by
+    /// the stable hash of the canonicalized goal. This is only recorded with
+    /// `-Zdump-solver-goal-summary` or `-Zcompare-solver-goal-summary`.
+    pub new_solver_goal_summary:
+        Lock<FxIndexMap<(LocalDefId, Hash64), (String, solve::GoalOutcome)>>,
+
+    /// The total time spent by the new solver evaluating the root goals of the
+    /// obligations with a given span, together with the number of evaluations.
+    /// This is only recorded with `-Zsolver-span-profile`.
+    pub new_solver_span_profile: Lock<FxIndexMap<Span, (Duration, usize)>>,
+
+    /// The file the goals of the new solver are recorded to with `-Zrecord-solver-session`,
+    /// opened when recording the first goal.
+    pub new_solver_session_log: Lock<Option<Result<std::fs::File, ErrorGuaranteed>>>,
+    /// The session replayed with `-Zreplay-solver-session`, loaded when replaying the
+    /// first goal.
+    pub new_solver_session_replay:
+        Lock<Option<Result<solve::ReplayedSolverSession, ErrorGuaranteed>>>,
+
+    /// The structure of the fulfillment errors whose diagnostics are currently stashed
+    /// with `StashKey::FulfillmentError`, keyed by the span of their obligation.
+    pub stashed_fulfillment_errors: Lock<FxIndexMap<Span, StashedFulfillmentError<'tcx>>>,
 
     pub canonical_param_env_cache: CanonicalParamEnvCache<'tcx>,
 
@@ -970,6 +995,11 @@ pub fn create_global_ctxt(
             evaluation_cache: Default::default(),
             new_solver_evaluation_cache: Default::default(),
             new_solver_coherence_evaluation_cache: Default::default(),
+            new_solver_goal_summary: Default::default(),
+            new_solver_span_profile: Default::default(),
+            new_solver_session_log: Default::default(),
+            new_solver_session_replay: Default::default(),
+            stashed_fulfillment_errors: Default::default(),
             canonical_param_env_cache: Default::default(),
             data_layout,
             alloc_map: Lock::new(interpret::AllocMap::new()),
@@ -1203,6 +1233,51 @@ pub fn def_path_debug_str(self, def_id: DefId) -> String {
     pub fn dcx(self) -> &'tcx DiagCtxt {
         self.sess.dcx()
     }
+
+    /// Stashes the diagnostic `err` for a fulfillment error with the obligation span `span`
+    /// together with its structure, so that a later pass can either enrich it via
+    /// [`Self::try_steal_modify_and_emit_fulfillment_error`] or supersede it via
+    /// [`Self::try_steal_replace_and_emit_fulfillment_error`]. If it is never stolen, it
+    /// gets emitted as is together with all other stashed diagnostics.
+    pub fn stash_fulfillment_error(
+        self,
+        span: Span,
+        stashed: StashedFulfillmentError<'tcx>,
+        err: Diag<'_>,
+    ) -> ErrorGuaranteed {
+        self.stashed_fulfillment_errors.lock().insert(span, stashed);
+        err.stash(span, StashKey::FulfillmentError)
+            .expect("stashing an error always returns a guarantee")
+    }
+
+    /// Whether there is a stashed fulfillment error for `span`.
+    pub fn has_stashed_fulfillment_error(self, span: Span) -> bool {
+        self.stashed_fulfillment_errors.lock().contains_key(&span)
+    }
+
+    /// Steals the fulfillment error stashed for `span`, if any, lets `modify_err` enrich
+    /// its diagnostic using its structure and emits it.
+    pub fn try_steal_modify_and_emit_fulfillment_error(
+        self,
+        span: Span,
+        mut modify_err: impl FnMut(&StashedFulfillmentError<'tcx>, &mut Diag<'_>),
+    ) -> Option<ErrorGuaranteed> {
+        let stashed = self.stashed_fulfillment_errors.lock().swap_remove(&span)?;
+        self.dcx().try_steal_modify_and_emit_err(span, StashKey::FulfillmentError, |err| {
+            modify_err(&stashed, err)
+        })
+    }
+
+    /// Cancels the fulfillment error stashed for `span`, if any, in favor of `new_err`,
+    /// which is emitted instead.
+    pub fn try_steal_replace_and_emit_fulfillment_error(
+        self,
+        span: Span,
+        new_err: Diag<'_>,
+    ) -> ErrorGuaranteed {
+        self.stashed_fulfillment_errors.lock().swap_remove(&span);
+        self.dcx().try_steal_replace_and_emit_err(span, StashKey::FulfillmentError, new_err)
+    }
 }
 
 impl<'tcx> TyCtxtAt<'tcx> {
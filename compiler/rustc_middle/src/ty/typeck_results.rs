@@ -154,6 +154,16 @@ pub struct TypeckResults<'tcx> {
     /// of 1 so that we can insert things into the set mutably.
     pub used_trait_imports: UnordSet<LocalDefId>,
 
+    /// The indices into the `caller_bounds` of the param-env of this body of the
+    /// where-clauses used while type-checking it. This is only tracked with the
+    /// new solver and `-Ztrack-used-param-env-clauses`.
+    pub used_param_env_clauses: UnordSet<usize>,
+
+    /// The fully concrete goals proven by the new solver while type-checking this
+    /// body which have not already been proven by an upstream crate, in the order
+    /// in which they have been proven. This is only tracked with `-Zsolver-metadata-cache`.
+    pub proven_global_goals: Vec<ty::Predicate<'tcx>>,
+
     /// If any errors occurred while type-checking this body,
     /// this field will be set to `Some(ErrorGuaranteed)`.
     pub tainted_by_errors: Option<ErrorGuaranteed>,
@@ -235,6 +245,8 @@ pub fn new(hir_owner: OwnerId) -> TypeckResults<'tcx> {
             fru_field_types: Default::default(),
             coercion_casts: Default::default(),
             used_trait_imports: Default::default(),
+            used_param_env_clauses: Default::default(),
+            proven_global_goals: Default::default(),
             tainted_by_errors: None,
             concrete_opaque_types: Default::default(),
             closure_min_captures: Default::default(),
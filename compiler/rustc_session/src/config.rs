@@ -798,6 +798,32 @@ pub enum DumpSolverProofTree {
     Never,
 }
 
+/// Which format to use when dumping proof trees of the new solver,
+/// see `-Z dump-solver-proof-tree-format`.
+#[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum SolverProofTreeFormat {
+    /// The `Debug` output of the proof tree, which contains all of its details.
+    #[default]
+    Debug,
+    /// A compact format which is kept stable across changes to the solver.
+    Stable,
+}
+
+/// How the new solver treats global where-bounds, i.e. where-bounds which do not
+/// mention any generic parameters, when they apply together with impls.
+/// See `-Z solver-global-where-bounds`.
+#[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq)]
+pub enum GlobalWhereBounds {
+    /// Prefer global where-bounds over impls, like all other where-bounds.
+    #[default]
+    Prefer,
+    /// Prefer impls over global where-bounds, matching the old solver.
+    PreferImpls,
+    /// Prefer global where-bounds over impls, but warn for each obligation
+    /// which only holds because of this preference.
+    Flag,
+}
+
 pub enum Input {
     /// Load source code from a file.
     File(PathBuf),
@@ -2896,9 +2922,9 @@ pub(crate) mod dep_tracking {
     use super::{
         BranchProtection, CFGuard, CFProtection, CollapseMacroDebuginfo, CoverageOptions,
         CrateType, DebugInfo, DebugInfoCompression, ErrorOutputType, FunctionReturn,
-        InliningThreshold, InstrumentCoverage, InstrumentXRay, LinkerPluginLto, LocationDetail,
-        LtoCli, NextSolverConfig, OomStrategy, OptLevel, OutFileName, OutputType, OutputTypes,
-        Polonius, RemapPathScopeComponents, ResolveDocLinks, SourceFileHashAlgorithm,
+        GlobalWhereBounds, InliningThreshold, InstrumentCoverage, InstrumentXRay, LinkerPluginLto,
+        LocationDetail, LtoCli, NextSolverConfig, OomStrategy, OptLevel, OutFileName, OutputType,
+        OutputTypes, Polonius, RemapPathScopeComponents, ResolveDocLinks, SourceFileHashAlgorithm,
         SplitDwarfKind, SwitchWithOptPath, SymbolManglingVersion, WasiExecModel,
     };
     use crate::lint;
@@ -3007,6 +3033,7 @@ fn hash(
         OomStrategy,
         LanguageIdentifier,
         NextSolverConfig,
+        GlobalWhereBounds,
         Polonius,
         InliningThreshold,
         FunctionReturn,
@@ -394,6 +394,8 @@ mod desc {
     pub const parse_linker_flavor: &str = ::rustc_target::spec::LinkerFlavorCli::one_of();
     pub const parse_optimization_fuel: &str = "crate=integer";
     pub const parse_dump_mono_stats: &str = "`markdown` (default) or `json`";
+    pub const parse_solver_proof_tree_format: &str = "`debug` (default) or `stable`";
+    pub const parse_global_where_bounds: &str = "`prefer` (default), `prefer-impls`, or `flag`";
     pub const parse_instrument_coverage: &str = parse_bool;
     pub const parse_coverage_options: &str = "`block` | `branch` | `mcdc`";
     pub const parse_instrument_xray: &str = "either a boolean (`yes`, `no`, `on`, `off`, etc), or a comma separated list of settings: `always` or `never` (mutually exclusive), `ignore-loops`, `instruction-threshold=N`, `skip-entry`, `skip-exit`";
@@ -926,6 +928,37 @@ pub(crate) fn parse_dump_mono_stats(slot: &mut DumpMonoStatsFormat, v: Option<&s
         }
     }
 
+    pub(crate) fn parse_solver_proof_tree_format(
+        slot: &mut SolverProofTreeFormat,
+        v: Option<&str>,
+    ) -> bool {
+        match v {
+            None => true,
+            Some("debug") => {
+                *slot = SolverProofTreeFormat::Debug;
+                true
+            }
+            Some("stable") => {
+                *slot = SolverProofTreeFormat::Stable;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
+    pub(crate) fn parse_global_where_bounds(
+        slot: &mut GlobalWhereBounds,
+        v: Option<&str>,
+    ) -> bool {
+        *slot = match v {
+            Some("prefer") => GlobalWhereBounds::Prefer,
+            Some("prefer-impls") => GlobalWhereBounds::PreferImpls,
+            Some("flag") => GlobalWhereBounds::Flag,
+            _ => return false,
+        };
+        true
+    }
+
     pub(crate) fn parse_instrument_coverage(
         slot: &mut InstrumentCoverage,
         v: Option<&str>,
@@ -1628,6 +1661,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "the backend to use"),
     combine_cgu: bool = (false, parse_bool, [TRACKED],
         "combine CGUs into a single one"),
+    compare_solver_goal_summary: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "compare the outcomes of the goals evaluated by the new trait solver with a summary \
+        written by `-Zdump-solver-goal-summary`, warning about each goal whose outcome changed"),
     coverage_options: CoverageOptions = (CoverageOptions::default(), parse_coverage_options, [TRACKED],
         "control details of coverage instrumentation"),
     crate_attr: Vec<String> = (Vec::new(), parse_string_push, [TRACKED],
@@ -1673,6 +1709,16 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "output statistics about monomorphization collection"),
     dump_mono_stats_format: DumpMonoStatsFormat = (DumpMonoStatsFormat::Markdown, parse_dump_mono_stats, [UNTRACKED],
         "the format to use for -Z dump-mono-stats (`markdown` (default) or `json`)"),
+    dump_solver_goal_summary: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "write the outcomes of the goals evaluated by the new trait solver in each body \
+        to the given file"),
+    dump_solver_proof_tree_format: SolverProofTreeFormat = (SolverProofTreeFormat::Debug,
+        parse_solver_proof_tree_format, [UNTRACKED],
+        "the format used when dumping the new solver's proof trees: `debug` (default) or \
+        `stable`, a compact format intended for UI tests"),
+    dump_solver_proof_trees_span: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "only dump the new solver's proof trees for obligations whose span starts at \
+        `file:line:col`, where `line` and `col` are optional (default: no)"),
     dwarf_version: Option<u32> = (None, parse_opt_number, [TRACKED],
         "version of DWARF debug information to emit (default: 2 or 4, depending on platform)"),
     dylib_lto: bool = (false, parse_bool, [UNTRACKED],
@@ -1760,6 +1806,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
          `=skip-entry`
          `=skip-exit`
          Multiple options can be combined with commas."),
+    json_ambiguity_reports: bool = (false, parse_bool, [UNTRACKED],
+        "emit a machine-readable report for each ambiguity or overflow error of the \
+        new trait solver when using `--error-format=json` (default: no)"),
     layout_seed: Option<u64> = (None, parse_opt_number, [TRACKED],
         "seed layout randomization"),
     link_directives: bool = (true, parse_bool, [TRACKED],
@@ -1817,6 +1866,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "emit noalias metadata for mutable references (default: yes)"),
     next_solver: Option<NextSolverConfig> = (None, parse_next_solver_config, [TRACKED],
         "enable and configure the next generation trait solver used by rustc"),
+    next_solver_shallowest_errors: bool = (false, parse_bool, [UNTRACKED],
+        "when reporting errors with the next trait solver, prefer the shallowest failing \
+        nested goal over the first one found by a depth-first search (default: no)"),
     nll_facts: bool = (false, parse_bool, [UNTRACKED],
         "dump facts from NLL analysis into side files (default: no)"),
     nll_facts_dir: String = ("nll-facts".to_string(), parse_string, [UNTRACKED],
@@ -1910,6 +1962,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "enable queries of the dependency graph for regression testing (default: no)"),
     randomize_layout: bool = (false, parse_bool, [TRACKED],
         "randomize the layout of types (default: no)"),
+    record_solver_session: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "write the obligations registered with and the goals evaluated by the new trait \
+        solver's fulfillment contexts to the given file, for `-Zreplay-solver-session`"),
     relax_elf_relocations: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "whether ELF relocations can be relaxed"),
     remap_cwd_prefix: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
@@ -1919,6 +1974,10 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
     remark_dir: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
         "directory into which to write optimization remarks (if not specified, they will be \
 written to standard error output)"),
+    replay_solver_session: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "evaluate the goals of a session recorded with `-Zrecord-solver-session` again in \
+        isolation while compiling the same crate, warning if their outcome differs from the \
+        recorded one"),
     sanitizer: SanitizerSet = (SanitizerSet::empty(), parse_sanitizers, [TRACKED],
         "use a sanitizer"),
     sanitizer_cfi_canonical_jump_tables: Option<bool> = (Some(true), parse_opt_bool, [TRACKED],
@@ -1960,6 +2019,26 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
     simulate_remapped_rust_src_base: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
         "simulate the effect of remap-debuginfo = true at bootstrapping by remapping path \
         to rust's source base directory. only meant for testing purposes"),
+    solver_check_obligation_order: bool = (false, parse_bool, [UNTRACKED],
+        "check that the outcome of the new trait solver's fulfillment loop does not depend \
+        on the order of the obligations by also evaluating them in shuffled orders (default: no)"),
+    solver_global_where_bounds: GlobalWhereBounds = (GlobalWhereBounds::Prefer,
+        parse_global_where_bounds, [TRACKED],
+        "whether the new trait solver prefers global where-bounds over impls: `prefer` \
+        (default), `prefer-impls` like the old solver, or `flag` to prefer them while \
+        warning for each obligation relying on this preference"),
+    solver_metadata_cache: bool = (false, parse_bool, [TRACKED],
+        "store fully concrete goals proven by the new trait solver in the crate metadata \
+        and reuse the goals proven by upstream crates (default: no)"),
+    solver_pass_warning_threshold: Option<usize> = (None, parse_opt_number, [UNTRACKED],
+        "warn if the new trait solver's fulfillment loop needs more than this number of \
+        fixpoint iterations, listing the obligations which keep making progress"),
+    solver_span_profile: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "write the time spent by the new trait solver on the obligations of each span \
+        to the given file as JSON"),
+    solver_step_limit: Option<usize> = (None, parse_opt_number, [TRACKED],
+        "give up with an overflow error once the new trait solver computed more than this \
+        number of goals while proving a single obligation (default: no limit)"),
     span_debug: bool = (false, parse_bool, [UNTRACKED],
         "forward proc_macro::Span's `Debug` impl to `Span`"),
     /// o/w tests have closure@path
@@ -2012,6 +2091,10 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "measure time of each rustc pass (default: no)"),
     time_passes_format: TimePassesFormat = (TimePassesFormat::Text, parse_time_passes_format, [UNTRACKED],
         "the format to use for -Z time-passes (`text` (default) or `json`)"),
+    time_solver: bool = (false, parse_bool, [UNTRACKED],
+        "measure the duration of each fixpoint iteration of the new trait solver's \
+        fulfillment loop, reporting them per body in the output of `-Z time-passes` \
+        and in self-profiles (default: no)"),
     tiny_const_eval_limit: bool = (false, parse_bool, [TRACKED],
         "sets a tiny, non-configurable limit for const eval; useful for compiler tests"),
     #[rustc_lint_opt_deny_field_access("use `Session::tls_model` instead of this field")]
@@ -2021,6 +2104,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
         "for every macro invocation, print its name and arguments (default: no)"),
     track_diagnostics: bool = (false, parse_bool, [UNTRACKED],
         "tracks where in rustc a diagnostic was emitted"),
+    track_used_param_env_clauses: bool = (false, parse_bool, [TRACKED],
+        "record which where-clauses are used when type checking bodies with the new \
+        trait solver, see the `used_param_env_clauses` query (default: no)"),
     // Diagnostics are considered side-effects of a query (see `QuerySideEffects`) and are saved
     // alongside query results and changes to translation options can affect diagnostics - so
     // translation options should be tracked.
@@ -2082,6 +2168,9 @@ pub(crate) fn parse_wasm_c_abi(slot: &mut WasmCAbi, v: Option<&str>) -> bool {
     #[rustc_lint_opt_deny_field_access("use `Session::verify_llvm_ir` instead of this field")]
     verify_llvm_ir: bool = (false, parse_bool, [TRACKED],
         "verify LLVM IR (default: no)"),
+    verify_next_solver_vtables: bool = (false, parse_bool, [UNTRACKED],
+        "check that the vtable layout computed with `-Znext-solver=globally` matches \
+        the one computed by the old trait solver (default: no)"),
     virtual_function_elimination: bool = (false, parse_bool, [TRACKED],
         "enables dead virtual function elimination optimization. \
         Requires `-Clto[=[fat,yes]]`"),
@@ -59,6 +59,15 @@ trait_selection_no_value_in_rustc_on_unimplemented = this attribute must have a
     .label = expected value here
     .note = eg `#[rustc_on_unimplemented(message="foo")]`
 
+trait_selection_solver_global_where_bound_preference = `{$predicate}` relies on preferring the global where-bound `{$clause}` over impls
+    .note = the old trait solver prefers impls over where-bounds which do not mention any generic parameters
+
+trait_selection_solver_pass_count = the new trait solver needed {$passes} fixpoint iterations for this body
+
+trait_selection_solver_pass_count_obligation = `{$predicate}` made progress in {$count} iterations
+
+trait_selection_solver_pass_count_other_obligations = and {$count} other obligations made progress
+
 trait_selection_trait_has_no_impls = this trait has no implementations, consider adding one
 
 trait_selection_ty_alias_overflow = in case this is a recursive type alias, consider using a struct, enum, or union instead
@@ -170,3 +170,40 @@ pub(crate) struct AsyncClosureNotFn {
     pub span: Span,
     pub kind: &'static str,
 }
+
+#[derive(Diagnostic)]
+#[diag(trait_selection_solver_global_where_bound_preference)]
+#[note]
+pub(crate) struct SolverGlobalWhereBoundPreference<'tcx> {
+    #[primary_span]
+    pub span: Span,
+    pub predicate: ty::Predicate<'tcx>,
+    pub clause: ty::Clause<'tcx>,
+}
+
+#[derive(Diagnostic)]
+#[diag(trait_selection_solver_pass_count)]
+pub(crate) struct SolverPassCount<'tcx> {
+    #[primary_span]
+    pub span: Span,
+    pub passes: usize,
+    #[subdiagnostic]
+    pub obligations: Vec<SolverPassCountObligation<'tcx>>,
+    #[subdiagnostic]
+    pub other_obligations: Option<SolverPassCountOtherObligations>,
+}
+
+#[derive(Subdiagnostic)]
+#[note(trait_selection_solver_pass_count_obligation)]
+pub(crate) struct SolverPassCountObligation<'tcx> {
+    #[primary_span]
+    pub span: Span,
+    pub predicate: ty::Predicate<'tcx>,
+    pub count: usize,
+}
+
+#[derive(Subdiagnostic)]
+#[note(trait_selection_solver_pass_count_other_obligations)]
+pub(crate) struct SolverPassCountOtherObligations {
+    pub count: usize,
+}
@@ -0,0 +1,114 @@
+//! Machine-readable reports for ambiguity and overflow errors of the new solver,
+//! see `-Zjson-ambiguity-reports`.
+//!
+//! Tools like IDEs use these to decide whether to offer a fix, e.g. adding a
+//! turbofish, without having to parse the rendered diagnostic.
+
+use std::ops::ControlFlow;
+
+use rustc_errors::emitter::AmbiguityReport;
+use rustc_infer::infer::canonical::OriginalQueryValues;
+use rustc_infer::infer::InferCtxt;
+use rustc_infer::traits::{FulfillmentError, FulfillmentErrorCode};
+use rustc_middle::traits::solve::inspect::ProbeKind;
+use rustc_middle::traits::solve::{CandidateSource, Certainty};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+use super::inspect::{InspectGoal, ProofTreeInferCtxtExt, ProofTreeVisitor};
+
+pub fn is_enabled(tcx: TyCtxt<'_>) -> bool {
+    tcx.sess.opts.unstable_opts.json_ambiguity_reports
+}
+
+/// Emits a report for an ambiguity or overflow error. This only emits anything if
+/// the error has been computed by the new solver and therefore has a proof tree.
+pub fn emit_ambiguity_report<'tcx>(infcx: &InferCtxt<'tcx>, error: &FulfillmentError<'tcx>) {
+    let (FulfillmentErrorCode::Ambiguity { overflow }, Some(proof_tree)) =
+        (&error.code, &error.proof_tree)
+    else {
+        return;
+    };
+
+    let tcx = infcx.tcx;
+    let span = error.obligation.cause.span;
+    let (goal, candidate_sources) = infcx.probe(|_| {
+        let mut visitor = AmbiguousLeaf { span };
+        infcx.visit_existing_proof_tree(proof_tree, &mut visitor).break_value().unwrap_or_else(
+            || {
+                let goal = infcx.canonicalize_query(
+                    error.obligation.param_env.and(error.obligation.predicate),
+                    &mut OriginalQueryValues::default(),
+                );
+                (goal.value.value.to_string(), vec![])
+            },
+        )
+    });
+    let blocking_infer_vars = error
+        .blocking_infer_vars
+        .iter()
+        .filter_map(|&ty| Some((format!("{ty:?}"), infcx.type_var_origin(ty)?.span)))
+        .collect();
+
+    tcx.dcx().emit_ambiguity_report(&AmbiguityReport {
+        span,
+        overflow: overflow.is_some(),
+        goal,
+        candidate_sources,
+        blocking_infer_vars,
+    });
+}
+
+/// Finds the first ambiguous goal without a unique applicable candidate by following
+/// the unique applicable candidate of each ambiguous goal, returning its canonical
+/// form and the sources of its candidates which may apply.
+struct AmbiguousLeaf {
+    span: Span,
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for AmbiguousLeaf {
+    type Result = ControlFlow<(String, Vec<(String, Option<Span>)>)>;
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) -> Self::Result {
+        let Ok(Certainty::Maybe(_)) = goal.result() else {
+            return ControlFlow::Continue(());
+        };
+
+        if let Some(candidate) = goal.unique_applicable_candidate() {
+            candidate.visit_nested_no_probe(self)?;
+        }
+
+        let infcx = goal.infcx();
+        let tcx = infcx.tcx;
+        let canonical = infcx.canonicalize_query(
+            goal.goal().param_env.and(goal.goal().predicate),
+            &mut OriginalQueryValues::default(),
+        );
+        let candidate_sources = goal
+            .candidates()
+            .iter()
+            .filter(|candidate| candidate.result().is_ok())
+            .filter_map(|candidate| match candidate.kind() {
+                ProbeKind::TraitCandidate { source, result: _ } => Some(source),
+                _ => None,
+            })
+            .map(|source| match source {
+                CandidateSource::Impl(def_id) => {
+                    (format!("impl `{}`", tcx.def_path_str(def_id)), Some(tcx.def_span(def_id)))
+                }
+                CandidateSource::BuiltinImpl(_) => ("builtin impl".to_string(), None),
+                CandidateSource::ParamEnv(idx) => {
+                    let clause = goal.goal().param_env.caller_bounds()[idx];
+                    (format!("where-clause `{clause}`"), None)
+                }
+                CandidateSource::AliasBound => ("alias bound".to_string(), None),
+                CandidateSource::CoherenceUnknowable => ("unknowable impl".to_string(), None),
+            })
+            .collect();
+        ControlFlow::Break((canonical.value.value.to_string(), candidate_sources))
+    }
+}
@@ -6,13 +6,14 @@
 use rustc_infer::traits::query::NoSolution;
 use rustc_middle::traits::solve::inspect::ProbeKind;
 use rustc_middle::traits::solve::{
-    CandidateSource, CanonicalResponse, Certainty, Goal, MaybeCause, QueryResult,
+    AmbiguityCause, CandidateSource, CanonicalResponse, Certainty, Goal, MaybeCause, QueryResult,
 };
 use rustc_middle::traits::BuiltinImplSource;
 use rustc_middle::ty::fast_reject::{SimplifiedType, TreatParams};
 use rustc_middle::ty::{self, Ty, TyCtxt};
 use rustc_middle::ty::{fast_reject, TypeFoldable};
 use rustc_middle::ty::{ToPredicate, TypeVisitableExt};
+use rustc_session::config::GlobalWhereBounds;
 use rustc_span::{ErrorGuaranteed, DUMMY_SP};
 use std::fmt::Debug;
 use std::mem;
@@ -86,7 +87,7 @@ fn probe_and_consider_object_bound_candidate(
                 bug!("expected object type in `probe_and_consider_object_bound_candidate`");
             };
             ecx.add_goals(
-                GoalSource::ImplWhereBound,
+                GoalSource::ObjectBound,
                 structural_traits::predicates_for_object_candidate(
                     ecx,
                     goal.param_env,
@@ -283,7 +284,10 @@ pub(super) fn assemble_and_evaluate_candidates<G: GoalKind<'tcx>>(
 
         if normalized_self_ty.is_ty_var() {
             debug!("self type has been normalized to infer");
-            return self.forced_ambiguity(MaybeCause::Ambiguity).into_iter().collect();
+            return self
+                .forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::UnresolvedType))
+                .into_iter()
+                .collect();
         }
 
         let goal =
@@ -304,6 +308,8 @@ pub(super) fn assemble_and_evaluate_candidates<G: GoalKind<'tcx>>(
 
         self.assemble_blanket_impl_candidates(goal, &mut candidates);
 
+        self.assemble_extra_impl_candidates(goal, &mut candidates);
+
         self.assemble_param_env_candidates(goal, &mut candidates);
 
         match self.solver_mode() {
@@ -470,6 +476,23 @@ fn assemble_blanket_impl_candidates<G: GoalKind<'tcx>>(
         }
     }
 
+    /// Assembles the impls contributed by external drivers via the
+    /// `extra_impls_for_new_solver` query.
+    #[instrument(level = "debug", skip_all)]
+    fn assemble_extra_impl_candidates<G: GoalKind<'tcx>>(
+        &mut self,
+        goal: Goal<'tcx, G>,
+        candidates: &mut Vec<Candidate<'tcx>>,
+    ) {
+        let tcx = self.tcx();
+        for &impl_def_id in tcx.extra_impls_for_new_solver(goal.predicate.trait_def_id(tcx)) {
+            match G::consider_impl_candidate(self, goal, impl_def_id) {
+                Ok(candidate) => candidates.push(candidate),
+                Err(NoSolution) => (),
+            }
+        }
+    }
+
     #[instrument(level = "debug", skip_all)]
     fn assemble_builtin_impl_candidates<G: GoalKind<'tcx>>(
         &mut self,
@@ -756,6 +779,10 @@ fn assemble_object_bound_candidates<G: GoalKind<'tcx>>(
     ///
     /// To do so we add an ambiguous candidate in case such an unknown impl could
     /// apply to the current goal.
+    ///
+    /// With `feature(with_negative_coherence)`, an explicit negative impl for the
+    /// trait ref is a promise that no such impl will be added, so we don't add the
+    /// ambiguous candidate in this case.
     #[instrument(level = "debug", skip_all)]
     fn assemble_coherence_unknowable_candidates<G: GoalKind<'tcx>>(
         &mut self,
@@ -769,6 +796,10 @@ fn assemble_coherence_unknowable_candidates<G: GoalKind<'tcx>>(
                 let trait_ref = goal.predicate.trait_ref(tcx);
                 if ecx.trait_ref_is_knowable(goal.param_env, trait_ref)? {
                     Err(NoSolution)
+                } else if tcx.features().with_negative_coherence
+                    && ecx.explicit_negative_impl_for(goal.param_env, trait_ref).is_some()
+                {
+                    Err(NoSolution)
                 } else {
                     ecx.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS)
                 }
@@ -802,6 +833,34 @@ fn discard_impls_shadowed_by_env<G: GoalKind<'tcx>>(
         let mut trait_candidates_from_env = Vec::new();
         self.assemble_param_env_candidates(trait_goal, &mut trait_candidates_from_env);
         self.assemble_alias_bound_candidates(trait_goal, &mut trait_candidates_from_env);
+
+        let is_global_where_bound = |candidate: &Candidate<'tcx>| match candidate.source {
+            CandidateSource::ParamEnv(i) => goal.param_env.caller_bounds()[i].is_global(),
+            _ => false,
+        };
+        if tcx.sess.opts.unstable_opts.solver_global_where_bounds == GlobalWhereBounds::PreferImpls
+        {
+            // Like the old solver, global where-bounds neither shadow impls
+            // nor get used if there is an applicable impl.
+            trait_candidates_from_env.retain(|c| !is_global_where_bound(c));
+            if candidates.iter().any(|c| {
+                matches!(c.source, CandidateSource::Impl(_) | CandidateSource::BuiltinImpl(_))
+            }) {
+                candidates.retain(|c| !is_global_where_bound(c));
+            }
+        }
+        // If the env only proves the goal via global where-bounds, we record
+        // that the goal relies on the preference for these where-bounds in
+        // case this causes us to discard any impl candidates.
+        let global_where_bound = match trait_candidates_from_env.first() {
+            Some(&Candidate { source: CandidateSource::ParamEnv(i), .. })
+                if trait_candidates_from_env.iter().all(is_global_where_bound) =>
+            {
+                Some(i)
+            }
+            _ => None,
+        };
+        let mut shadowed_by_global_where_bound = None;
         if !trait_candidates_from_env.is_empty() {
             let trait_env_result = self.merge_candidates(trait_candidates_from_env);
             match trait_env_result.unwrap().value.certainty {
@@ -812,6 +871,7 @@ fn discard_impls_shadowed_by_env<G: GoalKind<'tcx>>(
                 // a forced ambiguity candidate added earlier.
                 // This feels dangerous.
                 Certainty::Yes => {
+                    let num_candidates = candidates.len();
                     candidates.retain(|c| match c.source {
                         CandidateSource::Impl(_) | CandidateSource::BuiltinImpl(_) => {
                             debug!(?c, "discard impl candidate");
@@ -820,6 +880,9 @@ fn discard_impls_shadowed_by_env<G: GoalKind<'tcx>>(
                         CandidateSource::ParamEnv(_) | CandidateSource::AliasBound => true,
                         CandidateSource::CoherenceUnknowable => bug!("uh oh"),
                     });
+                    if candidates.len() < num_candidates {
+                        shadowed_by_global_where_bound = global_where_bound;
+                    }
                 }
                 // If it is still ambiguous we instead just force the whole goal
                 // to be ambig and wait for inference constraints. See
@@ -831,6 +894,11 @@ fn discard_impls_shadowed_by_env<G: GoalKind<'tcx>>(
             }
         }
         self.inspect = inspect;
+
+        if let Some(param_env_index) = shadowed_by_global_where_bound {
+            self.probe(|&()| ProbeKind::ShadowedByGlobalWhereBound { param_env_index })
+                .enter(|_| ());
+        }
     }
 
     /// If there are multiple ways to prove a trait or projection goal, we have
@@ -374,6 +374,7 @@ fn register_new_opaque_types(
         opaque_types: &[(ty::OpaqueTypeKey<'tcx>, Ty<'tcx>)],
     ) {
         for &(key, ty) in opaque_types {
+            self.record_opaque_type_constraint(key, ty);
             self.insert_hidden_type(key, param_env, ty).unwrap();
         }
     }
@@ -1,4 +1,6 @@
+use rustc_data_structures::fx::FxIndexSet;
 use rustc_data_structures::stack::ensure_sufficient_stack;
+use rustc_data_structures::unord::UnordSet;
 use rustc_hir::def_id::DefId;
 use rustc_infer::infer::at::ToTrace;
 use rustc_infer::infer::canonical::CanonicalVarValues;
@@ -14,9 +16,10 @@
 use rustc_middle::infer::unify_key::ConstVariableOrigin;
 use rustc_middle::traits::solve::inspect;
 use rustc_middle::traits::solve::{
-    CanonicalInput, CanonicalResponse, Certainty, PredefinedOpaques, PredefinedOpaquesData,
-    QueryResult,
+    CanonicalInput, CanonicalResponse, Certainty, ExternalConstraintsData, PredefinedOpaques,
+    PredefinedOpaquesData, QueryInput, QueryResult,
 };
+use rustc_middle::query::{LocalCrate, Providers};
 use rustc_middle::traits::specialization_graph;
 use rustc_middle::ty::{
     self, InferCtxtLike, OpaqueTypeKey, Ty, TyCtxt, TypeFoldable, TypeSuperVisitable,
@@ -24,17 +27,20 @@
 };
 use rustc_session::config::DumpSolverProofTree;
 use rustc_span::DUMMY_SP;
-use std::io::Write;
 use std::ops::ControlFlow;
 
 use crate::traits::coherence;
+use crate::traits::error_reporting::print_proof_tree;
 use crate::traits::vtable::{count_own_vtable_entries, prepare_vtable_segments, VtblSegment};
 
 use super::inspect::ProofTreeBuilder;
 use super::{search_graph, GoalEvaluationKind, FIXPOINT_STEP_LIMIT};
 use super::{search_graph::SearchGraph, Goal};
-use super::{GoalSource, SolverMode};
-pub use select::InferCtxtSelectExt;
+use super::{GoalSource, Interrupted, SolverCancellationToken, SolverMode};
+pub(super) use select::selected_candidate_source;
+pub use select::{
+    CandidateDiscardReason, CandidateExplanation, CandidatePreferenceRule, InferCtxtSelectExt,
+};
 
 pub(super) mod canonical;
 mod probe;
@@ -133,6 +139,130 @@ pub enum GenerateProofTree {
     Never,
 }
 
+pub(crate) fn provide(providers: &mut Providers) {
+    *providers = Providers {
+        evaluate_root_canonical_goal_in_new_solver,
+        extra_impls_for_new_solver: |_, _| &[],
+        proven_global_goals_in_new_solver,
+        upstream_proven_global_goals_in_new_solver,
+        ..*providers
+    };
+}
+
+/// Evaluates a canonical root goal using a new search graph. As the search graph is
+/// empty, the result of this does not depend on the surrounding evaluation, allowing
+/// it to be used as a query. This does not support building proof trees.
+///
+/// With `-Zsolver-metadata-cache`, fully concrete goals which have already been proven
+/// by an upstream crate are not evaluated again, see `proven_global_goal`.
+fn evaluate_root_canonical_goal_in_new_solver<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    canonical_input: CanonicalInput<'tcx>,
+) -> QueryResult<'tcx> {
+    if is_upstream_proven_global_goal(tcx, canonical_input) {
+        return Ok(super::response_no_constraints_raw(
+            tcx,
+            canonical_input.max_universe,
+            canonical_input.variables,
+            Certainty::Yes,
+        ));
+    }
+
+    let mut search_graph = search_graph::SearchGraph::new(SolverMode::Normal);
+    let result = EvalCtxt::evaluate_canonical_goal(
+        tcx,
+        &mut search_graph,
+        canonical_input,
+        &mut ProofTreeBuilder::new_noop(),
+    );
+    assert!(search_graph.is_empty());
+    result
+}
+
+/// With `-Zsolver-metadata-cache`, returns the predicate of `goal` if it is a fully concrete
+/// goal which holds without any constraints and which has not already been proven by an
+/// upstream crate. These goals are recorded in the `TypeckResults` of each body and get
+/// stored in the crate metadata, see the `proven_global_goals_in_new_solver` query.
+///
+/// This has to be called right after proving `goal`, so its result is cached.
+pub(in crate::solve) fn proven_global_goal<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+) -> Option<ty::Predicate<'tcx>> {
+    let tcx = infcx.tcx;
+    if !tcx.sess.opts.unstable_opts.solver_metadata_cache {
+        return None;
+    }
+
+    let (canonical_input, _) =
+        EvalCtxt::enter_root(infcx, SolverMode::Normal, GenerateProofTree::Never, None, |ecx| {
+            ecx.canonicalize_goal(goal).1
+        });
+    if !is_global_goal(canonical_input) || is_upstream_proven_global_goal(tcx, canonical_input) {
+        return None;
+    }
+
+    match tcx.evaluate_root_canonical_goal_in_new_solver(canonical_input) {
+        Ok(response)
+            if response.value.certainty == Certainty::Yes
+                && *response.value.external_constraints == ExternalConstraintsData::default() =>
+        {
+            Some(canonical_input.value.goal.predicate)
+        }
+        _ => None,
+    }
+}
+
+fn is_upstream_proven_global_goal<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    canonical_input: CanonicalInput<'tcx>,
+) -> bool {
+    tcx.sess.opts.unstable_opts.solver_metadata_cache
+        && is_global_goal(canonical_input)
+        && tcx
+            .upstream_proven_global_goals_in_new_solver(())
+            .contains(&canonical_input.value.goal.predicate)
+}
+
+/// Whether the result of this goal does not depend on the crate in which it is
+/// evaluated, as long as it holds. Such goals may be stored in the crate metadata.
+fn is_global_goal<'tcx>(canonical_input: CanonicalInput<'tcx>) -> bool {
+    let QueryInput { goal, predefined_opaques_in_body } = canonical_input.value;
+    canonical_input.variables.is_empty()
+        && canonical_input.defining_opaque_types.is_empty()
+        && predefined_opaques_in_body.opaque_types.is_empty()
+        && goal.param_env == ty::ParamEnv::empty()
+        && !goal.predicate.has_param()
+        && !goal.predicate.has_opaque_types()
+        && !goal.predicate.references_error()
+}
+
+fn proven_global_goals_in_new_solver<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    _: LocalCrate,
+) -> &'tcx [ty::Predicate<'tcx>] {
+    let mut goals = FxIndexSet::default();
+    for def_id in tcx.hir().body_owners() {
+        // Nested bodies, e.g. closures, are type-checked together with their typeck root.
+        if tcx.is_typeck_child(def_id.to_def_id()) {
+            continue;
+        }
+        goals.extend(tcx.typeck(def_id).proven_global_goals.iter().copied());
+    }
+    tcx.arena.alloc_from_iter(goals)
+}
+
+fn upstream_proven_global_goals_in_new_solver<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    (): (),
+) -> UnordSet<ty::Predicate<'tcx>> {
+    tcx.crates(())
+        .iter()
+        .flat_map(|&cnum| tcx.proven_global_goals_in_new_solver(cnum))
+        .copied()
+        .collect()
+}
+
 #[extension(pub trait InferCtxtEvalExt<'tcx>)]
 impl<'tcx> InferCtxt<'tcx> {
     /// Evaluates a goal from **outside** of the trait solver.
@@ -145,10 +275,70 @@ fn evaluate_root_goal(
         goal: Goal<'tcx, ty::Predicate<'tcx>>,
         generate_proof_tree: GenerateProofTree,
     ) -> (Result<(bool, Certainty), NoSolution>, Option<inspect::GoalEvaluation<'tcx>>) {
-        EvalCtxt::enter_root(self, generate_proof_tree, |ecx| {
+        self.evaluate_root_goal_in_mode(goal, SolverMode::for_infcx(self), generate_proof_tree)
+    }
+
+    /// Like [`InferCtxtEvalExt::evaluate_root_goal`], but evaluates the goal using
+    /// `solver_mode` instead of the mode of this inference context. This allows
+    /// using the same inference context for both coherence and ordinary goals.
+    #[instrument(level = "debug", skip(self))]
+    fn evaluate_root_goal_in_mode(
+        &self,
+        goal: Goal<'tcx, ty::Predicate<'tcx>>,
+        solver_mode: SolverMode,
+        generate_proof_tree: GenerateProofTree,
+    ) -> (Result<(bool, Certainty), NoSolution>, Option<inspect::GoalEvaluation<'tcx>>) {
+        EvalCtxt::enter_root(self, solver_mode, generate_proof_tree, None, |ecx| {
             ecx.evaluate_goal(GoalEvaluationKind::Root, GoalSource::Misc, goal)
         })
     }
+
+    /// Like [`InferCtxtEvalExt::evaluate_root_goal_in_mode`], but stops proving
+    /// goals once `cancellation` has been cancelled, returning [`Interrupted`].
+    /// This allows hosts, e.g. IDEs, to abandon the evaluation of expensive goals.
+    #[instrument(level = "debug", skip(self, cancellation))]
+    fn evaluate_root_goal_cancellable(
+        &self,
+        goal: Goal<'tcx, ty::Predicate<'tcx>>,
+        solver_mode: SolverMode,
+        generate_proof_tree: GenerateProofTree,
+        cancellation: &SolverCancellationToken,
+    ) -> Result<
+        (Result<(bool, Certainty), NoSolution>, Option<inspect::GoalEvaluation<'tcx>>),
+        Interrupted,
+    > {
+        let ((result, interrupted), proof_tree) = EvalCtxt::enter_root(
+            self,
+            solver_mode,
+            generate_proof_tree,
+            Some(cancellation),
+            |ecx| {
+                let result = ecx.evaluate_goal(GoalEvaluationKind::Root, GoalSource::Misc, goal);
+                (result, ecx.search_graph.interrupted())
+            },
+        );
+        if interrupted { Err(Interrupted) } else { Ok((result, proof_tree)) }
+    }
+
+    /// Evaluates multiple goals from **outside** of the trait solver, sharing a single
+    /// root evaluation context between them. This is cheaper than evaluating each goal
+    /// via [`InferCtxtEvalExt::evaluate_root_goal_in_mode`] but does not support proof
+    /// trees. `on_result` is called with the result of each goal right after it has
+    /// been evaluated.
+    #[instrument(level = "debug", skip(self, goals, on_result))]
+    fn evaluate_root_goals_batched(
+        &self,
+        goals: impl IntoIterator<Item = Goal<'tcx, ty::Predicate<'tcx>>>,
+        solver_mode: SolverMode,
+        mut on_result: impl FnMut(Result<(bool, Certainty), NoSolution>),
+    ) {
+        let ((), _) =
+            EvalCtxt::enter_root(self, solver_mode, GenerateProofTree::Never, None, |ecx| {
+                for goal in goals {
+                    on_result(ecx.evaluate_goal(GoalEvaluationKind::Root, GoalSource::Misc, goal));
+                }
+            });
+    }
 }
 
 impl<'a, 'tcx> EvalCtxt<'a, 'tcx> {
@@ -165,11 +355,15 @@ pub(super) fn set_is_normalizes_to_goal(&mut self) {
     /// over using this manually (such as [`InferCtxtEvalExt::evaluate_root_goal`]).
     pub(super) fn enter_root<R>(
         infcx: &InferCtxt<'tcx>,
+        solver_mode: SolverMode,
         generate_proof_tree: GenerateProofTree,
+        cancellation: Option<&SolverCancellationToken>,
         f: impl FnOnce(&mut EvalCtxt<'_, 'tcx>) -> R,
     ) -> (R, Option<inspect::GoalEvaluation<'tcx>>) {
-        let mode = if infcx.intercrate { SolverMode::Coherence } else { SolverMode::Normal };
-        let mut search_graph = search_graph::SearchGraph::new(mode);
+        let mut search_graph = search_graph::SearchGraph::new(solver_mode);
+        if let Some(cancellation) = cancellation {
+            search_graph.set_cancellation(cancellation.clone());
+        }
 
         let mut ecx = EvalCtxt {
             infcx,
@@ -191,13 +385,14 @@ pub(super) fn enter_root<R>(
         let result = f(&mut ecx);
 
         let tree = ecx.inspect.finalize();
+        // With `-Zdump-solver-proof-trees-span`, proof trees are instead dumped by the
+        // fulfillment context for the obligations with a matching span.
         if let (Some(tree), DumpSolverProofTree::Always) = (
             &tree,
             infcx.tcx.sess.opts.unstable_opts.next_solver.map(|c| c.dump_tree).unwrap_or_default(),
-        ) {
-            let mut lock = std::io::stdout().lock();
-            let _ = lock.write_fmt(format_args!("{tree:?}\n"));
-            let _ = lock.flush();
+        ) && infcx.tcx.sess.opts.unstable_opts.dump_solver_proof_trees_span.is_none()
+        {
+            print_proof_tree(infcx.tcx, tree);
         }
 
         assert!(
@@ -349,12 +544,22 @@ pub(super) fn evaluate_goal_raw(
         let (orig_values, canonical_goal) = self.canonicalize_goal(goal);
         let mut goal_evaluation =
             self.inspect.new_goal_evaluation(goal, &orig_values, goal_evaluation_kind);
-        let canonical_response = EvalCtxt::evaluate_canonical_goal(
-            self.tcx(),
-            self.search_graph,
-            canonical_goal,
-            &mut goal_evaluation,
-        );
+        // Root goals are evaluated using a new search graph, so we can use a query for
+        // them to reuse their result across incremental compilation sessions. This is
+        // not possible if we need to build a proof tree for them.
+        let canonical_response = if goal_evaluation_kind == GoalEvaluationKind::Root
+            && goal_evaluation.is_noop()
+            && self.search_graph.can_use_root_goal_query()
+        {
+            self.tcx().evaluate_root_canonical_goal_in_new_solver(canonical_goal)
+        } else {
+            EvalCtxt::evaluate_canonical_goal(
+                self.tcx(),
+                self.search_graph,
+                canonical_goal,
+                &mut goal_evaluation,
+            )
+        };
         let canonical_response = match canonical_response {
             Err(e) => {
                 self.inspect.goal_evaluation(goal_evaluation);
@@ -436,8 +641,8 @@ fn compute_goal(&mut self, goal: Goal<'tcx, ty::Predicate<'tcx>>) -> QueryResult
                 ty::PredicateKind::Clause(ty::ClauseKind::ConstEvaluatable(ct)) => {
                     self.compute_const_evaluatable_goal(Goal { param_env, predicate: ct })
                 }
-                ty::PredicateKind::ConstEquate(_, _) => {
-                    bug!("ConstEquate should not be emitted when `-Znext-solver` is active")
+                ty::PredicateKind::ConstEquate(a, b) => {
+                    self.compute_const_equate_goal(Goal { param_env, predicate: (a, b) })
                 }
                 ty::PredicateKind::NormalizesTo(predicate) => {
                     self.compute_normalizes_to_goal(Goal { param_env, predicate })
@@ -452,7 +657,9 @@ fn compute_goal(&mut self, goal: Goal<'tcx, ty::Predicate<'tcx>>) -> QueryResult
                 }
             }
         } else {
+            let bound_vars = kind.bound_vars();
             self.infcx.enter_forall(kind, |kind| {
+                self.inspect.enter_universe(self.infcx.universe(), bound_vars);
                 let goal = goal.with(self.tcx(), ty::Binder::dummy(kind));
                 self.add_goal(GoalSource::InstantiateHigherRanked, goal);
                 self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
@@ -976,6 +1183,22 @@ pub(super) fn insert_hidden_type(
         Ok(())
     }
 
+    /// Record that `opaque_type_key` got constrained to `hidden_ty` in the
+    /// proof tree. This is only used to explain opaque type inference in
+    /// diagnostics and does not constrain anything by itself.
+    pub(super) fn record_opaque_type_constraint(
+        &mut self,
+        opaque_type_key: OpaqueTypeKey<'tcx>,
+        hidden_ty: Ty<'tcx>,
+    ) {
+        self.inspect.record_opaque_type_constraint(
+            self.infcx,
+            self.max_input_universe,
+            opaque_type_key,
+            hidden_ty,
+        );
+    }
+
     pub(super) fn add_item_bounds_for_hidden_type(
         &mut self,
         opaque_def_id: DefId,
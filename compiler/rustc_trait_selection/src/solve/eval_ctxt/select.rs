@@ -3,12 +3,14 @@
 use rustc_hir::def_id::DefId;
 use rustc_infer::infer::{DefineOpaqueTypes, InferCtxt, InferOk};
 use rustc_infer::traits::solve::inspect::ProbeKind;
+use rustc_infer::traits::query::NoSolution;
 use rustc_infer::traits::solve::{CandidateSource, Certainty, Goal};
 use rustc_infer::traits::{
     BuiltinImplSource, ImplSource, ImplSourceUserDefinedData, Obligation, ObligationCause,
     PolyTraitObligation, PredicateObligation, Selection, SelectionError, SelectionResult,
 };
 use rustc_macros::extension;
+use rustc_middle::ty::{self, TyCtxt};
 use rustc_span::Span;
 
 use crate::solve::inspect::{self, ProofTreeInferCtxtExt};
@@ -28,6 +30,214 @@ fn select_in_new_trait_solver(
         .break_value()
         .unwrap()
     }
+
+    /// Explains how the candidates for `obligation` were selected: this returns all
+    /// trait candidates assembled for the goal together with the reason why they were
+    /// discarded, if they were.
+    ///
+    /// The candidates are ordered by preference: where-clauses come first, followed by
+    /// alias bounds, impls and finally builtin impls. Candidates which apply are then
+    /// discarded according to the following rules, in order:
+    /// - if a where-clause or alias bound holds, all impls are discarded,
+    ///   see [`CandidateDiscardReason::ShadowedByEnv`];
+    /// - object candidates are preferred over all other candidates,
+    ///   see [`CandidatePreferenceRule::Object`];
+    /// - specializing impls are preferred over the impls they specialize,
+    ///   see [`CandidatePreferenceRule::Specialization`].
+    ///
+    /// This is purely meant for debugging and diagnostics and does not affect
+    /// selection in any way.
+    fn explain_selection_in_new_trait_solver(
+        &self,
+        obligation: &PolyTraitObligation<'tcx>,
+    ) -> Vec<CandidateExplanation<'tcx>> {
+        assert!(self.next_trait_solver());
+
+        self.probe(|_| {
+            self.visit_proof_tree(
+                Goal::new(self.tcx, obligation.param_env, obligation.predicate),
+                &mut ExplainSelection { span: obligation.cause.span },
+            )
+            .break_value()
+            .unwrap()
+        })
+    }
+}
+
+/// A trait candidate of a goal and the reason why it was discarded, if it was.
+/// See [`InferCtxtSelectExt::explain_selection_in_new_trait_solver`].
+#[derive(Debug, Clone)]
+pub struct CandidateExplanation<'tcx> {
+    pub source: CandidateSource,
+    pub result: Result<Certainty, NoSolution>,
+    /// `None` if the candidate was not discarded.
+    pub discard_reason: Option<CandidateDiscardReason<'tcx>>,
+}
+
+impl<'tcx> CandidateExplanation<'tcx> {
+    /// Describes this explanation for debugging output,
+    /// see `#[rustc_evaluate_where_clauses]`.
+    pub fn describe(&self, tcx: TyCtxt<'tcx>) -> String {
+        let reason = match self.discard_reason {
+            None => String::new(),
+            Some(CandidateDiscardReason::DidNotUnify) => {
+                ", discarded as it does not unify with the goal".to_owned()
+            }
+            Some(CandidateDiscardReason::NestedGoalFailed(goal)) => {
+                format!(", discarded as `{}` does not hold", goal.predicate)
+            }
+            Some(CandidateDiscardReason::ShadowedByEnv) => {
+                ", discarded in favor of the environment".to_owned()
+            }
+            Some(CandidateDiscardReason::Winnowed { in_favor_of, rule }) => {
+                format!(
+                    ", discarded in favor of {} ({rule:?})",
+                    describe_candidate_source(tcx, in_favor_of)
+                )
+            }
+        };
+        format!(
+            "candidate {}: {:?}{reason}",
+            describe_candidate_source(tcx, self.source),
+            self.result
+        )
+    }
+}
+
+fn describe_candidate_source(tcx: TyCtxt<'_>, source: CandidateSource) -> String {
+    match source {
+        CandidateSource::Impl(def_id) => format!("`{}`", tcx.def_path_str(def_id)),
+        CandidateSource::BuiltinImpl(source) => format!("builtin {source:?}"),
+        CandidateSource::ParamEnv(idx) => format!("where-clause #{idx}"),
+        CandidateSource::AliasBound => "alias bound".to_owned(),
+        CandidateSource::CoherenceUnknowable => "coherence unknowable".to_owned(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CandidateDiscardReason<'tcx> {
+    /// The candidate does not unify with the goal.
+    DidNotUnify,
+    /// The candidate unifies with the goal, but one of its nested goals,
+    /// e.g. a where-clause of the impl, does not hold.
+    NestedGoalFailed(Goal<'tcx, ty::Predicate<'tcx>>),
+    /// The candidate applies, but the goal also holds by using a where-clause
+    /// or an alias bound, which we prefer over impls.
+    ShadowedByEnv,
+    /// The candidate applies, but was dropped in favor of a more specific
+    /// candidate when winnowing.
+    Winnowed { in_favor_of: CandidateSource, rule: CandidatePreferenceRule },
+}
+
+/// The rule used to prefer one applicable candidate over another when winnowing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidatePreferenceRule {
+    /// Object candidates are preferred over all other candidates. This is necessary
+    /// to handle the unsoundness between `impl<T: ?Sized> Any for T` and `dyn Any: Any`.
+    Object,
+    /// Specializing impls are preferred over the impls they specialize.
+    Specialization,
+}
+
+/// The order in which candidates are returned by `explain_selection_in_new_trait_solver`.
+fn candidate_source_order(source: CandidateSource) -> usize {
+    match source {
+        CandidateSource::ParamEnv(_) => 0,
+        CandidateSource::AliasBound => 1,
+        CandidateSource::Impl(_) => 2,
+        CandidateSource::BuiltinImpl(_) => 3,
+        CandidateSource::CoherenceUnknowable => 4,
+    }
+}
+
+struct ExplainSelection {
+    span: Span,
+}
+
+impl<'tcx> inspect::ProofTreeVisitor<'tcx> for ExplainSelection {
+    type Result = ControlFlow<Vec<CandidateExplanation<'tcx>>>;
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        let candidates: Vec<_> = goal
+            .candidates()
+            .into_iter()
+            .filter(|cand| matches!(cand.kind(), ProbeKind::TraitCandidate { .. }))
+            .collect();
+        let shadowed_by_env = candidates.iter().any(|cand| {
+            matches!(
+                cand.kind(),
+                ProbeKind::TraitCandidate {
+                    source: CandidateSource::ParamEnv(_) | CandidateSource::AliasBound,
+                    result: _,
+                }
+            ) && matches!(cand.result(), Ok(Certainty::Yes))
+        });
+
+        let mut explanations = candidates
+            .iter()
+            .enumerate()
+            .map(|(i, cand)| {
+                let ProbeKind::TraitCandidate { source, result: _ } = cand.kind() else {
+                    unreachable!()
+                };
+
+                let discard_reason = if cand.result().is_err() {
+                    let infcx = goal.infcx();
+                    let failing_goal = infcx.probe(|_| {
+                        cand.instantiate_nested_goals(self.span)
+                            .into_iter()
+                            .find(|nested| nested.result().is_err())
+                            .map(|nested| infcx.resolve_vars_if_possible(nested.goal()))
+                    });
+                    Some(match failing_goal {
+                        Some(goal) => CandidateDiscardReason::NestedGoalFailed(goal),
+                        None => CandidateDiscardReason::DidNotUnify,
+                    })
+                } else if shadowed_by_env
+                    && matches!(source, CandidateSource::Impl(_) | CandidateSource::BuiltinImpl(_))
+                {
+                    Some(CandidateDiscardReason::ShadowedByEnv)
+                } else if source == CandidateSource::CoherenceUnknowable {
+                    // This candidate only exists to make the goal ambiguous during
+                    // coherence and never takes part in winnowing.
+                    None
+                } else {
+                    candidates
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, other)| {
+                            i != j
+                                && other.result().is_ok()
+                                && !matches!(
+                                    other.kind(),
+                                    ProbeKind::TraitCandidate {
+                                        source: CandidateSource::CoherenceUnknowable,
+                                        result: _,
+                                    }
+                                )
+                        })
+                        .find_map(|(_, other)| {
+                            let rule = candidate_preference_rule(cand, other)?;
+                            let ProbeKind::TraitCandidate { source: in_favor_of, result: _ } =
+                                other.kind()
+                            else {
+                                unreachable!()
+                            };
+                            Some(CandidateDiscardReason::Winnowed { in_favor_of, rule })
+                        })
+                };
+
+                CandidateExplanation { source, result: cand.result(), discard_reason }
+            })
+            .collect::<Vec<_>>();
+        explanations.sort_by_key(|explanation| candidate_source_order(explanation.source));
+
+        ControlFlow::Break(explanations)
+    }
 }
 
 struct Select {
@@ -47,7 +257,10 @@ fn visit_goal(&mut self, goal: &inspect::InspectGoal<'_, 'tcx>) -> Self::Result
 
         // No candidates -- not implemented.
         if candidates.is_empty() {
-            return ControlFlow::Break(Err(SelectionError::Unimplemented));
+            return ControlFlow::Break(Err(match goal.explicit_negative_impl() {
+                Some(impl_def_id) => SelectionError::ExplicitNegativeImpl(impl_def_id),
+                None => SelectionError::Unimplemented,
+            }));
         }
 
         // One candidate, no need to winnow.
@@ -78,6 +291,38 @@ fn visit_goal(&mut self, goal: &inspect::InspectGoal<'_, 'tcx>) -> Self::Result
     }
 }
 
+/// Returns the source of the trait candidate used to prove `goal`, or `None` if
+/// there is no unique such candidate after winnowing, e.g. because the goal does
+/// not hold.
+pub(in crate::solve) fn selected_candidate_source<'tcx>(
+    goal: &inspect::InspectGoal<'_, 'tcx>,
+) -> Option<CandidateSource> {
+    let mut candidates = goal.candidates();
+    candidates.retain(|cand| {
+        matches!(cand.kind(), ProbeKind::TraitCandidate { .. }) && cand.result().is_ok()
+    });
+
+    let mut i = 0;
+    while i < candidates.len() {
+        let should_drop_i = (0..candidates.len())
+            .filter(|&j| i != j)
+            .any(|j| candidate_should_be_dropped_in_favor_of(&candidates[i], &candidates[j]));
+        if should_drop_i {
+            candidates.swap_remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    match candidates.as_slice() {
+        [candidate] => match candidate.kind() {
+            ProbeKind::TraitCandidate { source, result: _ } => Some(source),
+            _ => unreachable!(),
+        },
+        _ => None,
+    }
+}
+
 /// This is a lot more limited than the old solver's equivalent method. This may lead to more `Ok(None)`
 /// results when selecting traits in polymorphic contexts, but we should never rely on the lack of ambiguity,
 /// and should always just gracefully fail here. We shouldn't rely on this incompleteness.
@@ -85,19 +330,27 @@ fn candidate_should_be_dropped_in_favor_of<'tcx>(
     victim: &inspect::InspectCandidate<'_, 'tcx>,
     other: &inspect::InspectCandidate<'_, 'tcx>,
 ) -> bool {
+    candidate_preference_rule(victim, other).is_some()
+}
+
+/// Returns the rule which causes `victim` to be dropped in favor of `other`, if any.
+fn candidate_preference_rule<'tcx>(
+    victim: &inspect::InspectCandidate<'_, 'tcx>,
+    other: &inspect::InspectCandidate<'_, 'tcx>,
+) -> Option<CandidatePreferenceRule> {
     // Don't winnow until `Certainty::Yes` -- we don't need to winnow until
     // codegen, technically.
     if matches!(other.result().unwrap(), Certainty::Maybe(..)) {
-        return false;
+        return None;
     }
 
     let inspect::ProbeKind::TraitCandidate { source: victim_source, result: _ } = victim.kind()
     else {
-        return false;
+        return None;
     };
     let inspect::ProbeKind::TraitCandidate { source: other_source, result: _ } = other.kind()
     else {
-        return false;
+        return None;
     };
 
     match (victim_source, other_source) {
@@ -110,18 +363,21 @@ fn candidate_should_be_dropped_in_favor_of<'tcx>(
         (
             CandidateSource::BuiltinImpl(BuiltinImplSource::Object { .. }),
             CandidateSource::BuiltinImpl(BuiltinImplSource::Object { .. }),
-        ) => false,
+        ) => None,
         (
             CandidateSource::Impl(_) | CandidateSource::ParamEnv(_) | CandidateSource::AliasBound,
             CandidateSource::BuiltinImpl(BuiltinImplSource::Object { .. }),
-        ) => true,
+        ) => Some(CandidatePreferenceRule::Object),
 
         // Prefer specializing candidates over specialized candidates.
-        (CandidateSource::Impl(victim_def_id), CandidateSource::Impl(other_def_id)) => {
-            victim.goal().infcx().tcx.specializes((other_def_id, victim_def_id))
-        }
+        (CandidateSource::Impl(victim_def_id), CandidateSource::Impl(other_def_id)) => victim
+            .goal()
+            .infcx()
+            .tcx
+            .specializes((other_def_id, victim_def_id))
+            .then_some(CandidatePreferenceRule::Specialization),
 
-        _ => false,
+        _ => None,
     }
 }
 
@@ -168,6 +424,8 @@ fn to_selection<'tcx>(
         | ProbeKind::UnsizeAssembly
         | ProbeKind::UpcastProjectionCompatibility
         | ProbeKind::OpaqueTypeStorageLookup { result: _ }
+        | ProbeKind::ExplicitNegativeImpl { impl_def_id: _, result: _ }
+        | ProbeKind::ShadowedByGlobalWhereBound { param_env_index: _ }
         | ProbeKind::Root { result: _ } => {
             span_bug!(span, "didn't expect to assemble trait candidate from {:#?}", cand.kind())
         }
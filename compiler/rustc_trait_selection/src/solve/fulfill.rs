@@ -1,20 +1,51 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::iter;
 use std::mem;
 use std::ops::ControlFlow;
+use std::time::Instant;
 
-use rustc_infer::infer::InferCtxt;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxHasher, FxIndexMap, FxIndexSet};
+use rustc_data_structures::sync::Lrc;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_infer::infer::canonical::query_response::make_query_region_constraints;
+use rustc_infer::infer::canonical::QueryOutlivesConstraint;
+use rustc_infer::infer::outlives::env::OutlivesEnvironment;
+use rustc_infer::infer::region_constraints::RegionConstraintData;
+use rustc_infer::infer::type_variable::TypeVariableOrigin;
+use rustc_infer::infer::{DefineOpaqueTypes, InferCtxt, InferOk, RegionResolutionError};
 use rustc_infer::traits::query::NoSolution;
 use rustc_infer::traits::solve::inspect::ProbeKind;
-use rustc_infer::traits::solve::{CandidateSource, GoalSource, MaybeCause};
+use rustc_infer::traits::solve::{CandidateSource, Goal, GoalSource, MaybeCause};
 use rustc_infer::traits::{
-    self, FulfillmentError, FulfillmentErrorCode, MismatchedProjectionTypes, Obligation,
-    ObligationCause, PredicateObligation, SelectionError, TraitEngine,
+    self, CoercionStep, FulfillmentError, FulfillmentErrorCode, MismatchedProjectionTypes,
+    Obligation, ObligationCause, ObligationCauseCode, OverflowChain, PredicateObligation,
+    SelectionError, TraitEngine, TraitEngineExt,
 };
+use rustc_middle::infer::unify_key::ConstVariableOrigin;
+use rustc_middle::ty::abstract_const::NotConstEvaluatable;
 use rustc_middle::ty::error::{ExpectedFound, TypeError};
-use rustc_middle::ty::{self, TyCtxt};
+use rustc_middle::ty::{
+    self, Ty, TyCtxt, TypeSuperVisitable, TypeVisitable, TypeVisitableExt, TypeVisitor,
+};
+use rustc_session::config::{DumpSolverProofTree, GlobalWhereBounds};
+use rustc_span::symbol::sym;
+use rustc_span::Span;
+
+use crate::errors::{
+    SolverGlobalWhereBoundPreference, SolverPassCount, SolverPassCountObligation,
+    SolverPassCountOtherObligations,
+};
+use crate::regions::InferCtxtRegionExt;
+use crate::traits::error_reporting::dump_proof_tree;
+use crate::traits::{const_evaluatable, wf};
 
-use super::eval_ctxt::GenerateProofTree;
-use super::inspect::{ProofTreeInferCtxtExt, ProofTreeVisitor};
-use super::{Certainty, InferCtxtEvalExt};
+use super::eval_ctxt::{proven_global_goal, selected_candidate_source, GenerateProofTree};
+use super::{goal_summary, solver_session, span_profile};
+use super::inspect::{self, ProofTreeInferCtxtExt, ProofTreeVisitor};
+use super::{Certainty, InferCtxtEvalExt, Interrupted, SolverCancellationToken, SolverMode};
 
 /// A trait engine using the new trait solver.
 ///
@@ -30,11 +61,73 @@
 pub struct FulfillmentCtxt<'tcx> {
     obligations: ObligationStorage<'tcx>,
 
+    /// The outlives constraints added to the inference context while proving
+    /// the registered obligations, grouped by the root obligation they are
+    /// attributable to. See `TraitEngine::outlives_constraints_by_root`.
+    outlives_constraints: FxIndexMap<PredicateObligation<'tcx>, Vec<QueryOutlivesConstraint<'tcx>>>,
+    /// Whether to record `outlives_constraints`, see `TraitEngine::track_outlives_constraints`.
+    /// Looking at the region constraints after each evaluation is not free, so this
+    /// is disabled by default.
+    track_outlives_constraints: bool,
+
+    /// If enabled, the source of the candidate used to prove each trait obligation
+    /// which holds. See `FulfillmentCtxt::record_selected_candidates`.
+    selected_candidates: Option<FxIndexMap<PredicateObligation<'tcx>, CandidateSource>>,
+
+    /// If enabled, the indices of the param-env clauses used to prove each obligation
+    /// which holds. See `TraitEngine::track_used_param_env_clauses`.
+    used_param_env_clauses: Option<FxIndexMap<PredicateObligation<'tcx>, FxIndexSet<usize>>>,
+
+    /// If enabled, the fully concrete goals proven so far which hold without any
+    /// constraints. See `TraitEngine::track_proven_global_goals`.
+    proven_global_goals: Option<FxIndexSet<ty::Predicate<'tcx>>>,
+
+    /// If enabled, the obligations which have been proven to hold so far.
+    /// See `FulfillmentCtxt::revalidate_goals_mentioning`.
+    proven: Option<Vec<PredicateObligation<'tcx>>>,
+
     /// The snapshot in which this context was created. Using the context
     /// outside of this snapshot leads to subtle bugs if the snapshot
     /// gets rolled back. Because of this we explicitly check that we only
     /// use the context in exactly this snapshot.
     usable_in_snapshot: usize,
+
+    /// The mode used to evaluate the registered obligations. This defaults to
+    /// the mode of the inference context, see `FulfillmentCtxt::new_in_mode`.
+    solver_mode: SolverMode,
+
+    /// Allows hosts to abandon the evaluation of the pending obligations,
+    /// see `FulfillmentCtxt::set_cancellation_token`.
+    cancellation: Option<SolverCancellationToken>,
+    /// Whether the evaluation of some obligation has been interrupted
+    /// via `cancellation`.
+    interrupted: bool,
+
+    /// Whether to check that the outcome of `select_where_possible` does not
+    /// depend on the order of the obligations, see `-Zsolver-check-obligation-order`.
+    check_obligation_order: bool,
+    /// If set, obligations are evaluated in a permutation of their order seeded by
+    /// this value, see `ObligationStorage::unstalled_for_select`.
+    shuffle_seed: Option<u64>,
+}
+
+/// The allocations backing a [`FulfillmentCtxt`]. These can be taken out of a
+/// fulfillment context which is no longer needed via [`FulfillmentCtxt::into_storage`]
+/// and reused by [`FulfillmentCtxt::new_with_storage`], avoiding reallocations when
+/// creating many short-lived fulfillment contexts, e.g. one for each body.
+#[derive(Default)]
+pub struct FulfillmentStorage<'tcx> {
+    obligations: ObligationStorage<'tcx>,
+    outlives_constraints: FxIndexMap<PredicateObligation<'tcx>, Vec<QueryOutlivesConstraint<'tcx>>>,
+}
+
+/// The errors returned by [`FulfillmentCtxt::select_all_and_resolve_regions`].
+#[derive(Debug)]
+pub enum SelectAndResolveRegionsError<'tcx> {
+    /// Some obligations do not hold, so regions have not been resolved.
+    Fulfillment(Vec<FulfillmentError<'tcx>>),
+    /// All obligations hold, but resolving regions failed.
+    Regions(Vec<RegionResolutionError<'tcx>>),
 }
 
 #[derive(Default)]
@@ -45,252 +138,1971 @@ struct ObligationStorage<'tcx> {
     /// to avoid recomputing them each time `select_where_possible` is called.
     /// This also allows us to return the correct `FulfillmentError` for them.
     overflowed: Vec<PredicateObligation<'tcx>>,
-    pending: Vec<PredicateObligation<'tcx>>,
+    pending: Vec<PendingObligation<'tcx>>,
+    /// For pending obligations registered via `register_nested_predicate_obligation`,
+    /// the outermost obligation with a non-dummy span whose processing caused them to
+    /// be registered. This is used as the `root_obligation` when reporting errors.
+    ///
+    /// We store the root itself instead of the direct parent so that entries can be
+    /// removed as soon as their obligation has been selected without losing the root
+    /// of obligations nested under it.
+    roots: FxHashMap<PredicateObligation<'tcx>, PredicateObligation<'tcx>>,
+    /// Shares the cause codes of registered obligations and of the causes
+    /// derived from them.
+    causes: CauseCache<'tcx>,
+    /// The number of obligations registered so far, used to order obligations
+    /// by their registration, see `ObligationOrder`.
+    registered: u64,
+}
+
+/// Shares equal cause codes between obligations. Cause codes are refcounted, so this
+/// avoids keeping separate copies of the same cause code chain, e.g. for obligations
+/// registered in a batch or for the causes derived for the where-bounds of an impl.
+#[derive(Default)]
+struct CauseCache<'tcx> {
+    /// The cause of the most recently registered obligation. Obligations are usually
+    /// registered in batches whose causes have the same cause code.
+    last_registered: Option<ObligationCause<'tcx>>,
+    /// The causes created by `derive_cause`, keyed by the impl and the index of the
+    /// where-bound they have been derived for, or `None` for builtin impls, and the
+    /// parent trait predicate. Each entry also stores the cause it has been derived
+    /// from, which has to match for the entry to be reused.
+    derived: RefCell<
+        FxHashMap<DerivedCauseKey<'tcx>, (ObligationCause<'tcx>, ObligationCause<'tcx>)>,
+    >,
+}
+
+type DerivedCauseKey<'tcx> = (Option<DefId>, usize, ty::PolyTraitPredicate<'tcx>);
+
+impl<'tcx> CauseCache<'tcx> {
+    /// Makes `cause` share its cause code with the previously registered obligation
+    /// if both cause codes are equal.
+    fn share_registered(&mut self, cause: &mut ObligationCause<'tcx>) {
+        if let Some(last_registered) = &self.last_registered {
+            cause.share_code_with(last_registered);
+        }
+        self.last_registered = Some(cause.clone());
+    }
+
+    fn clear(&mut self) {
+        self.last_registered = None;
+        self.derived.get_mut().clear();
+    }
+}
+
+/// A pending obligation together with its position in the order in which the
+/// pending obligations are evaluated.
+struct PendingObligation<'tcx> {
+    obligation: PredicateObligation<'tcx>,
+    order: ObligationOrder,
+    /// The type inference variables mentioned by the obligation when it last resulted
+    /// in ambiguity. This is empty for obligations which have not been evaluated yet.
+    stalled_on: Vec<ty::TyVid>,
+    /// How often evaluating this obligation made progress without proving it. Once
+    /// this exceeds the recursion limit, the obligation overflows, see
+    /// `ObligationStorage::overflow`.
+    depth: usize,
+}
+
+impl<'tcx> ObligationStorage<'tcx> {
+    fn register(&mut self, infcx: &InferCtxt<'tcx>, mut obligation: PredicateObligation<'tcx>) {
+        self.causes.share_registered(&mut obligation.cause);
+        let order = ObligationOrder::of(infcx, &obligation, self.registered);
+        self.registered += 1;
+        self.pending.push(PendingObligation { obligation, order, stalled_on: vec![], depth: 0 });
+    }
+
+    /// Registers an obligation returned by `unstalled_for_select` again, reusing its order.
+    fn requeue(&mut self, pending: PendingObligation<'tcx>) {
+        self.pending.push(pending);
+    }
+
+    fn register_goals(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        cause: &ObligationCause<'tcx>,
+        goals: Vec<Goal<'tcx, ty::Predicate<'tcx>>>,
+    ) {
+        self.pending.reserve(goals.len());
+        for goal in goals {
+            let obligation = Obligation {
+                cause: cause.clone(),
+                param_env: goal.param_env,
+                predicate: goal.predicate,
+                recursion_depth: 0,
+            };
+            self.register(infcx, obligation);
+        }
+    }
+
+    fn register_nested(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: PredicateObligation<'tcx>,
+        parent: &PredicateObligation<'tcx>,
+    ) {
+        // Obligations are compared without their cause, so an obligation may be
+        // registered again as its own descendant. We only track the first root of
+        // each obligation.
+        if obligation != *parent && !self.roots.contains_key(&obligation) {
+            let root = match self.roots.get(parent) {
+                Some(root) => Some(root.clone()),
+                None => (!parent.cause.span.is_dummy()).then(|| parent.clone()),
+            };
+            if let Some(root) = root {
+                self.roots.insert(obligation.clone(), root);
+            }
+        }
+        self.register(infcx, obligation);
+    }
+
+    /// Returns the obligation which should be used as the `root_obligation` when
+    /// reporting an error for `obligation`. This is its outermost ancestor with a
+    /// non-dummy span, or `obligation` itself if there is no such ancestor.
+    fn root_obligation(&self, obligation: &PredicateObligation<'tcx>) -> PredicateObligation<'tcx> {
+        self.roots.get(obligation).unwrap_or(obligation).clone()
+    }
+
+    /// Stops tracking the root of `obligation` once it has been selected and
+    /// is no longer pending.
+    fn forget_root(&mut self, obligation: &PredicateObligation<'tcx>) {
+        if !self.roots.is_empty() {
+            self.roots.remove(obligation);
+        }
+    }
+
+    fn clone_pending(&self) -> Vec<PredicateObligation<'tcx>> {
+        let mut obligations: Vec<_> =
+            self.pending.iter().map(|pending| pending.obligation.clone()).collect();
+        obligations.extend(self.overflowed.iter().cloned());
+        obligations
+    }
+
+    fn take_pending(&mut self) -> Vec<PredicateObligation<'tcx>> {
+        let mut obligations = self.take_pending_without_overflowed();
+        obligations.append(&mut self.overflowed);
+        obligations
+    }
+
+    fn take_pending_without_overflowed(&mut self) -> Vec<PredicateObligation<'tcx>> {
+        mem::take(&mut self.pending).into_iter().map(|pending| pending.obligation).collect()
+    }
+
+    /// Returns the obligations to evaluate in the next iteration of `select_where_possible`,
+    /// sorted by their order, see `ObligationOrder`. With `shuffle_seed`, which is used
+    /// by `-Zsolver-check-obligation-order`, obligations of the same priority are returned
+    /// in a permutation of their order determined by the seed.
+    ///
+    /// The order of each obligation is computed once when registering it. Obligations which
+    /// remain pending are registered again in the order in which they have been returned,
+    /// so only the obligations registered since the last pass are out of order here.
+    fn unstalled_for_select(
+        &mut self,
+        shuffle_seed: Option<u64>,
+    ) -> impl Iterator<Item = PendingObligation<'tcx>> {
+        if let Some(seed) = shuffle_seed {
+            self.pending.sort_by_cached_key(|pending| {
+                let ObligationOrder { priority, span, tiebreak } = pending.order;
+                let mut hasher = FxHasher::default();
+                (seed, span, tiebreak).hash(&mut hasher);
+                (priority, hasher.finish())
+            });
+        } else {
+            self.pending.sort_by_key(|pending| pending.order);
+        }
+
+        mem::take(&mut self.pending).into_iter()
+    }
+
+    /// Moves an obligation whose evaluation made progress more often than the
+    /// recursion limit allows to `overflowed`. Only such obligations are responsible
+    /// for fulfillment not reaching a fixpoint, so all other obligations stay pending
+    /// and are reported as ambiguous if they never hold.
+    fn overflow(&mut self, infcx: &InferCtxt<'tcx>, pending: PendingObligation<'tcx>) {
+        if let Some(inspector) = infcx.overflow_inspector.get() {
+            (inspector)(infcx, &pending.obligation, pending.depth);
+        }
+
+        self.overflowed.push(pending.obligation);
+    }
+}
+
+/// The order in which pending obligations are evaluated, see
+/// `ObligationStorage::unstalled_for_select`.
+///
+/// Obligations which are likely to constrain inference variables are evaluated first,
+/// as other obligations may be stalled on these variables. This reduces the number of
+/// iterations needed to reach a fixpoint. Otherwise obligations are ordered by their span
+/// and then by `tiebreak`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct ObligationOrder {
+    priority: ObligationPriority,
+    span: Span,
+    /// The index in which the obligation has been registered. With
+    /// `-Zsolver-check-obligation-order`, this is instead the stable hash of the
+    /// canonicalized goal at the time the obligation has been registered, so that the
+    /// order does not depend on the order in which obligations are registered.
+    /// Canonicalizing goals is comparatively expensive, so we only do so when checking
+    /// the order.
+    tiebreak: u64,
+}
+
+impl ObligationOrder {
+    fn of<'tcx>(
+        infcx: &InferCtxt<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        registration_index: u64,
+    ) -> Self {
+        let tiebreak = if infcx.tcx.sess.opts.unstable_opts.solver_check_obligation_order {
+            goal_summary::goal_hash(infcx, obligation).as_u64()
+        } else {
+            registration_index
+        };
+        ObligationOrder {
+            priority: ObligationPriority::of(infcx.tcx, obligation),
+            span: obligation.cause.span,
+            tiebreak,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ObligationPriority {
+    /// Goals which are likely to constrain inference variables, e.g. projections,
+    /// subtyping, `Sized` or closure signature goals.
+    ConstrainsInference,
+    Other,
+}
+
+impl ObligationPriority {
+    fn of<'tcx>(tcx: TyCtxt<'tcx>, obligation: &PredicateObligation<'tcx>) -> ObligationPriority {
+        match obligation.predicate.kind().skip_binder() {
+            ty::PredicateKind::Clause(ty::ClauseKind::Projection(_))
+            | ty::PredicateKind::NormalizesTo(_)
+            | ty::PredicateKind::AliasRelate(..)
+            | ty::PredicateKind::Subtype(_)
+            | ty::PredicateKind::Coerce(_) => ObligationPriority::ConstrainsInference,
+            ty::PredicateKind::Clause(ty::ClauseKind::Trait(pred))
+                if tcx.lang_items().sized_trait() == Some(pred.def_id())
+                    || tcx.is_fn_trait(pred.def_id()) =>
+            {
+                ObligationPriority::ConstrainsInference
+            }
+            ty::PredicateKind::Clause(_)
+            | ty::PredicateKind::ObjectSafe(_)
+            | ty::PredicateKind::ConstEquate(..)
+            | ty::PredicateKind::Ambiguous => ObligationPriority::Other,
+        }
+    }
+}
+
+impl<'tcx> FulfillmentCtxt<'tcx> {
+    pub fn new(infcx: &InferCtxt<'tcx>) -> FulfillmentCtxt<'tcx> {
+        FulfillmentCtxt::new_with_storage(infcx, Default::default())
+    }
+
+    /// Creates a new fulfillment context which evaluates its obligations using
+    /// `solver_mode` instead of the mode of `infcx`. This e.g. allows checking
+    /// whether goals hold in coherence mode using an existing inference context.
+    pub fn new_in_mode(infcx: &InferCtxt<'tcx>, solver_mode: SolverMode) -> FulfillmentCtxt<'tcx> {
+        FulfillmentCtxt { solver_mode, ..FulfillmentCtxt::new(infcx) }
+    }
+
+    /// Creates a new fulfillment context reusing the allocations of a previous one,
+    /// see [`FulfillmentCtxt::into_storage`].
+    pub fn new_with_storage(
+        infcx: &InferCtxt<'tcx>,
+        storage: FulfillmentStorage<'tcx>,
+    ) -> FulfillmentCtxt<'tcx> {
+        assert!(
+            infcx.next_trait_solver(),
+            "new trait solver fulfillment context created when \
+            infcx is set up for old trait solver"
+        );
+        let FulfillmentStorage { obligations, outlives_constraints } = storage;
+        debug_assert!(obligations.pending.is_empty() && obligations.roots.is_empty());
+        debug_assert!(outlives_constraints.is_empty());
+        FulfillmentCtxt {
+            obligations,
+            outlives_constraints,
+            track_outlives_constraints: false,
+            selected_candidates: None,
+            used_param_env_clauses: None,
+            proven_global_goals: None,
+            proven: None,
+            usable_in_snapshot: infcx.num_open_snapshots(),
+            solver_mode: SolverMode::for_infcx(infcx),
+            cancellation: None,
+            interrupted: false,
+            check_obligation_order: infcx.tcx.sess.opts.unstable_opts.solver_check_obligation_order,
+            shuffle_seed: None,
+        }
+    }
+
+    /// Stops evaluating obligations once `cancellation` has been cancelled. Obligations
+    /// whose evaluation has been interrupted stay pending, so callers have to check
+    /// [`FulfillmentCtxt::was_interrupted`] instead of reporting them as ambiguous.
+    pub fn set_cancellation_token(&mut self, cancellation: SolverCancellationToken) {
+        self.cancellation = Some(cancellation);
+    }
+
+    /// Whether the evaluation of some obligation has been interrupted,
+    /// see [`FulfillmentCtxt::set_cancellation_token`].
+    pub fn was_interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Discards all remaining obligations of this fulfillment context, returning
+    /// its allocations so that they can be reused by a new fulfillment context.
+    pub fn into_storage(self) -> FulfillmentStorage<'tcx> {
+        let FulfillmentCtxt { mut obligations, mut outlives_constraints, .. } = self;
+        obligations.pending.clear();
+        obligations.overflowed.clear();
+        obligations.roots.clear();
+        obligations.causes.clear();
+        obligations.registered = 0;
+        outlives_constraints.clear();
+        FulfillmentStorage { obligations, outlives_constraints }
+    }
+
+    /// Enables recording the candidate used to prove each trait obligation which holds,
+    /// see [`FulfillmentCtxt::selected_candidates`]. This requires computing the proof
+    /// tree of every evaluated obligation, so it should only be used by tools.
+    pub fn record_selected_candidates(&mut self) {
+        if self.selected_candidates.is_none() {
+            self.selected_candidates = Some(Default::default());
+        }
+    }
+
+    /// Returns the source of the candidate, e.g. an impl or a where-clause, used to
+    /// prove each trait obligation which has been proven so far. This is `None`
+    /// unless enabled via [`FulfillmentCtxt::record_selected_candidates`].
+    pub fn selected_candidates(
+        &self,
+    ) -> Option<&FxIndexMap<PredicateObligation<'tcx>, CandidateSource>> {
+        self.selected_candidates.as_ref()
+    }
+
+    /// Enables recording the obligations which have been proven to hold, allowing
+    /// them to be rechecked via [`FulfillmentCtxt::revalidate_goals_mentioning`].
+    pub fn record_proven_obligations(&mut self) {
+        if self.proven.is_none() {
+            self.proven = Some(Default::default());
+        }
+    }
+
+    /// Registers the previously proven obligations which mention the opaque type
+    /// `def_id` again, returning how many of them have been registered.
+    ///
+    /// Once the hidden type of an opaque has been defined, e.g. at the end of typeck,
+    /// goals which have been proven while the opaque was still undefined may no
+    /// longer hold. Rechecking only the goals mentioning that opaque avoids having
+    /// to prove all obligations of the body again.
+    ///
+    /// This requires [`FulfillmentCtxt::record_proven_obligations`] to have been
+    /// called before registering the obligations.
+    pub fn revalidate_goals_mentioning(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        def_id: LocalDefId,
+    ) -> usize {
+        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
+        let Some(proven) = &mut self.proven else {
+            bug!("revalidating goals without recording proven obligations");
+        };
+
+        let mut visitor = MentionsOpaque { def_id: def_id.to_def_id() };
+        let (revalidated, still_proven): (Vec<_>, Vec<_>) =
+            mem::take(proven).into_iter().partition(|obligation| {
+                let predicate = infcx.resolve_vars_if_possible(obligation.predicate);
+                predicate.visit_with(&mut visitor).is_break()
+            });
+        *proven = still_proven;
+        let count = revalidated.len();
+        for obligation in revalidated {
+            self.obligations.register(infcx, obligation);
+        }
+        count
+    }
+
+    /// Computes the errors for the obligations which are still `pending` or
+    /// have `overflowed`, see `TraitEngine::collect_remaining_errors`.
+    fn errors_for_remaining(
+        &self,
+        infcx: &InferCtxt<'tcx>,
+        pending: Vec<PredicateObligation<'tcx>>,
+        overflowed: Vec<PredicateObligation<'tcx>>,
+    ) -> Vec<FulfillmentError<'tcx>> {
+        let mut errors: Vec<_> = pending
+            .into_iter()
+            .map(|obligation| {
+                let root_obligation = self.obligations.root_obligation(&obligation);
+                fulfillment_error_for_stalled(
+                    infcx,
+                    &self.obligations.causes,
+                    self.solver_mode,
+                    obligation,
+                    root_obligation,
+                )
+            })
+            .collect();
+
+        errors.extend(overflowed.into_iter().map(|obligation| {
+            let proof_tree = compute_proof_tree(infcx, self.solver_mode, &obligation);
+            FulfillmentError {
+                obligation: find_best_leaf_obligation(
+                    infcx,
+                    &self.obligations.causes,
+                    &obligation,
+                    &proof_tree,
+                ),
+                code: FulfillmentErrorCode::Ambiguity { overflow: Some(true) },
+                root_obligation: self.obligations.root_obligation(&obligation),
+                overflow_chain: find_overflow_chain(infcx, &obligation, &proof_tree),
+                proof_tree: Some(proof_tree),
+                blocking_infer_vars: vec![],
+                ambiguity_cause: None,
+                redundant_with: None,
+                reached_step_limit: false,
+            }
+        }));
+
+        errors
+    }
+
+    fn record_selected_candidate(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        proof_tree: Option<&Lrc<inspect::GoalEvaluation<'tcx>>>,
+    ) {
+        let (Some(selected_candidates), Some(proof_tree)) =
+            (&mut self.selected_candidates, proof_tree)
+        else {
+            return;
+        };
+
+        if obligation.predicate.as_trait_clause().is_none() {
+            return;
+        }
+
+        let source = infcx.probe(|_| {
+            infcx
+                .visit_existing_proof_tree(
+                    proof_tree,
+                    &mut SelectedCandidate { span: obligation.cause.span },
+                )
+                .break_value()
+        });
+        if let Some(source) = source {
+            selected_candidates.insert(obligation.clone(), source);
+        }
+    }
+
+    fn record_used_param_env_clauses(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        proof_tree: Option<&Lrc<inspect::GoalEvaluation<'tcx>>>,
+    ) {
+        let (Some(used_param_env_clauses), Some(proof_tree)) =
+            (&mut self.used_param_env_clauses, proof_tree)
+        else {
+            return;
+        };
+
+        let mut visitor = UsedParamEnvClauses {
+            span: obligation.cause.span,
+            param_env: obligation.param_env,
+            clauses: Default::default(),
+        };
+        infcx.probe(|_| infcx.visit_existing_proof_tree(proof_tree, &mut visitor));
+        used_param_env_clauses.entry(obligation.clone()).or_default().extend(visitor.clauses);
+    }
+
+    /// Checks that selecting the pending obligations results in the same errors and
+    /// inference constraints regardless of the order in which they are evaluated.
+    /// To do so, we select them in their canonical order and in permutations of it
+    /// seeded by `ORDER_CHECK_SEEDS` inside of probes and compare the results.
+    fn assert_order_independent(&self, infcx: &InferCtxt<'tcx>) {
+        const ORDER_CHECK_SEEDS: [u64; 3] = [0x9e37_79b9_7f4a_7c15, 0x2545_f491_4f6c_dd1d, 1];
+
+        let pending = self.obligations.clone_pending();
+        let Some(span) = pending.first().map(|o| o.cause.span) else {
+            return;
+        };
+
+        let select = |shuffle_seed| {
+            infcx.probe(|_| {
+                let mut fulfill_cx = FulfillmentCtxt::new_in_mode(infcx, self.solver_mode);
+                fulfill_cx.check_obligation_order = false;
+                fulfill_cx.shuffle_seed = shuffle_seed;
+                fulfill_cx.register_predicate_obligations(infcx, pending.iter().cloned());
+                let errors = fulfill_cx.select_where_possible(infcx).len();
+                // The inference variables created while selecting may differ depending on
+                // the order, so we canonicalize the pending obligations before comparing them.
+                let predicates: Vec<_> = pending.iter().map(|o| o.predicate).collect();
+                (errors, infcx.canonicalize_response(predicates))
+            })
+        };
+
+        let (canonical_errors, canonical_predicates) = select(None);
+        for seed in ORDER_CHECK_SEEDS {
+            let (shuffled_errors, shuffled_predicates) = select(Some(seed));
+            if canonical_errors != shuffled_errors {
+                span_bug!(
+                    span,
+                    "selecting obligations in the order seeded by {seed:#x} resulted in \
+                     {shuffled_errors} errors instead of {canonical_errors}: {pending:#?}",
+                );
+            }
+            if canonical_predicates != shuffled_predicates {
+                span_bug!(
+                    span,
+                    "selecting obligations in the order seeded by {seed:#x} resulted in \
+                     different inference constraints: {canonical_predicates:#?} and \
+                     {shuffled_predicates:#?}",
+                );
+            }
+        }
+    }
+
+    /// Selects the registered obligations until reaching a fixpoint and returns
+    /// errors for all obligations which do not hold.
+    ///
+    /// Errors for obligations which do not hold are returned on their own. Only if
+    /// there are none of them do we return errors for the remaining ambiguous and
+    /// overflowing obligations. In both cases, no obligations remain pending afterwards.
+    #[must_use]
+    pub fn select_all_or_error(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
+        TraitEngineExt::select_all_or_error(self, infcx)
+    }
+
+    /// Like [`FulfillmentCtxt::select_all_or_error`], but if all obligations hold,
+    /// this then also resolves the regions of `infcx` using `outlives_env`.
+    ///
+    /// Regions are only resolved after all obligations have been proven, as proving
+    /// them may add further region constraints.
+    pub fn select_all_and_resolve_regions(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        outlives_env: &OutlivesEnvironment<'tcx>,
+    ) -> Result<(), SelectAndResolveRegionsError<'tcx>> {
+        let errors = self.select_all_or_error(infcx);
+        if !errors.is_empty() {
+            return Err(SelectAndResolveRegionsError::Fulfillment(errors));
+        }
+
+        let errors = infcx.resolve_regions(outlives_env);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SelectAndResolveRegionsError::Regions(errors))
+        }
+    }
+
+    /// Speculatively checks whether all `obligations` hold, without constraining
+    /// the inference variables of `infcx`. This registers the obligations in a new
+    /// fulfillment context inside of a probe and selects until reaching a fixpoint.
+    ///
+    /// This is intended for suggestions and coercions which want to know whether
+    /// a change would cause everything to fulfill.
+    ///
+    /// The proof trees of the returned errors only make sense inside of the probe, so
+    /// we drop them and resolve the inference variables of the errors before leaving
+    /// the probe. The errors may still mention inference variables which were created
+    /// inside of the probe and remained unconstrained, so they should only be used
+    /// for diagnostics.
+    pub fn try_fulfill_in_probe(
+        infcx: &InferCtxt<'tcx>,
+        obligations: impl IntoIterator<Item = PredicateObligation<'tcx>>,
+    ) -> Result<(), Vec<FulfillmentError<'tcx>>> {
+        infcx.probe(|_| {
+            let mut fulfill_cx = FulfillmentCtxt::new(infcx);
+            for obligation in obligations {
+                fulfill_cx.register_predicate_obligation(infcx, obligation);
+            }
+
+            let errors = fulfill_cx.select_all_or_error(infcx);
+            if errors.is_empty() {
+                return Ok(());
+            }
+
+            Err(errors
+                .into_iter()
+                .map(|error| FulfillmentError {
+                    obligation: infcx.resolve_vars_if_possible(error.obligation),
+                    root_obligation: infcx.resolve_vars_if_possible(error.root_obligation),
+                    proof_tree: None,
+                    blocking_infer_vars: infcx.resolve_vars_if_possible(error.blocking_infer_vars),
+                    redundant_with: infcx.resolve_vars_if_possible(error.redundant_with),
+                    overflow_chain: error.overflow_chain.map(|chain| OverflowChain {
+                        goals: infcx.resolve_vars_if_possible(chain.goals),
+                        truncated: chain.truncated,
+                    }),
+                    ..error
+                })
+                .collect())
+        })
+    }
+
+    /// Evaluates the well-formedness obligation `first` together with the well-formedness
+    /// obligations directly following it in `rest` in a single root evaluation, returning
+    /// their results in order. Checking large tuples, arrays, or ADT constructors results in
+    /// many such obligations which are cheap to prove, so this avoids the overhead of a
+    /// separate root evaluation for each of them. Their results are still tracked
+    /// separately, so errors are reported for the failing sub-term.
+    ///
+    /// We only batch consecutive obligations to evaluate them in the same order as
+    /// when evaluating them separately, see `ObligationOrder`.
+    fn evaluate_wf_obligations_batched(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        first: &PredicateObligation<'tcx>,
+        rest: &[PendingObligation<'tcx>],
+    ) -> VecDeque<Result<(bool, Certainty), NoSolution>> {
+        let is_wf = |obligation: &PredicateObligation<'tcx>| {
+            matches!(
+                obligation.predicate.kind().skip_binder(),
+                ty::PredicateKind::Clause(ty::ClauseKind::WellFormed(_))
+            )
+        };
+        let len = rest.iter().take_while(|pending| is_wf(&pending.obligation)).count();
+        if !is_wf(first) || len == 0 {
+            return VecDeque::new();
+        }
+
+        let wf_obligations: Vec<_> = iter::once(first)
+            .chain(rest[..len].iter().map(|pending| &pending.obligation))
+            .collect();
+        let mut results = VecDeque::with_capacity(wf_obligations.len());
+        let mut lens = self.track_outlives_constraints.then(|| region_constraints_lens(infcx));
+        let mut evaluated = wf_obligations.iter();
+        infcx.evaluate_root_goals_batched(
+            wf_obligations.iter().map(|&obligation| obligation.clone().into()),
+            self.solver_mode,
+            |result| {
+                let obligation = evaluated.next().unwrap();
+                if let Some(lens) = &mut lens {
+                    self.record_outlives_constraints(infcx, obligation, *lens);
+                    *lens = region_constraints_lens(infcx);
+                }
+                results.push_back(result);
+            },
+        );
+        results
+    }
+
+    /// Records the outlives constraints added to `infcx` since the
+    /// given lengths of its region obligations and region constraints
+    /// as being attributable to the root of `obligation`.
+    fn record_outlives_constraints(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        (region_obligations_len, region_constraints_len): (usize, usize),
+    ) {
+        let region_obligations =
+            infcx.inner.borrow().region_obligations()[region_obligations_len..].to_owned();
+        let constraints = infcx.with_region_constraints(|data| {
+            let new_constraints = RegionConstraintData {
+                constraints: data.constraints[region_constraints_len..].to_owned(),
+                ..Default::default()
+            };
+            make_query_region_constraints(
+                infcx.tcx,
+                region_obligations
+                    .iter()
+                    .map(|r_o| (r_o.sup_type, r_o.sub_region, r_o.origin.to_constraint_category())),
+                &new_constraints,
+            )
+        });
+
+        if !constraints.outlives.is_empty() {
+            self.outlives_constraints
+                .entry(self.obligations.root_obligation(obligation))
+                .or_default()
+                .extend(constraints.outlives);
+        }
+    }
+
+    fn inspect_evaluated_obligation(
+        &self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        result: &Result<(bool, Certainty), NoSolution>,
+    ) {
+        let certainty = match result {
+            Ok((_, c)) => Ok(*c),
+            Err(NoSolution) => Err(NoSolution),
+        };
+        if let Some(inspector) = infcx.obligation_inspector.get() {
+            (inspector)(infcx, &obligation, certainty);
+        }
+        goal_summary::record_goal_outcome(infcx, obligation, certainty);
+        solver_session::record_evaluated(
+            infcx,
+            obligation.cause.body_id,
+            Goal::new(infcx.tcx, obligation.param_env, obligation.predicate),
+            certainty,
+        );
+
+        if let Some(filter) = &infcx.tcx.sess.opts.unstable_opts.dump_solver_proof_trees_span
+            && span_matches_dump_filter(infcx.tcx, obligation.cause.span, filter)
+        {
+            dump_proof_tree(obligation, infcx);
+        }
+    }
+}
+
+impl<'tcx> TraitEngine<'tcx> for FulfillmentCtxt<'tcx> {
+    #[instrument(level = "debug", skip(self, infcx))]
+    fn register_predicate_obligation(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: PredicateObligation<'tcx>,
+    ) {
+        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
+        solver_session::record_registered(
+            infcx,
+            obligation.cause.body_id,
+            Goal::new(infcx.tcx, obligation.param_env, obligation.predicate),
+        );
+        self.obligations.register(infcx, obligation);
+    }
+
+    #[instrument(level = "debug", skip(self, infcx))]
+    fn register_nested_predicate_obligation(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        obligation: PredicateObligation<'tcx>,
+        parent: &PredicateObligation<'tcx>,
+    ) {
+        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
+        solver_session::record_registered(
+            infcx,
+            obligation.cause.body_id,
+            Goal::new(infcx.tcx, obligation.param_env, obligation.predicate),
+        );
+        self.obligations.register_nested(infcx, obligation, parent);
+    }
+
+    fn register_goals(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        cause: &ObligationCause<'tcx>,
+        goals: Vec<Goal<'tcx, ty::Predicate<'tcx>>>,
+    ) {
+        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
+        for &goal in &goals {
+            solver_session::record_registered(infcx, cause.body_id, goal);
+        }
+        self.obligations.register_goals(infcx, cause, goals);
+    }
+
+    fn collect_remaining_errors(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
+        let pending = self.obligations.take_pending_without_overflowed();
+        let overflowed = mem::take(&mut self.obligations.overflowed);
+        let errors = self.errors_for_remaining(infcx, pending, overflowed);
+        self.obligations.roots.clear();
+        errors
+    }
+
+    fn peek_remaining_errors(&self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
+        let pending =
+            self.obligations.pending.iter().map(|pending| pending.obligation.clone()).collect();
+        let overflowed = self.obligations.overflowed.clone();
+        infcx.probe(|_| self.errors_for_remaining(infcx, pending, overflowed))
+    }
+
+    fn select_where_possible(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
+        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
+        if self.check_obligation_order {
+            self.assert_order_independent(infcx);
+        }
+        let mut errors = Vec::new();
+        let body_id = self.obligations.pending.first().map(|p| p.obligation.cause.body_id);
+        // See `-Ztime-solver`. Each pass is reported as a separate activity, so that
+        // they show up in the output of `-Ztime-passes` and in self-profiles.
+        let timed_body = body_id
+            .filter(|_| infcx.tcx.sess.opts.unstable_opts.time_solver)
+            .map(|body_id| infcx.tcx.def_path_str(body_id));
+        let recursion_limit = infcx.tcx.recursion_limit();
+        // See `-Zsolver-pass-warning-threshold`.
+        let pass_warning_threshold =
+            infcx.tcx.sess.opts.unstable_opts.solver_pass_warning_threshold;
+        let mut changed_counts: FxIndexMap<PredicateObligation<'tcx>, usize> = Default::default();
+        // We only need the proof trees of goals which hold when recording
+        // which candidates have been used or when flagging goals relying on
+        // global where-bounds. Otherwise, proof trees are only
+        // computed for goals which fail or stall, see
+        // `reevaluate_with_proof_tree_if_enabled`.
+        let flag_global_where_bounds = infcx.tcx.sess.opts.unstable_opts.solver_global_where_bounds
+            == GlobalWhereBounds::Flag;
+        let generate_proof_tree = if self.selected_candidates.is_some()
+            || self.used_param_env_clauses.is_some()
+            || flag_global_where_bounds
+        {
+            GenerateProofTree::Yes
+        } else {
+            GenerateProofTree::Never
+        };
+        let timing_inspector = infcx.evaluation_timing_inspector.get();
+        let span_profile = span_profile::is_enabled(infcx.tcx);
+        let batch_wf_obligations = generate_proof_tree == GenerateProofTree::Never
+            && self.cancellation.is_none()
+            && timing_inspector.is_none()
+            && !span_profile
+            && !solver_session::is_replaying(infcx.tcx);
+        let mut passes = 0;
+        // Each pass in which some obligation makes progress increases the depth of that
+        // obligation, which overflows once its depth exceeds the recursion limit. As the
+        // obligations stay the same, this means that we always reach a fixpoint.
+        loop {
+            passes += 1;
+            let _pass_timer = timed_body.as_deref().map(|body| {
+                infcx.tcx.prof.verbose_generic_activity_with_arg(
+                    "solve_fulfillment_pass",
+                    format!("{body}, pass {passes}"),
+                )
+            });
+            let mut has_changed = false;
+            let obligations: Vec<_> =
+                self.obligations.unstalled_for_select(self.shuffle_seed).collect();
+            let mut obligations = obligations.into_iter();
+            let mut batched_results = VecDeque::new();
+            while let Some(pending) = obligations.next() {
+                let PendingObligation { obligation, order, stalled_on: _, depth } = pending;
+                // Evaluating a batch has to happen before computing the lengths of the
+                // region constraints, as it records the outlives constraints itself.
+                if batch_wf_obligations && batched_results.is_empty() {
+                    batched_results = self.evaluate_wf_obligations_batched(
+                        infcx,
+                        &obligation,
+                        obligations.as_slice(),
+                    );
+                }
+                let goal = obligation.clone().into();
+                solver_session::replay_goal(
+                    infcx,
+                    self.solver_mode,
+                    obligation.cause.body_id,
+                    obligation.cause.span,
+                    goal,
+                );
+                let region_constraints_lens =
+                    self.track_outlives_constraints.then(|| region_constraints_lens(infcx));
+                let evaluation_start =
+                    (timing_inspector.is_some() || span_profile).then(Instant::now);
+                let batched_result = batched_results.pop_front();
+                let (result, proof_tree) = if let Some(result) = batched_result {
+                    (result, None)
+                } else if let Some(cancellation) = &self.cancellation {
+                    match infcx.evaluate_root_goal_cancellable(
+                        goal,
+                        self.solver_mode,
+                        generate_proof_tree,
+                        cancellation,
+                    ) {
+                        Ok(evaluation) => evaluation,
+                        Err(Interrupted) => {
+                            self.interrupted = true;
+                            self.obligations.requeue(PendingObligation {
+                                obligation,
+                                order,
+                                stalled_on: vec![],
+                                depth,
+                            });
+                            continue;
+                        }
+                    }
+                } else {
+                    infcx.evaluate_root_goal_in_mode(goal, self.solver_mode, generate_proof_tree)
+                };
+                let proof_tree = proof_tree.map(Lrc::new);
+                if let Some(start) = evaluation_start {
+                    let elapsed = start.elapsed();
+                    if let Some(inspector) = timing_inspector {
+                        let certainty = result.map(|(_, certainty)| certainty);
+                        (inspector)(infcx, &obligation, certainty, elapsed);
+                    }
+                    if span_profile {
+                        span_profile::record_evaluation(infcx.tcx, obligation.cause.span, elapsed);
+                    }
+                }
+                if let Some(lens) = region_constraints_lens {
+                    self.record_outlives_constraints(infcx, &obligation, lens);
+                }
+                self.inspect_evaluated_obligation(infcx, &obligation, &result);
+                if generate_proof_tree == GenerateProofTree::Never
+                    && !matches!(result, Ok((_, Certainty::Yes)))
+                {
+                    reevaluate_with_proof_tree_if_enabled(infcx, self.solver_mode, &obligation);
+                }
+                let (changed, certainty) = match result {
+                    Ok(result) => result,
+                    Err(NoSolution) => {
+                        let root_obligation = self.obligations.root_obligation(&obligation);
+                        self.obligations.forget_root(&obligation);
+                        errors.push(fulfillment_error_for_no_solution(
+                            infcx,
+                            &self.obligations.causes,
+                            self.solver_mode,
+                            obligation,
+                            root_obligation,
+                        ));
+                        continue;
+                    }
+                };
+                has_changed |= changed;
+                if changed && pass_warning_threshold.is_some() {
+                    *changed_counts.entry(obligation.clone()).or_default() += 1;
+                }
+                match certainty {
+                    Certainty::Yes => {
+                        self.obligations.forget_root(&obligation);
+                        if let Some(proven_global_goals) = &mut self.proven_global_goals
+                            && let Some(predicate) = proven_global_goal(infcx, goal)
+                        {
+                            proven_global_goals.insert(predicate);
+                        }
+                        self.record_selected_candidate(infcx, &obligation, proof_tree.as_ref());
+                        self.record_used_param_env_clauses(infcx, &obligation, proof_tree.as_ref());
+                        if flag_global_where_bounds {
+                            warn_global_where_bound_preference(
+                                infcx,
+                                &obligation,
+                                proof_tree.as_ref(),
+                            );
+                        }
+                        if let Some(proven) = &mut self.proven {
+                            proven.push(obligation);
+                        }
+                    }
+                    Certainty::Maybe(_) => {
+                        let depth = if changed { depth + 1 } else { depth };
+                        let stalled_on = stalled_on(infcx, &obligation);
+                        let pending = PendingObligation { obligation, order, stalled_on, depth };
+                        if recursion_limit.value_within_limit(depth) {
+                            self.obligations.requeue(pending);
+                        } else {
+                            self.obligations.overflow(infcx, pending);
+                        }
+                    }
+                }
+            }
+
+            if !has_changed || self.interrupted {
+                break;
+            }
+        }
+
+        if let (Some(threshold), Some(body_id)) = (pass_warning_threshold, body_id)
+            && passes > threshold
+        {
+            warn_pathological_pass_count(infcx.tcx, body_id, passes, changed_counts);
+        }
+
+        mark_redundant_errors(&mut errors);
+        errors
+    }
+
+    fn pending_obligations(&self) -> Vec<PredicateObligation<'tcx>> {
+        self.obligations.clone_pending()
+    }
+
+    fn outlives_constraints_by_root(
+        &self,
+    ) -> Vec<(PredicateObligation<'tcx>, QueryOutlivesConstraint<'tcx>)> {
+        self.outlives_constraints
+            .iter()
+            .flat_map(|(root, constraints)| {
+                constraints.iter().map(|&constraint| (root.clone(), constraint))
+            })
+            .collect()
+    }
+
+    fn track_outlives_constraints(&mut self) {
+        self.track_outlives_constraints = true;
+    }
+
+    fn track_used_param_env_clauses(&mut self) {
+        if self.used_param_env_clauses.is_none() {
+            self.used_param_env_clauses = Some(Default::default());
+        }
+    }
+
+    fn used_param_env_clauses(&self) -> Vec<(PredicateObligation<'tcx>, Vec<usize>)> {
+        self.used_param_env_clauses
+            .iter()
+            .flatten()
+            .map(|(obligation, clauses)| (obligation.clone(), clauses.iter().copied().collect()))
+            .collect()
+    }
+
+    fn track_proven_global_goals(&mut self) {
+        if self.proven_global_goals.is_none() {
+            self.proven_global_goals = Some(Default::default());
+        }
+    }
+
+    fn proven_global_goals(&self) -> Vec<ty::Predicate<'tcx>> {
+        self.proven_global_goals.iter().flatten().copied().collect()
+    }
+
+    fn obligations_resolved_by_fallback(
+        &self,
+        infcx: &InferCtxt<'tcx>,
+        var: Ty<'tcx>,
+        fallback_ty: Ty<'tcx>,
+    ) -> Vec<PredicateObligation<'tcx>> {
+        let Some(vid) = infcx.shallow_resolve(var).ty_vid() else {
+            return vec![];
+        };
+        let vid = infcx.root_var(vid);
+        // Only obligations stalled on `var` can be affected by its fallback, so we
+        // avoid evaluating all other pending obligations.
+        self.obligations
+            .pending
+            .iter()
+            .filter(|pending| {
+                pending.stalled_on.iter().any(|&stalled_on| infcx.root_var(stalled_on) == vid)
+            })
+            .map(|pending| &pending.obligation)
+            .filter(|obligation| {
+                infcx.probe(|_| {
+                    let Ok(InferOk { value: (), obligations }) = infcx
+                        .at(&obligation.cause, obligation.param_env)
+                        .eq(DefineOpaqueTypes::No, var, fallback_ty)
+                    else {
+                        return false;
+                    };
+
+                    obligations.into_iter().chain([(*obligation).clone()]).all(|obligation| {
+                        let (result, _) = infcx.evaluate_root_goal_in_mode(
+                            obligation.into(),
+                            self.solver_mode,
+                            GenerateProofTree::Never,
+                        );
+                        matches!(result, Ok((_, Certainty::Yes)))
+                    })
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn drain_unstalled_obligations(
+        &mut self,
+        _: &InferCtxt<'tcx>,
+    ) -> Vec<PredicateObligation<'tcx>> {
+        self.obligations.take_pending()
+    }
+}
+
+/// A single mistake, e.g. a bad function argument, often causes multiple goals to
+/// fail: a `Subtype` goal, a `Coerce` goal and trait goals derived from them. Each of
+/// them results in a separate error, even though they share the same root cause.
+///
+/// We consider errors whose leaf obligations have the same span to be related. For
+/// each such group, all errors other than the first type relation error are marked
+/// as redundant with it if they are either type relations themselves or derived
+/// trait goals. Error reporting then only emits the primary error.
+fn mark_redundant_errors<'tcx>(errors: &mut [FulfillmentError<'tcx>]) {
+    fn is_type_relation(predicate: ty::Predicate<'_>) -> bool {
+        matches!(
+            predicate.kind().skip_binder(),
+            ty::PredicateKind::Subtype(_) | ty::PredicateKind::Coerce(_)
+        )
+    }
+
+    for primary in 0..errors.len() {
+        if errors[primary].redundant_with.is_some()
+            || !is_type_relation(errors[primary].obligation.predicate)
+        {
+            continue;
+        }
+
+        let span = errors[primary].obligation.cause.span;
+        let primary_predicate = errors[primary].obligation.predicate;
+        for (index, error) in errors.iter_mut().enumerate() {
+            if index == primary
+                || error.redundant_with.is_some()
+                || error.obligation.cause.span != span
+            {
+                continue;
+            }
+
+            let is_redundant = if is_type_relation(error.obligation.predicate) {
+                index > primary
+            } else {
+                matches!(
+                    error.obligation.cause.code(),
+                    ObligationCauseCode::BuiltinDerivedObligation(_)
+                        | ObligationCauseCode::ImplDerivedObligation(_)
+                        | ObligationCauseCode::WellFormedDerivedObligation(_)
+                        | ObligationCauseCode::UnsizeTailField(_)
+                        | ObligationCauseCode::UnsizeMetadata(_)
+                        | ObligationCauseCode::TraitAliasBound(_)
+                        | ObligationCauseCode::ObjectCandidateBound(_)
+                )
+            };
+            if is_redundant {
+                error.redundant_with = Some(primary_predicate);
+            }
+        }
+    }
+}
+
+fn fulfillment_error_for_no_solution<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    causes: &CauseCache<'tcx>,
+    solver_mode: SolverMode,
+    obligation: PredicateObligation<'tcx>,
+    root_obligation: PredicateObligation<'tcx>,
+) -> FulfillmentError<'tcx> {
+    let proof_tree = compute_proof_tree(infcx, solver_mode, &obligation);
+    let mut obligation = find_best_leaf_obligation(infcx, causes, &obligation, &proof_tree);
+
+    let code = match obligation.predicate.kind().skip_binder() {
+        ty::PredicateKind::Clause(ty::ClauseKind::Projection(_)) => {
+            FulfillmentErrorCode::ProjectionError(
+                // FIXME: This could be a `Sorts` if the term is a type
+                MismatchedProjectionTypes { err: TypeError::Mismatch },
+            )
+        }
+        ty::PredicateKind::NormalizesTo(normalizes_to) => {
+            FulfillmentErrorCode::ProjectionError(MismatchedProjectionTypes {
+                err: normalizes_to_type_error(infcx, &obligation, normalizes_to),
+            })
+        }
+        ty::PredicateKind::AliasRelate(lhs, rhs, _) => {
+            // `AliasRelate` goals are proven by normalizing their aliases, so we
+            // report them like the `NormalizesTo` goal of their alias side.
+            let err = match (lhs.to_alias_ty(infcx.tcx), rhs.to_alias_ty(infcx.tcx)) {
+                (Some(alias), None) => normalizes_to_type_error(
+                    infcx,
+                    &obligation,
+                    ty::NormalizesTo { alias, term: rhs },
+                ),
+                (None, Some(alias)) => normalizes_to_type_error(
+                    infcx,
+                    &obligation,
+                    ty::NormalizesTo { alias, term: lhs },
+                ),
+                _ => TypeError::Mismatch,
+            };
+            FulfillmentErrorCode::ProjectionError(MismatchedProjectionTypes { err })
+        }
+        ty::PredicateKind::Subtype(pred) => {
+            let (a, b) = infcx.enter_forall_and_leak_universe(
+                obligation.predicate.kind().rebind((pred.a, pred.b)),
+            );
+            let (a, b) = infcx.resolve_vars_if_possible((a, b));
+            let expected_found = ExpectedFound::new(true, a, b);
+            FulfillmentErrorCode::SubtypeError(expected_found, TypeError::Sorts(expected_found))
+        }
+        ty::PredicateKind::Coerce(pred) => {
+            let (a, b) = infcx.enter_forall_and_leak_universe(
+                obligation.predicate.kind().rebind((pred.a, pred.b)),
+            );
+            let (a, b) = infcx.resolve_vars_if_possible((a, b));
+            if let Some((step, source, target)) = coercion_step(a, b) {
+                obligation.cause.map_code(|parent_code| ObligationCauseCode::CoerceGoal {
+                    step,
+                    source,
+                    target,
+                    parent_code,
+                });
+            }
+            let expected_found = ExpectedFound::new(false, a, b);
+            FulfillmentErrorCode::SubtypeError(expected_found, TypeError::Sorts(expected_found))
+        }
+        ty::PredicateKind::Clause(ty::ClauseKind::Trait(pred))
+            if pred.polarity == ty::PredicatePolarity::Positive =>
+        {
+            if let Some(trait_def_id) = unsize_to_non_object_safe_trait(infcx, pred) {
+                FulfillmentErrorCode::SelectionError(SelectionError::TraitNotObjectSafe(
+                    trait_def_id,
+                ))
+            } else {
+                match find_explicit_negative_impl(infcx, &obligation, &proof_tree) {
+                    Some(impl_def_id) => FulfillmentErrorCode::SelectionError(
+                        SelectionError::ExplicitNegativeImpl(impl_def_id),
+                    ),
+                    None => FulfillmentErrorCode::SelectionError(
+                        impl_obligation_failed(infcx.tcx, &obligation)
+                            .unwrap_or(SelectionError::Unimplemented),
+                    ),
+                }
+            }
+        }
+        ty::PredicateKind::ObjectSafe(trait_def_id) => {
+            FulfillmentErrorCode::SelectionError(SelectionError::TraitNotObjectSafe(trait_def_id))
+        }
+        ty::PredicateKind::Clause(ty::ClauseKind::ConstArgHasType(ct, expected_ty)) => {
+            let (ct, expected_ty) = infcx.enter_forall_and_leak_universe(
+                obligation.predicate.kind().rebind((ct, expected_ty)),
+            );
+            let (ct, expected_ty) = infcx.resolve_vars_if_possible((ct, expected_ty));
+            let evaluated =
+                evaluate_const_for_diagnostics(infcx, obligation.param_env, ct, &obligation.cause);
+            FulfillmentErrorCode::SelectionError(SelectionError::ConstArgHasWrongType {
+                ct,
+                evaluated,
+                expected_ty,
+            })
+        }
+        ty::PredicateKind::Clause(ty::ClauseKind::ConstEvaluatable(ct)) => {
+            let ct = infcx.resolve_vars_if_possible(ct);
+            let error = match const_evaluatable::is_const_evaluatable(
+                infcx,
+                ct,
+                obligation.param_env,
+                obligation.cause.span,
+            ) {
+                // The goal failed, so the constant cannot be evaluated,
+                // even if we're unable to tell why.
+                Ok(()) | Err(NotConstEvaluatable::MentionsInfer) => {
+                    NotConstEvaluatable::MentionsParam
+                }
+                Err(error) => error,
+            };
+            FulfillmentErrorCode::SelectionError(SelectionError::NotConstEvaluatable(error))
+        }
+        ty::PredicateKind::ConstEquate(a, b) => {
+            let (a, b) = infcx.enter_forall_and_leak_universe(
+                obligation.predicate.kind().rebind((a, b)),
+            );
+            let expected_found = ExpectedFound::new(true, a, b);
+            FulfillmentErrorCode::ConstEquateError(
+                expected_found,
+                TypeError::ConstMismatch(expected_found),
+            )
+        }
+        ty::PredicateKind::Clause(_) | ty::PredicateKind::Ambiguous => {
+            FulfillmentErrorCode::SelectionError(SelectionError::Unimplemented)
+        }
+    };
+
+    FulfillmentError {
+        obligation,
+        code,
+        root_obligation,
+        proof_tree: Some(proof_tree),
+        blocking_infer_vars: vec![],
+        ambiguity_cause: None,
+        redundant_with: None,
+        overflow_chain: None,
+        reached_step_limit: false,
+    }
+}
+
+/// If the best leaf `obligation` is a where-clause of an impl which applies to its
+/// parent goal, the parent goal fails because of that where-clause instead of there
+/// not being any impl at all.
+fn impl_obligation_failed<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+) -> Option<SelectionError<'tcx>> {
+    if let ObligationCauseCode::ImplDerivedObligation(cause) = obligation.cause.code()
+        && let Some(clause_idx) = cause.impl_def_predicate_index
+        && matches!(tcx.def_kind(cause.impl_or_alias_def_id), DefKind::Impl { .. })
+    {
+        Some(SelectionError::ImplObligationFailed {
+            impl_def_id: cause.impl_or_alias_def_id,
+            clause_idx,
+        })
+    } else {
+        None
+    }
+}
+
+/// Returns the adjustment which would have been necessary to coerce `source` to
+/// `target`, together with the types it would have been applied to. `Coerce` goals
+/// are proven via subtyping, so this is what caused them to fail.
+fn coercion_step<'tcx>(
+    source: Ty<'tcx>,
+    target: Ty<'tcx>,
+) -> Option<(CoercionStep, Ty<'tcx>, Ty<'tcx>)> {
+    match (*source.kind(), *target.kind()) {
+        (ty::FnDef(..) | ty::Closure(..), ty::FnPtr(_)) => {
+            Some((CoercionStep::PointerCast, source, target))
+        }
+        (
+            ty::Ref(_, source_pointee, _) | ty::RawPtr(source_pointee, _),
+            ty::Ref(_, target_pointee, _) | ty::RawPtr(target_pointee, _),
+        ) => {
+            if source_pointee != target_pointee
+                && matches!(target_pointee.kind(), ty::Slice(_) | ty::Str | ty::Dynamic(..))
+            {
+                Some((CoercionStep::Unsize, source_pointee, target_pointee))
+            } else if source.is_ref() && target.is_unsafe_ptr() {
+                Some((CoercionStep::PointerCast, source, target))
+            } else if source.is_ref() && target.is_ref() {
+                Some((CoercionStep::Deref, source, target))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Computes the type error for a failing `NormalizesTo` goal by normalizing
+/// its alias in a probe and comparing the result with the expected term.
+///
+/// Falls back to `TypeError::Mismatch` if the alias cannot be normalized
+/// to a concrete term.
+fn normalizes_to_type_error<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    normalizes_to: ty::NormalizesTo<'tcx>,
+) -> TypeError<'tcx> {
+    let Some(normalizes_to) = obligation.predicate.kind().rebind(normalizes_to).no_bound_vars()
+    else {
+        return TypeError::Mismatch;
+    };
+
+    infcx.probe(|_| {
+        let span = obligation.cause.span;
+        let unconstrained_term: ty::Term<'tcx> = match normalizes_to.term.unpack() {
+            ty::TermKind::Ty(_) => {
+                infcx.next_ty_var(TypeVariableOrigin { param_def_id: None, span }).into()
+            }
+            ty::TermKind::Const(ct) => infcx
+                .next_const_var(ct.ty(), ConstVariableOrigin { param_def_id: None, span })
+                .into(),
+        };
+        let goal = obligation.with(
+            infcx.tcx,
+            ty::PredicateKind::NormalizesTo(ty::NormalizesTo {
+                alias: normalizes_to.alias,
+                term: unconstrained_term,
+            }),
+        );
+        match infcx.evaluate_root_goal(goal.into(), GenerateProofTree::Never).0 {
+            Ok((_, Certainty::Yes)) => {}
+            Ok((_, Certainty::Maybe(_))) | Err(NoSolution) => return TypeError::Mismatch,
+        }
+
+        let computed = infcx.resolve_vars_if_possible(unconstrained_term);
+        let expected = infcx.resolve_vars_if_possible(normalizes_to.term);
+        match (computed.unpack(), expected.unpack()) {
+            (ty::TermKind::Ty(computed), ty::TermKind::Ty(expected)) => {
+                TypeError::Sorts(ExpectedFound::new(true, computed, expected))
+            }
+            (ty::TermKind::Const(computed), ty::TermKind::Const(expected)) => {
+                TypeError::ConstMismatch(ExpectedFound::new(true, computed, expected))
+            }
+            _ => TypeError::Mismatch,
+        }
+    })
+}
+
+fn fulfillment_error_for_stalled<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    causes: &CauseCache<'tcx>,
+    solver_mode: SolverMode,
+    obligation: PredicateObligation<'tcx>,
+    root_obligation: PredicateObligation<'tcx>,
+) -> FulfillmentError<'tcx> {
+    let (code, ambiguity_cause, reached_step_limit) = infcx.probe(|_| {
+        let goal = obligation.clone().into();
+        match infcx.evaluate_root_goal_in_mode(goal, solver_mode, GenerateProofTree::Never).0 {
+            Ok((_, Certainty::Maybe(MaybeCause::Ambiguity(cause)))) => {
+                (FulfillmentErrorCode::Ambiguity { overflow: None }, Some(cause), false)
+            }
+            Ok((
+                _,
+                Certainty::Maybe(MaybeCause::Overflow {
+                    suggest_increasing_limit,
+                    reached_step_limit,
+                }),
+            )) => (
+                FulfillmentErrorCode::Ambiguity { overflow: Some(suggest_increasing_limit) },
+                None,
+                reached_step_limit,
+            ),
+            Ok((_, Certainty::Yes)) => {
+                bug!("did not expect successful goal when collecting ambiguity errors")
+            }
+            Err(_) => {
+                bug!("did not expect selection error when collecting ambiguity errors")
+            }
+        }
+    });
+
+    let proof_tree = compute_proof_tree(infcx, solver_mode, &obligation);
+    let mut visitor = BlockingInferVars { span: obligation.cause.span };
+    let blocking_infer_vars = infcx
+        .visit_existing_proof_tree(&proof_tree, &mut visitor)
+        .break_value()
+        .unwrap_or_default();
+    let overflow_chain = match code {
+        FulfillmentErrorCode::Ambiguity { overflow: Some(_) } => {
+            find_overflow_chain(infcx, &obligation, &proof_tree)
+        }
+        _ => None,
+    };
+    FulfillmentError {
+        obligation: find_best_leaf_obligation(infcx, causes, &obligation, &proof_tree),
+        code,
+        root_obligation,
+        proof_tree: Some(proof_tree),
+        blocking_infer_vars,
+        ambiguity_cause,
+        redundant_with: None,
+        overflow_chain,
+        reached_step_limit,
+    }
+}
+
+/// Warns that `select_where_possible` needed `passes` fixpoint iterations for the
+/// obligations of `body_id`, see `-Zsolver-pass-warning-threshold`. This points to
+/// the obligations which made progress most often, as these are usually responsible
+/// for the slowly propagating inference.
+fn warn_pathological_pass_count<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body_id: LocalDefId,
+    passes: usize,
+    changed_counts: FxIndexMap<PredicateObligation<'tcx>, usize>,
+) {
+    const MAX_LISTED_OBLIGATIONS: usize = 5;
+
+    let mut changed_counts: Vec<_> = changed_counts.into_iter().collect();
+    // This is a stable sort, so obligations with the same count stay in the
+    // order in which they first made progress.
+    changed_counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let obligations = changed_counts
+        .iter()
+        .take(MAX_LISTED_OBLIGATIONS)
+        .map(|(obligation, count)| SolverPassCountObligation {
+            span: obligation.cause.span,
+            predicate: obligation.predicate,
+            count: *count,
+        })
+        .collect();
+    let other_obligations = (changed_counts.len() > MAX_LISTED_OBLIGATIONS).then(|| {
+        SolverPassCountOtherObligations { count: changed_counts.len() - MAX_LISTED_OBLIGATIONS }
+    });
+    tcx.dcx().emit_warn(SolverPassCount {
+        span: tcx.def_span(body_id),
+        passes,
+        obligations,
+        other_obligations,
+    });
+}
+
+/// Warns if `obligation` only holds because a global where-bound has been preferred
+/// over impls, see `-Zsolver-global-where-bounds=flag`.
+fn warn_global_where_bound_preference<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    proof_tree: Option<&Lrc<inspect::GoalEvaluation<'tcx>>>,
+) {
+    let Some(proof_tree) = proof_tree else {
+        return;
+    };
+
+    let mut visitor = GlobalWhereBoundPreference { span: obligation.cause.span };
+    let Some(clause) =
+        infcx.probe(|_| infcx.visit_existing_proof_tree(proof_tree, &mut visitor).break_value())
+    else {
+        return;
+    };
+
+    let predicate = infcx.resolve_vars_if_possible(obligation.predicate);
+    infcx.tcx.dcx().emit_warn(SolverGlobalWhereBoundPreference {
+        span: obligation.cause.span,
+        predicate,
+        clause,
+    });
+}
+
+/// Evaluates the unevaluated constant `ct` for diagnostics, e.g. to point out the value
+/// of a constant argument with the wrong type. Returns `None` if `ct` is not an unevaluated
+/// constant or if evaluating it fails.
+fn evaluate_const_for_diagnostics<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    ct: ty::Const<'tcx>,
+    cause: &ObligationCause<'tcx>,
+) -> Option<ty::Const<'tcx>> {
+    let ty::ConstKind::Unevaluated(uv) = ct.kind() else {
+        return None;
+    };
+    match infcx.const_eval_resolve(param_env, uv, cause.span) {
+        Ok(Some(valtree)) => Some(ty::Const::new_value(infcx.tcx, valtree, ct.ty())),
+        Ok(None) | Err(_) => None,
+    }
+}
+
+/// Checks whether `span` starts at the location given by `filter`, which has the
+/// form `file:line:col` with optional `line` and `col`, as used by
+/// `-Zdump-solver-proof-trees-span`. The file only has to be a suffix of the path.
+fn span_matches_dump_filter(tcx: TyCtxt<'_>, span: Span, filter: &str) -> bool {
+    if span.is_dummy() {
+        return false;
+    }
+
+    let source_map = tcx.sess.source_map();
+    let loc = source_map.lookup_char_pos(span.lo());
+    let mut parts = filter.splitn(3, ':');
+    let file = parts.next().unwrap_or_default();
+    let line = parts.next().map(|line| line.parse::<usize>());
+    let col = parts.next().map(|col| col.parse::<usize>());
+
+    source_map.filename_for_diagnostics(&loc.file.name).to_string().ends_with(file)
+        && line.map_or(true, |line| line == Ok(loc.line))
+        && col.map_or(true, |col| col == Ok(loc.col.0 + 1))
+}
+
+/// The number of region obligations and region constraints of `infcx`, used to find
+/// the constraints added while evaluating an obligation.
+fn region_constraints_lens(infcx: &InferCtxt<'_>) -> (usize, usize) {
+    (
+        infcx.inner.borrow().region_obligations().len(),
+        infcx.with_region_constraints(|data| data.constraints.len()),
+    )
+}
+
+/// Goals are initially evaluated without computing their proof tree, as doing so is
+/// expensive and most goals hold. With `-Znext-solver=dump-tree`, we then
+/// re-evaluate goals which fail or stall in a probe, dumping their proof tree.
+fn reevaluate_with_proof_tree_if_enabled<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    solver_mode: SolverMode,
+    obligation: &PredicateObligation<'tcx>,
+) {
+    let opts = &infcx.tcx.sess.opts.unstable_opts;
+    if opts.next_solver.map(|c| c.dump_tree).unwrap_or_default() != DumpSolverProofTree::Always {
+        return;
+    }
+
+    infcx.probe(|_| {
+        let obligation = infcx.resolve_vars_if_possible(obligation.clone());
+        let _ = infcx.evaluate_root_goal_in_mode(
+            obligation.into(),
+            solver_mode,
+            GenerateProofTree::IfEnabled,
+        );
+    });
+}
+
+/// Computes the proof tree of `obligation`, which is stored in the
+/// `FulfillmentError` so that error reporting can inspect it later.
+fn compute_proof_tree<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    solver_mode: SolverMode,
+    obligation: &PredicateObligation<'tcx>,
+) -> Lrc<inspect::GoalEvaluation<'tcx>> {
+    let obligation = infcx.resolve_vars_if_possible(obligation.clone());
+    let (_, proof_tree) =
+        infcx.evaluate_root_goal_in_mode(obligation.into(), solver_mode, GenerateProofTree::Yes);
+    Lrc::new(proof_tree.unwrap())
+}
+
+fn find_best_leaf_obligation<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    causes: &CauseCache<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    proof_tree: &Lrc<inspect::GoalEvaluation<'tcx>>,
+) -> PredicateObligation<'tcx> {
+    let obligation = infcx.resolve_vars_if_possible(obligation.clone());
+    // Visiting the proof tree instantiates the nested goals of candidates, which
+    // constrains inference variables. We use `fudge_inference_if_ok` to not leak
+    // these constraints while still returning the leaf obligation with its
+    // inference variables resolved.
+    infcx
+        .fudge_inference_if_ok(|| {
+            if infcx.tcx.sess.opts.unstable_opts.next_solver_shallowest_errors {
+                // Search for the shallowest failing leaf by iterative deepening.
+                for depth_limit in 0.. {
+                    let mut visitor = BestObligation {
+                        causes,
+                        obligation: obligation.clone(),
+                        depth: 0,
+                        depth_limit: Some(depth_limit),
+                        hit_depth_limit: false,
+                    };
+                    match infcx.visit_existing_proof_tree(proof_tree, &mut visitor) {
+                        ControlFlow::Break(leaf) => return Ok(leaf),
+                        ControlFlow::Continue(()) if visitor.hit_depth_limit => {}
+                        ControlFlow::Continue(()) => return Err(()),
+                    }
+                }
+            }
+
+            infcx
+                .visit_existing_proof_tree(
+                    proof_tree,
+                    &mut BestObligation {
+                        causes,
+                        obligation: obligation.clone(),
+                        depth: 0,
+                        depth_limit: None,
+                        hit_depth_limit: false,
+                    },
+                )
+                .break_value()
+                .ok_or(())
+        })
+        .map(|leaf| blame_closure_signature(infcx, leaf))
+        .unwrap_or(obligation)
+}
+
+/// Goals arising from the signature of a closure are reported with the cause
+/// of the expression requiring the closure to implement a `Fn` trait. We instead
+/// want these errors to point at the closure itself.
+///
+/// Goals which already have a dedicated cause, e.g. because they were created
+/// while inferring the closure signature or computing a coroutine interior, are
+/// kept as is.
+fn blame_closure_signature<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    mut obligation: PredicateObligation<'tcx>,
+) -> PredicateObligation<'tcx> {
+    if matches!(
+        obligation.cause.code().peel_derives(),
+        ObligationCauseCode::ClosureSignature(_) | ObligationCauseCode::CoroutineInterior(_)
+    ) {
+        return obligation;
+    }
+
+    let tcx = infcx.tcx;
+    let Some(trait_pred) = obligation.predicate.to_opt_poly_trait_pred() else {
+        return obligation;
+    };
+    if tcx.fn_trait_kind_from_def_id(trait_pred.def_id()).is_none() {
+        return obligation;
+    }
+    if let ty::Closure(def_id, _) = *trait_pred.self_ty().skip_binder().kind()
+        && let Some(def_id) = def_id.as_local()
+    {
+        obligation.cause = ObligationCause::new(
+            tcx.def_span(def_id),
+            obligation.cause.body_id,
+            ObligationCauseCode::ClosureSignature(def_id),
+        );
+    }
+    obligation
+}
+
+/// Unsizing to a trait object whose principal trait is not object safe always fails.
+/// Returns that trait in this case, so that we report the reasons why it is not
+/// object safe instead of a generic "trait not implemented" error.
+fn unsize_to_non_object_safe_trait<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    pred: ty::TraitPredicate<'tcx>,
+) -> Option<DefId> {
+    let tcx = infcx.tcx;
+    if Some(pred.def_id()) != tcx.lang_items().unsize_trait() {
+        return None;
+    }
+
+    let target = infcx.shallow_resolve(pred.trait_ref.args.type_at(1));
+    if let ty::Dynamic(data, _, ty::Dyn) = target.kind()
+        && let Some(principal_def_id) = data.principal_def_id()
+        && !tcx.check_is_object_safe(principal_def_id)
+    {
+        Some(principal_def_id)
+    } else {
+        None
+    }
+}
+
+/// Returns the explicit negative impl, e.g. `impl !Send for Foo`, which caused
+/// the trait goal of the leaf obligation `obligation` to fail, if there is one.
+///
+/// This searches for the goal of `obligation` in the failing goals of `proof_tree`,
+/// which is the proof tree of the root obligation, instead of evaluating it again.
+fn find_explicit_negative_impl<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    proof_tree: &Lrc<inspect::GoalEvaluation<'tcx>>,
+) -> Option<DefId> {
+    let mut visitor = ExplicitNegativeImpl {
+        span: obligation.cause.span,
+        predicate: infcx.resolve_vars_if_possible(obligation.predicate),
+    };
+    infcx.probe(|_| infcx.visit_existing_proof_tree(proof_tree, &mut visitor).break_value())
+}
+
+struct ExplicitNegativeImpl<'tcx> {
+    span: Span,
+    predicate: ty::Predicate<'tcx>,
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for ExplicitNegativeImpl<'tcx> {
+    type Result = ControlFlow<DefId>;
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        if goal.goal().predicate == self.predicate {
+            return match goal.explicit_negative_impl() {
+                Some(impl_def_id) => ControlFlow::Break(impl_def_id),
+                None => ControlFlow::Continue(()),
+            };
+        }
+
+        // The leaf obligation is always reached through failing goals.
+        if goal.result().is_ok() {
+            return ControlFlow::Continue(());
+        }
+
+        for candidate in goal.candidates() {
+            candidate.visit_nested_in_probe(self)?;
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Returns the chain of nested goals which caused `obligation` to overflow, by
+/// following nested goals whose result is overflow starting at the root goal.
+fn find_overflow_chain<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    proof_tree: &Lrc<inspect::GoalEvaluation<'tcx>>,
+) -> Option<OverflowChain<'tcx>> {
+    // Like `find_best_leaf_obligation`, instantiating nested goals constrains
+    // inference variables, which we must not leak.
+    infcx
+        .fudge_inference_if_ok(|| {
+            let mut visitor = OverflowChainVisitor { span: obligation.cause.span, goals: vec![] };
+            let truncated = infcx.visit_existing_proof_tree(proof_tree, &mut visitor).is_continue();
+            let goals = infcx.resolve_vars_if_possible(visitor.goals);
+            if goals.is_empty() { Err(()) } else { Ok((goals, truncated)) }
+        })
+        .ok()
+        .map(|(goals, truncated)| OverflowChain { goals, truncated })
+}
+
+/// Collects the goals on the path towards the overflow. Breaks once it reaches
+/// the end of that path, and continues if the path gets truncated because the
+/// proof tree is too deep to be visited completely.
+struct OverflowChainVisitor<'tcx> {
+    span: Span,
+    goals: Vec<ty::Predicate<'tcx>>,
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for OverflowChainVisitor<'tcx> {
+    type Result = ControlFlow<()>;
+
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn config(&self) -> inspect::InspectConfig {
+        inspect::InspectConfig { max_depth: 16, ..Default::default() }
+    }
+
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        fn is_overflow(result: Result<Certainty, NoSolution>) -> bool {
+            matches!(result, Ok(Certainty::Maybe(MaybeCause::Overflow { .. })))
+        }
+
+        self.goals.push(goal.goal().predicate);
+        for candidate in goal.candidates() {
+            if !is_overflow(candidate.result()) {
+                continue;
+            }
+
+            for nested_goal in candidate.instantiate_nested_goals(self.span) {
+                if is_overflow(nested_goal.result()) {
+                    return nested_goal.visit_with(self);
+                }
+            }
+        }
+
+        // None of the nested goals overflowed, so this goal is the end of the chain.
+        ControlFlow::Break(())
+    }
+}
+
+struct BlockingInferVars {
+    span: Span,
 }
 
-impl<'tcx> ObligationStorage<'tcx> {
-    fn register(&mut self, obligation: PredicateObligation<'tcx>) {
-        self.pending.push(obligation);
-    }
+impl<'tcx> ProofTreeVisitor<'tcx> for BlockingInferVars {
+    type Result = ControlFlow<Vec<ty::Ty<'tcx>>>;
 
-    fn clone_pending(&self) -> Vec<PredicateObligation<'tcx>> {
-        let mut obligations = self.pending.clone();
-        obligations.extend(self.overflowed.iter().cloned());
-        obligations
+    fn span(&self) -> Span {
+        self.span
     }
 
-    fn take_pending(&mut self) -> Vec<PredicateObligation<'tcx>> {
-        let mut obligations = mem::take(&mut self.pending);
-        obligations.append(&mut self.overflowed);
-        obligations
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        ControlFlow::Break(goal.blocking_infer_vars(self.span))
     }
+}
 
-    fn unstalled_for_select(&mut self) -> impl Iterator<Item = PredicateObligation<'tcx>> {
-        mem::take(&mut self.pending).into_iter()
+struct SelectedCandidate {
+    span: Span,
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for SelectedCandidate {
+    type Result = ControlFlow<CandidateSource>;
+
+    fn span(&self) -> Span {
+        self.span
     }
 
-    fn on_fulfillment_overflow(&mut self, infcx: &InferCtxt<'tcx>) {
-        infcx.probe(|_| {
-            // IMPORTANT: we must not use solve any inference variables in the obligations
-            // as this is all happening inside of a probe. We use a probe to make sure
-            // we get all obligations involved in the overflow. We pretty much check: if
-            // we were to do another step of `select_where_possible`, which goals would
-            // change.
-            self.overflowed.extend(self.pending.extract_if(|o| {
-                let goal = o.clone().into();
-                let result = infcx.evaluate_root_goal(goal, GenerateProofTree::Never).0;
-                match result {
-                    Ok((has_changed, _)) => has_changed,
-                    _ => false,
-                }
-            }));
-        })
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        match selected_candidate_source(goal) {
+            Some(source) => ControlFlow::Break(source),
+            None => ControlFlow::Continue(()),
+        }
     }
 }
 
-impl<'tcx> FulfillmentCtxt<'tcx> {
-    pub fn new(infcx: &InferCtxt<'tcx>) -> FulfillmentCtxt<'tcx> {
-        assert!(
-            infcx.next_trait_solver(),
-            "new trait solver fulfillment context created when \
-            infcx is set up for old trait solver"
-        );
-        FulfillmentCtxt {
-            obligations: Default::default(),
-            usable_in_snapshot: infcx.num_open_snapshots(),
+/// Checks whether a value mentions the opaque type `def_id`, used by
+/// `FulfillmentCtxt::revalidate_goals_mentioning`.
+struct MentionsOpaque {
+    def_id: DefId,
+}
+
+impl<'tcx> TypeVisitor<TyCtxt<'tcx>> for MentionsOpaque {
+    type Result = ControlFlow<()>;
+
+    fn visit_ty(&mut self, ty: Ty<'tcx>) -> Self::Result {
+        if !ty.has_opaque_types() {
+            return ControlFlow::Continue(());
         }
-    }
 
-    fn inspect_evaluated_obligation(
-        &self,
-        infcx: &InferCtxt<'tcx>,
-        obligation: &PredicateObligation<'tcx>,
-        result: &Result<(bool, Certainty), NoSolution>,
-    ) {
-        if let Some(inspector) = infcx.obligation_inspector.get() {
-            let result = match result {
-                Ok((_, c)) => Ok(*c),
-                Err(NoSolution) => Err(NoSolution),
-            };
-            (inspector)(infcx, &obligation, result);
+        match *ty.kind() {
+            ty::Alias(ty::Opaque, alias_ty) if alias_ty.def_id == self.def_id => {
+                ControlFlow::Break(())
+            }
+            _ => ty.super_visit_with(self),
         }
     }
 }
 
-impl<'tcx> TraitEngine<'tcx> for FulfillmentCtxt<'tcx> {
-    #[instrument(level = "debug", skip(self, infcx))]
-    fn register_predicate_obligation(
-        &mut self,
-        infcx: &InferCtxt<'tcx>,
-        obligation: PredicateObligation<'tcx>,
-    ) {
-        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
-        self.obligations.register(obligation);
+/// Returns the unresolved type inference variables mentioned by `obligation`, which we
+/// consider it to be stalled on if it is ambiguous, see `PendingObligation::stalled_on`.
+fn stalled_on<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+) -> Vec<ty::TyVid> {
+    let predicate = infcx.resolve_vars_if_possible(obligation.predicate);
+    let mut visitor = StalledOn { vars: vec![] };
+    if predicate.has_non_region_infer() {
+        predicate.visit_with(&mut visitor);
     }
+    visitor.vars
+}
 
-    fn collect_remaining_errors(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
-        let mut errors: Vec<_> = self
-            .obligations
-            .pending
-            .drain(..)
-            .map(|obligation| fulfillment_error_for_stalled(infcx, obligation))
-            .collect();
+struct StalledOn {
+    vars: Vec<ty::TyVid>,
+}
 
-        errors.extend(self.obligations.overflowed.drain(..).map(|obligation| FulfillmentError {
-            obligation: find_best_leaf_obligation(infcx, &obligation),
-            code: FulfillmentErrorCode::Ambiguity { overflow: Some(true) },
-            root_obligation: obligation,
-        }));
+impl<'tcx> TypeVisitor<TyCtxt<'tcx>> for StalledOn {
+    fn visit_ty(&mut self, ty: Ty<'tcx>) {
+        if !ty.has_non_region_infer() {
+            return;
+        }
 
-        errors
+        match *ty.kind() {
+            ty::Infer(ty::TyVar(vid)) => self.vars.push(vid),
+            _ => ty.super_visit_with(self),
+        }
     }
+}
 
-    fn select_where_possible(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
-        assert_eq!(self.usable_in_snapshot, infcx.num_open_snapshots());
-        let mut errors = Vec::new();
-        for i in 0.. {
-            if !infcx.tcx.recursion_limit().value_within_limit(i) {
-                self.obligations.on_fulfillment_overflow(infcx);
-                // Only return true errors that we have accumulated while processing.
-                return errors;
-            }
+/// Collects the param-env clauses used to prove a goal which holds by following the
+/// selected candidate of each nested goal. We ignore nested goals with a different
+/// param-env than the root goal, as indices into their `caller_bounds` would be
+/// meaningless to the caller.
+struct UsedParamEnvClauses<'tcx> {
+    span: Span,
+    param_env: ty::ParamEnv<'tcx>,
+    clauses: FxIndexSet<usize>,
+}
 
-            let mut has_changed = false;
-            for obligation in self.obligations.unstalled_for_select() {
-                let goal = obligation.clone().into();
-                let result = infcx.evaluate_root_goal(goal, GenerateProofTree::IfEnabled).0;
-                self.inspect_evaluated_obligation(infcx, &obligation, &result);
-                let (changed, certainty) = match result {
-                    Ok(result) => result,
-                    Err(NoSolution) => {
-                        errors.push(fulfillment_error_for_no_solution(infcx, obligation));
-                        continue;
-                    }
-                };
-                has_changed |= changed;
-                match certainty {
-                    Certainty::Yes => {}
-                    Certainty::Maybe(_) => self.obligations.register(obligation),
-                }
-            }
+impl<'tcx> ProofTreeVisitor<'tcx> for UsedParamEnvClauses<'tcx> {
+    fn span(&self) -> Span {
+        self.span
+    }
 
-            if !has_changed {
-                break;
-            }
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) {
+        if goal.result().is_err() || goal.goal().param_env != self.param_env {
+            return;
         }
 
-        errors
-    }
-
-    fn pending_obligations(&self) -> Vec<PredicateObligation<'tcx>> {
-        self.obligations.clone_pending()
+        let candidates = goal.candidates();
+        let selected = match selected_candidate_source(goal) {
+            Some(CandidateSource::ParamEnv(index)) => {
+                self.clauses.insert(index);
+                return;
+            }
+            Some(source) => candidates.iter().find(|candidate| {
+                matches!(
+                    candidate.kind(),
+                    ProbeKind::TraitCandidate { source: candidate_source, result: _ }
+                        if candidate_source == source
+                )
+            }),
+            None => match candidates.as_slice() {
+                [candidate] if candidate.result().is_ok() => Some(candidate),
+                _ => None,
+            },
+        };
+        if let Some(candidate) = selected {
+            candidate.visit_nested_in_probe(self);
+        }
     }
+}
 
-    fn drain_unstalled_obligations(
-        &mut self,
-        _: &InferCtxt<'tcx>,
-    ) -> Vec<PredicateObligation<'tcx>> {
-        self.obligations.take_pending()
-    }
+/// Finds the first goal whose impl candidates have been discarded in favor of a
+/// global where-bound, see `InspectGoal::shadowed_by_global_where_bound`.
+struct GlobalWhereBoundPreference {
+    span: Span,
 }
 
-fn fulfillment_error_for_no_solution<'tcx>(
-    infcx: &InferCtxt<'tcx>,
-    root_obligation: PredicateObligation<'tcx>,
-) -> FulfillmentError<'tcx> {
-    let obligation = find_best_leaf_obligation(infcx, &root_obligation);
+impl<'tcx> ProofTreeVisitor<'tcx> for GlobalWhereBoundPreference {
+    type Result = ControlFlow<ty::Clause<'tcx>>;
 
-    let code = match obligation.predicate.kind().skip_binder() {
-        ty::PredicateKind::Clause(ty::ClauseKind::Projection(_)) => {
-            FulfillmentErrorCode::ProjectionError(
-                // FIXME: This could be a `Sorts` if the term is a type
-                MismatchedProjectionTypes { err: TypeError::Mismatch },
-            )
-        }
-        ty::PredicateKind::NormalizesTo(..) => {
-            FulfillmentErrorCode::ProjectionError(MismatchedProjectionTypes {
-                err: TypeError::Mismatch,
-            })
-        }
-        ty::PredicateKind::AliasRelate(_, _, _) => {
-            FulfillmentErrorCode::ProjectionError(MismatchedProjectionTypes {
-                err: TypeError::Mismatch,
-            })
-        }
-        ty::PredicateKind::Subtype(pred) => {
-            let (a, b) = infcx.enter_forall_and_leak_universe(
-                obligation.predicate.kind().rebind((pred.a, pred.b)),
-            );
-            let expected_found = ExpectedFound::new(true, a, b);
-            FulfillmentErrorCode::SubtypeError(expected_found, TypeError::Sorts(expected_found))
-        }
-        ty::PredicateKind::Coerce(pred) => {
-            let (a, b) = infcx.enter_forall_and_leak_universe(
-                obligation.predicate.kind().rebind((pred.a, pred.b)),
-            );
-            let expected_found = ExpectedFound::new(false, a, b);
-            FulfillmentErrorCode::SubtypeError(expected_found, TypeError::Sorts(expected_found))
-        }
-        ty::PredicateKind::Clause(_)
-        | ty::PredicateKind::ObjectSafe(_)
-        | ty::PredicateKind::Ambiguous => {
-            FulfillmentErrorCode::SelectionError(SelectionError::Unimplemented)
-        }
-        ty::PredicateKind::ConstEquate(..) => {
-            bug!("unexpected goal: {obligation:?}")
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::Result {
+        if goal.result().is_err() {
+            return ControlFlow::Continue(());
         }
-    };
 
-    FulfillmentError { obligation, code, root_obligation }
-}
+        if let Some(clause) = goal.shadowed_by_global_where_bound() {
+            return ControlFlow::Break(clause);
+        }
 
-fn fulfillment_error_for_stalled<'tcx>(
-    infcx: &InferCtxt<'tcx>,
-    obligation: PredicateObligation<'tcx>,
-) -> FulfillmentError<'tcx> {
-    let code = infcx.probe(|_| {
-        match infcx.evaluate_root_goal(obligation.clone().into(), GenerateProofTree::Never).0 {
-            Ok((_, Certainty::Maybe(MaybeCause::Ambiguity))) => {
-                FulfillmentErrorCode::Ambiguity { overflow: None }
-            }
-            Ok((_, Certainty::Maybe(MaybeCause::Overflow { suggest_increasing_limit }))) => {
-                FulfillmentErrorCode::Ambiguity { overflow: Some(suggest_increasing_limit) }
-            }
-            Ok((_, Certainty::Yes)) => {
-                bug!("did not expect successful goal when collecting ambiguity errors")
-            }
-            Err(_) => {
-                bug!("did not expect selection error when collecting ambiguity errors")
+        for candidate in goal.candidates() {
+            if candidate.result().is_ok() {
+                candidate.visit_nested_in_probe(self)?;
             }
         }
-    });
-
-    FulfillmentError {
-        obligation: find_best_leaf_obligation(infcx, &obligation),
-        code,
-        root_obligation: obligation,
+        ControlFlow::Continue(())
     }
 }
 
-fn find_best_leaf_obligation<'tcx>(
-    infcx: &InferCtxt<'tcx>,
-    obligation: &PredicateObligation<'tcx>,
-) -> PredicateObligation<'tcx> {
-    let obligation = infcx.resolve_vars_if_possible(obligation.clone());
-    infcx
-        .visit_proof_tree(
-            obligation.clone().into(),
-            &mut BestObligation { obligation: obligation.clone() },
-        )
-        .break_value()
-        .unwrap_or(obligation)
-}
-
-struct BestObligation<'tcx> {
+struct BestObligation<'a, 'tcx> {
+    causes: &'a CauseCache<'tcx>,
     obligation: PredicateObligation<'tcx>,
+    /// The number of nested goals between the root goal and the current one.
+    depth: usize,
+    /// If set, we do not descend into nested goals deeper than this. As we then
+    /// do not know whether goals at this depth are responsible for the error, we
+    /// do not report them if they have failing nested goals.
+    depth_limit: Option<usize>,
+    /// Whether we skipped any failing nested goals due to `depth_limit`.
+    hit_depth_limit: bool,
 }
 
-impl<'tcx> BestObligation<'tcx> {
+impl<'tcx> BestObligation<'_, 'tcx> {
     fn with_derived_obligation(
         &mut self,
         derived_obligation: PredicateObligation<'tcx>,
@@ -303,10 +2115,10 @@ fn with_derived_obligation(
     }
 }
 
-impl<'tcx> ProofTreeVisitor<'tcx> for BestObligation<'tcx> {
+impl<'tcx> ProofTreeVisitor<'tcx> for BestObligation<'_, 'tcx> {
     type Result = ControlFlow<PredicateObligation<'tcx>>;
 
-    fn span(&self) -> rustc_span::Span {
+    fn span(&self) -> Span {
         self.obligation.cause.span
     }
 
@@ -320,25 +2132,74 @@ fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::
             return ControlFlow::Break(self.obligation.clone());
         };
 
+        // Don't descend into impls marked with `#[do_not_recommend]`, as their
+        // where-bounds are an implementation detail the user shouldn't see.
+        if let ProbeKind::TraitCandidate { source: CandidateSource::Impl(impl_def_id), result: _ } =
+            candidate.kind()
+            && goal.infcx().tcx.has_attr(impl_def_id, sym::do_not_recommend)
+        {
+            return ControlFlow::Break(self.obligation.clone());
+        }
+
         // FIXME: Could we extract a trait ref from a projection here too?
         // FIXME: Also, what about considering >1 layer up the stack? May be necessary
         // for normalizes-to.
-        let Some(parent_trait_pred) = goal.goal().predicate.to_opt_poly_trait_pred() else {
-            return ControlFlow::Break(self.obligation.clone());
+        let parent_trait_pred = goal.goal().predicate.to_opt_poly_trait_pred();
+        let wf_obligations = match goal.goal().predicate.kind().skip_binder() {
+            ty::PredicateKind::Clause(ty::ClauseKind::WellFormed(arg)) => {
+                wf::unnormalized_obligations(goal.infcx(), goal.goal().param_env, arg)
+            }
+            _ => None,
         };
+        if parent_trait_pred.is_none() && wf_obligations.is_none() {
+            return ControlFlow::Break(self.obligation.clone());
+        }
 
         let tcx = goal.infcx().tcx;
+        let nested_goals = candidate.instantiate_nested_goals(self.span());
+        // We usually do not descend into `GoalSource::Misc` goals, as they tend to be
+        // implementation details of the solver. If they are the only nested goals which
+        // do not hold, they are responsible for the failure however, so we descend into
+        // them in this case.
+        let descend_into_misc = nested_goals.iter().all(|nested_goal| {
+            nested_goal.source() == GoalSource::Misc
+                || matches!(nested_goal.result(), Ok(Certainty::Yes))
+        });
         let mut impl_where_bound_count = 0;
-        for nested_goal in candidate.instantiate_nested_goals(self.span()) {
+        let mut skipped_due_to_depth_limit = false;
+        for nested_goal in nested_goals {
             let obligation;
             match nested_goal.source() {
                 GoalSource::Misc => {
-                    continue;
+                    if !descend_into_misc {
+                        continue;
+                    }
+                    let mut cause = self.obligation.cause.clone();
+                    if let Some(parent_trait_pred) = parent_trait_pred {
+                        cause = cause
+                            .derived_cause(parent_trait_pred, traits::BuiltinDerivedObligation);
+                    }
+                    obligation = Obligation {
+                        cause,
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
                 }
                 GoalSource::ImplWhereBound => {
+                    let Some(parent_trait_pred) = parent_trait_pred else {
+                        continue;
+                    };
+                    // Avoid deriving the cause of where-bounds which hold, as each of
+                    // them needs its own cause code.
+                    if matches!(nested_goal.result(), Ok(Certainty::Yes)) {
+                        impl_where_bound_count += 1;
+                        continue;
+                    }
                     obligation = Obligation {
                         cause: derive_cause(
                             tcx,
+                            self.causes,
                             candidate.kind(),
                             self.obligation.cause.clone(),
                             impl_where_bound_count,
@@ -350,8 +2211,82 @@ fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::
                     };
                     impl_where_bound_count += 1;
                 }
+                source @ (GoalSource::UnsizeTailField | GoalSource::UnsizeMetadata) => {
+                    let Some(parent_trait_pred) = parent_trait_pred else {
+                        continue;
+                    };
+                    let cause = self.obligation.cause.clone();
+                    obligation = Obligation {
+                        cause: if source == GoalSource::UnsizeTailField {
+                            cause.derived_cause(parent_trait_pred, traits::UnsizeTailField)
+                        } else {
+                            cause.derived_cause(parent_trait_pred, traits::UnsizeMetadata)
+                        },
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
+                }
+                GoalSource::TraitAliasBound => {
+                    // Look through the trait alias, only mentioning it in a note, so
+                    // that we blame the bound of the alias which does not hold.
+                    let Some(parent_trait_pred) = parent_trait_pred else {
+                        continue;
+                    };
+                    obligation = Obligation {
+                        cause: self
+                            .obligation
+                            .cause
+                            .clone()
+                            .derived_cause(parent_trait_pred, traits::TraitAliasBound),
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
+                }
+                GoalSource::ObjectBound => {
+                    let Some(parent_trait_pred) = parent_trait_pred else {
+                        continue;
+                    };
+                    let supertrait_path = object_supertrait_path(tcx, parent_trait_pred);
+                    obligation = Obligation {
+                        cause: self.obligation.cause.clone().derived_cause(
+                            parent_trait_pred,
+                            |derived| {
+                                traits::ObjectCandidateBound(Box::new(
+                                    traits::ObjectCandidateBoundCause { derived, supertrait_path },
+                                ))
+                            },
+                        ),
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
+                }
                 GoalSource::InstantiateHigherRanked => {
-                    obligation = self.obligation.clone();
+                    let mut cause = self.obligation.cause.clone();
+                    cause.map_code(|parent_code| traits::InstantiatedHigherRanked {
+                        higher_ranked_predicate: goal.goal().predicate,
+                        parent_code,
+                    });
+                    obligation = Obligation {
+                        cause,
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
+                }
+                GoalSource::WellFormed => {
+                    obligation = Obligation {
+                        cause: derive_wf_cause(
+                            wf_obligations.as_deref().unwrap_or_default(),
+                            self.obligation.cause.clone(),
+                            nested_goal.goal().predicate,
+                        ),
+                        param_env: nested_goal.goal().param_env,
+                        predicate: nested_goal.goal().predicate,
+                        recursion_depth: self.obligation.recursion_depth + 1,
+                    };
                 }
             }
 
@@ -362,39 +2297,141 @@ fn visit_goal(&mut self, goal: &super::inspect::InspectGoal<'_, 'tcx>) -> Self::
                 continue;
             }
 
-            self.with_derived_obligation(obligation, |this| nested_goal.visit_with(this))?;
+            if self.depth_limit.is_some_and(|depth_limit| self.depth >= depth_limit) {
+                self.hit_depth_limit = true;
+                skipped_due_to_depth_limit = true;
+                continue;
+            }
+
+            self.depth += 1;
+            let res = self.with_derived_obligation(obligation, |this| nested_goal.visit_with(this));
+            self.depth -= 1;
+            res?;
+        }
+
+        // One of the skipped nested goals may be responsible for the error,
+        // so we don't know whether the current goal is the best leaf.
+        if skipped_due_to_depth_limit {
+            return ControlFlow::Continue(());
+        }
+
+        // If we stopped visiting nested goals because the proof tree is too large,
+        // the current obligation may not be the actual cause of the error. Fall back
+        // to the root obligation in this case.
+        if goal.budget_exhausted() {
+            return ControlFlow::Continue(());
         }
 
         ControlFlow::Break(self.obligation.clone())
     }
 }
 
+/// Uses the cause of the matching obligation computed by `wf::unnormalized_obligations`,
+/// e.g. a where-bound of the type being checked, while keeping the span and body
+/// of the original obligation.
+fn derive_wf_cause<'tcx>(
+    wf_obligations: &[PredicateObligation<'tcx>],
+    cause: ObligationCause<'tcx>,
+    predicate: ty::Predicate<'tcx>,
+) -> ObligationCause<'tcx> {
+    match wf_obligations.iter().find(|obligation| obligation.predicate == predicate) {
+        Some(wf_obligation) => wf_obligation.cause.with_span_and_body_id(cause.span, cause.body_id),
+        None => cause,
+    }
+}
+
+/// Returns the supertraits elaborated from the principal trait of the object type
+/// `dyn Trait` to get to the trait of `trait_pred`, starting with the principal.
+/// The self type of `trait_pred` has to be an object type.
+fn object_supertrait_path<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_pred: ty::PolyTraitPredicate<'tcx>,
+) -> Vec<ty::PolyTraitRef<'tcx>> {
+    fn search<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        target: DefId,
+        path: &mut Vec<ty::PolyTraitRef<'tcx>>,
+        visited: &mut FxHashSet<DefId>,
+    ) -> bool {
+        let trait_ref = *path.last().unwrap();
+        if trait_ref.def_id() == target {
+            return true;
+        } else if !visited.insert(trait_ref.def_id()) {
+            return false;
+        }
+
+        for &(clause, _) in tcx.super_predicates_of(trait_ref.def_id()).predicates {
+            let Some(supertrait) = clause.instantiate_supertrait(tcx, &trait_ref).as_trait_clause()
+            else {
+                continue;
+            };
+            path.push(supertrait.map_bound(|pred| pred.trait_ref));
+            if search(tcx, target, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let self_ty = trait_pred.self_ty().skip_binder();
+    let ty::Dynamic(bounds, ..) = *self_ty.kind() else {
+        return vec![];
+    };
+    let Some(principal) = bounds.principal() else {
+        return vec![];
+    };
+
+    let mut path = vec![principal.with_self_ty(tcx, self_ty)];
+    if search(tcx, trait_pred.def_id(), &mut path, &mut FxHashSet::default()) {
+        path
+    } else {
+        vec![]
+    }
+}
+
 fn derive_cause<'tcx>(
     tcx: TyCtxt<'tcx>,
+    causes: &CauseCache<'tcx>,
     candidate_kind: ProbeKind<'tcx>,
-    mut cause: ObligationCause<'tcx>,
+    cause: ObligationCause<'tcx>,
     idx: usize,
     parent_trait_pred: ty::PolyTraitPredicate<'tcx>,
 ) -> ObligationCause<'tcx> {
-    match candidate_kind {
+    let impl_def_id = match candidate_kind {
         ProbeKind::TraitCandidate { source: CandidateSource::Impl(impl_def_id), result: _ } => {
-            if let Some((_, span)) =
-                tcx.predicates_of(impl_def_id).instantiate_identity(tcx).iter().nth(idx)
-            {
-                cause = cause.derived_cause(parent_trait_pred, |derived| {
+            Some(impl_def_id)
+        }
+        ProbeKind::TraitCandidate { source: CandidateSource::BuiltinImpl(..), result: _ } => None,
+        _ => return cause,
+    };
+
+    // The same where-bound is often derived from the same cause multiple times,
+    // e.g. when computing the errors of several obligations, so we share the
+    // derived cause instead of creating a new cause code each time.
+    let key = (impl_def_id, idx, parent_trait_pred);
+    if let Some((parent_cause, derived_cause)) = causes.derived.borrow().get(&key)
+        && *parent_cause == cause
+    {
+        return derived_cause.clone();
+    }
+
+    let derived_cause = match impl_def_id {
+        Some(impl_def_id) => {
+            match tcx.predicates_of(impl_def_id).instantiate_identity(tcx).iter().nth(idx) {
+                Some((_, span)) => cause.clone().derived_cause(parent_trait_pred, |derived| {
                     traits::ImplDerivedObligation(Box::new(traits::ImplDerivedObligationCause {
                         derived,
                         impl_or_alias_def_id: impl_def_id,
                         impl_def_predicate_index: Some(idx),
                         span,
                     }))
-                })
+                }),
+                None => cause.clone(),
             }
         }
-        ProbeKind::TraitCandidate { source: CandidateSource::BuiltinImpl(..), result: _ } => {
-            cause = cause.derived_cause(parent_trait_pred, traits::BuiltinDerivedObligation);
-        }
-        _ => {}
+        None => cause.clone().derived_cause(parent_trait_pred, traits::BuiltinDerivedObligation),
     };
-    cause
+    causes.derived.borrow_mut().insert(key, (cause, derived_cause.clone()));
+    derived_cause
 }
@@ -0,0 +1,141 @@
+//! Summaries of the goals evaluated by the new solver, see `-Zdump-solver-goal-summary`
+//! and `-Zcompare-solver-goal-summary`.
+//!
+//! For each body, we record the outcome of its root goals, keyed by the stable hash of
+//! the canonicalized goal. Comparing the summaries of two compilations, e.g. before and
+//! after a change to the solver, then shows the goals whose outcome changed, which is a
+//! lot easier than comparing full proof tree dumps.
+
+use std::fs;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{Hash64, HashStable, StableHasher};
+use rustc_infer::infer::canonical::OriginalQueryValues;
+use rustc_infer::infer::InferCtxt;
+use rustc_infer::traits::query::NoSolution;
+use rustc_infer::traits::PredicateObligation;
+use rustc_middle::traits::solve::{Certainty, GoalOutcome};
+use rustc_middle::ty::TyCtxt;
+
+fn is_enabled(tcx: TyCtxt<'_>) -> bool {
+    let opts = &tcx.sess.opts.unstable_opts;
+    opts.dump_solver_goal_summary.is_some() || opts.compare_solver_goal_summary.is_some()
+}
+
+/// Records the outcome of evaluating the root goal of `obligation`.
+pub(super) fn record_goal_outcome<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    result: Result<Certainty, NoSolution>,
+) {
+    let tcx = infcx.tcx;
+    if !is_enabled(tcx) {
+        return;
+    }
+
+    let hash = goal_hash(infcx, obligation);
+    // Each goal is stored on a single line, so we have to get rid of any line
+    // breaks and tabs in its description.
+    let description = infcx
+        .resolve_vars_if_possible(obligation.predicate)
+        .to_string()
+        .replace(|c: char| c == '\t' || c == '\n', " ");
+    tcx.new_solver_goal_summary
+        .lock()
+        .insert((obligation.cause.body_id, hash), (description, GoalOutcome::from_result(result)));
+}
+
+/// The stable hash of the canonicalized goal of `obligation`. This does not depend
+/// on the inference variables of `infcx`, so it is stable across compilations.
+pub(super) fn goal_hash<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+) -> Hash64 {
+    let canonical = infcx.canonicalize_query(
+        obligation.param_env.and(obligation.predicate),
+        &mut OriginalQueryValues::default(),
+    );
+    infcx.tcx.with_stable_hashing_context(|mut hcx| {
+        let mut hasher = StableHasher::new();
+        canonical.hash_stable(&mut hcx, &mut hasher);
+        hasher.finish()
+    })
+}
+
+/// Writes the summary of the goals evaluated by the new solver and compares it with the
+/// summary of a previous compilation, depending on the enabled options. The summary
+/// contains one line per goal: the path of its body, its hash, its outcome, and the goal
+/// itself, separated by tabs.
+pub fn emit_goal_summary(tcx: TyCtxt<'_>) {
+    if !is_enabled(tcx) {
+        return;
+    }
+
+    let goals: Vec<_> = tcx.new_solver_goal_summary.lock().drain(..).collect();
+    let goals: Vec<_> = goals
+        .into_iter()
+        .map(|((body_id, hash), (goal, outcome))| {
+            (tcx.def_path_str(body_id), format!("{:016x}", hash.as_u64()), goal, outcome)
+        })
+        .collect();
+
+    // We compare before writing the summary, so that both options can use the same file.
+    if let Some(path) = &tcx.sess.opts.unstable_opts.compare_solver_goal_summary {
+        let previous = match fs::read_to_string(path) {
+            Ok(previous) => previous,
+            Err(err) => {
+                tcx.dcx().err(format!(
+                    "failed to read the solver goal summary `{}`: {err}",
+                    path.display()
+                ));
+                return;
+            }
+        };
+
+        let mut previous_outcomes = FxHashMap::default();
+        for line in previous.lines() {
+            let mut parts = line.splitn(4, '\t');
+            let (Some(body), Some(hash), Some(outcome), Some(_goal)) =
+                (parts.next(), parts.next(), parts.next(), parts.next())
+            else {
+                tcx.dcx().err(format!("malformed solver goal summary `{}`", path.display()));
+                return;
+            };
+            let Some(outcome) = GoalOutcome::from_str(outcome) else {
+                tcx.dcx().err(format!(
+                    "malformed solver goal summary `{}`: unknown outcome `{outcome}`",
+                    path.display()
+                ));
+                return;
+            };
+            previous_outcomes.insert((body, hash), outcome);
+        }
+
+        for (body, hash, goal, outcome) in &goals {
+            if let Some(&previous) = previous_outcomes.get(&(body.as_str(), hash.as_str()))
+                && previous != *outcome
+            {
+                tcx.dcx().warn(format!(
+                    "the outcome of `{goal}` in `{body}` changed from `{}` to `{}`",
+                    previous.as_str(),
+                    outcome.as_str(),
+                ));
+            }
+        }
+    }
+
+    if let Some(path) = &tcx.sess.opts.unstable_opts.dump_solver_goal_summary {
+        let summary: String = goals
+            .iter()
+            .map(|(body, hash, goal, outcome)| {
+                format!("{body}\t{hash}\t{}\t{goal}\n", outcome.as_str())
+            })
+            .collect();
+        if let Err(err) = fs::write(path, summary) {
+            tcx.dcx().err(format!(
+                "failed to write the solver goal summary to `{}`: {err}",
+                path.display()
+            ));
+        }
+    }
+}
@@ -9,8 +9,14 @@
 //! coherence right now and was annoying to implement, so I am leaving it
 //! as is until we start using it for something else.
 
+use std::cell::Cell;
+use std::rc::Rc;
+
 use rustc_ast_ir::try_visit;
 use rustc_ast_ir::visit::VisitorResult;
+use rustc_data_structures::fx::FxIndexSet;
+use rustc_data_structures::sync::Lrc;
+use rustc_hir::def_id::DefId;
 use rustc_infer::infer::resolve::EagerResolver;
 use rustc_infer::infer::type_variable::TypeVariableOrigin;
 use rustc_infer::infer::{DefineOpaqueTypes, InferCtxt, InferOk};
@@ -18,30 +24,68 @@
 use rustc_middle::infer::unify_key::ConstVariableOrigin;
 use rustc_middle::traits::query::NoSolution;
 use rustc_middle::traits::solve::{inspect, QueryResult};
-use rustc_middle::traits::solve::{Certainty, Goal};
+use rustc_middle::traits::solve::{Certainty, Goal, MaybeCause};
 use rustc_middle::traits::ObligationCause;
-use rustc_middle::ty;
+use rustc_middle::ty::{self, Ty};
 use rustc_middle::ty::TypeFoldable;
 use rustc_span::{Span, DUMMY_SP};
 
 use crate::solve::eval_ctxt::canonical;
-use crate::solve::{EvalCtxt, GoalEvaluationKind, GoalSource};
-use crate::solve::{GenerateProofTree, InferCtxtEvalExt};
+use crate::solve::{CanonicalResponseExt, EvalCtxt, GoalEvaluationKind, GoalSource};
+use crate::solve::{GenerateProofTree, InferCtxtEvalExt, SolverMode};
 use crate::traits::ObligationCtxt;
 
 pub struct InspectConfig {
     pub max_depth: usize,
+    /// The maximum number of nested goals which get instantiated and evaluated
+    /// again while visiting a single proof tree. Once this budget is exhausted,
+    /// the nested goals of candidates are no longer visited.
+    ///
+    /// This avoids hangs when visiting exponentially large proof trees.
+    pub max_nested_evaluations: usize,
+}
+
+impl Default for InspectConfig {
+    fn default() -> Self {
+        InspectConfig { max_depth: 10, max_nested_evaluations: 1000 }
+    }
 }
 
 pub struct InspectGoal<'a, 'tcx> {
     infcx: &'a InferCtxt<'tcx>,
     depth: usize,
+    /// The remaining number of nested evaluations, shared between
+    /// all goals of the visited proof tree.
+    budget: Rc<Cell<usize>>,
     orig_values: Vec<ty::GenericArg<'tcx>>,
     goal: Goal<'tcx, ty::Predicate<'tcx>>,
+    max_input_universe: ty::UniverseIndex,
     result: Result<Certainty, NoSolution>,
     evaluation_kind: inspect::CanonicalGoalEvaluationKind<'tcx>,
     normalizes_to_term_hack: Option<NormalizesToTermHack<'tcx>>,
     source: GoalSource,
+    saved: SavedInspectGoal<'tcx>,
+}
+
+/// A handle to a goal of a proof tree which can be used to resume visiting
+/// the proof tree from that goal, see [`ProofTreeInferCtxtExt::visit_saved_goal`].
+///
+/// This stores the root proof tree together with the path to the goal,
+/// so resuming only instantiates the nested goals along that path instead
+/// of visiting the whole proof tree again.
+#[derive(Clone)]
+pub struct SavedInspectGoal<'tcx> {
+    root: Lrc<inspect::GoalEvaluation<'tcx>>,
+    /// For each step from the root, the index of the candidate and
+    /// the index of the nested goal of that candidate.
+    path: Vec<(usize, usize)>,
+}
+
+/// A handle to a candidate of a proof tree, see [`SavedInspectGoal`].
+#[derive(Clone)]
+pub struct SavedInspectCandidate<'tcx> {
+    goal: SavedInspectGoal<'tcx>,
+    index: usize,
 }
 
 /// The expected term of a `NormalizesTo` goal gets replaced
@@ -88,10 +132,16 @@ fn constrain(
     }
 }
 
+type OpaqueTypeConstraint<'tcx> =
+    inspect::CanonicalState<'tcx, (ty::OpaqueTypeKey<'tcx>, Ty<'tcx>)>;
+
 pub struct InspectCandidate<'a, 'tcx> {
     goal: &'a InspectGoal<'a, 'tcx>,
+    /// The index of this candidate in [`InspectGoal::candidates`].
+    index: usize,
     kind: inspect::ProbeKind<'tcx>,
     nested_goals: Vec<(GoalSource, inspect::CanonicalState<'tcx, Goal<'tcx, ty::Predicate<'tcx>>>)>,
+    opaque_type_constraints: Vec<OpaqueTypeConstraint<'tcx>>,
     final_state: inspect::CanonicalState<'tcx, ()>,
     result: QueryResult<'tcx>,
     shallow_certainty: Certainty,
@@ -122,10 +172,20 @@ pub fn shallow_certainty(&self) -> Certainty {
         self.shallow_certainty
     }
 
+    /// Returns a handle which can be used to visit the nested goals of this
+    /// candidate again later on, see [`ProofTreeInferCtxtExt::visit_saved_candidate`].
+    pub fn save(&self) -> SavedInspectCandidate<'tcx> {
+        SavedInspectCandidate { goal: self.goal.save(), index: self.index }
+    }
+
     /// Visit all nested goals of this candidate without rolling
     /// back their inference constraints. This function modifies
     /// the state of the `infcx`.
     pub fn visit_nested_no_probe<V: ProofTreeVisitor<'tcx>>(&self, visitor: &mut V) -> V::Result {
+        if self.goal.budget_exhausted() {
+            return V::Result::output();
+        }
+
         for goal in self.instantiate_nested_goals(visitor.span()) {
             try_visit!(goal.visit_with(visitor));
         }
@@ -135,7 +195,19 @@ pub fn visit_nested_no_probe<V: ProofTreeVisitor<'tcx>>(&self, visitor: &mut V)
 
     /// Instantiate the nested goals for the candidate without rolling back their
     /// inference constraints. This function modifies the state of the `infcx`.
+    ///
+    /// This returns no goals if doing so would exceed the remaining budget
+    /// of nested evaluations, see [`InspectConfig::max_nested_evaluations`].
     pub fn instantiate_nested_goals(&self, span: Span) -> Vec<InspectGoal<'a, 'tcx>> {
+        let budget = &self.goal.budget;
+        match budget.get().checked_sub(self.nested_goals.len()) {
+            Some(remaining) => budget.set(remaining),
+            None => {
+                budget.set(0);
+                return vec![];
+            }
+        }
+
         let infcx = self.goal.infcx;
         let param_env = self.goal.goal.param_env;
         let mut orig_values = self.goal.orig_values.to_vec();
@@ -171,9 +243,15 @@ pub fn instantiate_nested_goals(&self, span: Span) -> Vec<InspectGoal<'a, 'tcx>>
             let _ = term_hack.constrain(infcx, span, param_env);
         }
 
+        let saved_goal = |nested_index| {
+            let mut saved = self.goal.saved.clone();
+            saved.path.push((self.index, nested_index));
+            saved
+        };
         instantiated_goals
             .into_iter()
-            .map(|(source, goal)| match goal.predicate.kind().no_bound_vars() {
+            .enumerate()
+            .map(|(nested_index, (source, goal))| match goal.predicate.kind().no_bound_vars() {
                 Some(ty::PredicateKind::NormalizesTo(ty::NormalizesTo { alias, term })) => {
                     let unconstrained_term = match term.unpack() {
                         ty::TermKind::Ty(_) => infcx
@@ -188,29 +266,66 @@ pub fn instantiate_nested_goals(&self, span: Span) -> Vec<InspectGoal<'a, 'tcx>>
                     };
                     let goal =
                         goal.with(infcx.tcx, ty::NormalizesTo { alias, term: unconstrained_term });
-                    let proof_tree = EvalCtxt::enter_root(infcx, GenerateProofTree::Yes, |ecx| {
-                        ecx.evaluate_goal_raw(GoalEvaluationKind::Root, GoalSource::Misc, goal)
-                    })
+                    let solver_mode = SolverMode::for_infcx(infcx);
+                    let proof_tree = EvalCtxt::enter_root(
+                        infcx,
+                        solver_mode,
+                        GenerateProofTree::Yes,
+                        None,
+                        |ecx| {
+                            ecx.evaluate_goal_raw(GoalEvaluationKind::Root, GoalSource::Misc, goal)
+                        },
+                    )
                     .1;
                     InspectGoal::new(
                         infcx,
                         self.goal.depth + 1,
-                        proof_tree.unwrap(),
+                        self.goal.budget.clone(),
+                        &proof_tree.unwrap(),
                         Some(NormalizesToTermHack { term, unconstrained_term }),
                         source,
+                        saved_goal(nested_index),
                     )
                 }
                 _ => InspectGoal::new(
                     infcx,
                     self.goal.depth + 1,
-                    infcx.evaluate_root_goal(goal, GenerateProofTree::Yes).1.unwrap(),
+                    self.goal.budget.clone(),
+                    &infcx.evaluate_root_goal(goal, GenerateProofTree::Yes).1.unwrap(),
                     None,
                     source,
+                    saved_goal(nested_index),
                 ),
             })
             .collect()
     }
 
+    /// Instantiate the opaque type constraints recorded while evaluating this
+    /// candidate, e.g. `impl Trait` being constrained to `u32`. This includes
+    /// opaque types constrained by nested goals of the candidate.
+    ///
+    /// This does not roll back inference constraints and modifies the state of the `infcx`.
+    pub fn instantiate_opaque_type_constraints(
+        &self,
+        span: Span,
+    ) -> Vec<(ty::OpaqueTypeKey<'tcx>, Ty<'tcx>)> {
+        let infcx = self.goal.infcx;
+        let param_env = self.goal.goal.param_env;
+        let mut orig_values = self.goal.orig_values.to_vec();
+        self.opaque_type_constraints
+            .iter()
+            .map(|&constraint| {
+                canonical::instantiate_canonical_state(
+                    infcx,
+                    span,
+                    param_env,
+                    &mut orig_values,
+                    constraint,
+                )
+            })
+            .collect()
+    }
+
     /// Visit all nested goals of this candidate, rolling back
     /// all inference constraints.
     pub fn visit_nested_in_probe<V: ProofTreeVisitor<'tcx>>(&self, visitor: &mut V) -> V::Result {
@@ -235,6 +350,55 @@ pub fn source(&self) -> GoalSource {
         self.source
     }
 
+    /// The highest universe of the canonical input of this goal. All universes
+    /// entered while evaluating this goal are higher than this one.
+    pub fn max_input_universe(&self) -> ty::UniverseIndex {
+        self.max_input_universe
+    }
+
+    /// Returns the universes entered while evaluating this goal, together with
+    /// the bound variables which have been instantiated with placeholders in
+    /// them. Placeholders from these universes must not escape the goal.
+    ///
+    /// The universes are local to the proof tree, with the universes of the
+    /// canonical input being `0..=self.max_input_universe()`.
+    pub fn entered_universes(
+        &self,
+    ) -> Vec<(ty::UniverseIndex, &'tcx ty::List<ty::BoundVariableKind>)> {
+        fn search_probe<'tcx>(
+            probe: &inspect::Probe<'tcx>,
+            universes: &mut Vec<(ty::UniverseIndex, &'tcx ty::List<ty::BoundVariableKind>)>,
+        ) {
+            for step in &probe.steps {
+                match *step {
+                    inspect::ProbeStep::EnterUniverse { universe, bound_vars } => {
+                        universes.push((universe, bound_vars))
+                    }
+                    inspect::ProbeStep::NestedProbe(ref probe) => search_probe(probe, universes),
+                    inspect::ProbeStep::AddGoal(..)
+                    | inspect::ProbeStep::EvaluateGoals(_)
+                    | inspect::ProbeStep::RecordOpaqueTypeConstraint(_)
+                    | inspect::ProbeStep::MakeCanonicalResponse { .. } => {}
+                }
+            }
+        }
+
+        let mut universes = vec![];
+        if let inspect::CanonicalGoalEvaluationKind::Evaluation { revisions } =
+            self.evaluation_kind
+            && let Some(last) = revisions.last()
+        {
+            search_probe(&last.evaluation, &mut universes);
+        }
+        universes
+    }
+
+    /// Returns a handle which can be used to resume visiting the proof
+    /// tree from this goal, see [`ProofTreeInferCtxtExt::visit_saved_goal`].
+    pub fn save(&self) -> SavedInspectGoal<'tcx> {
+        self.saved.clone()
+    }
+
     fn candidates_recur(
         &'a self,
         candidates: &mut Vec<InspectCandidate<'a, 'tcx>>,
@@ -242,31 +406,45 @@ fn candidates_recur(
             GoalSource,
             inspect::CanonicalState<'tcx, Goal<'tcx, ty::Predicate<'tcx>>>,
         )>,
+        opaque_type_constraints: &mut Vec<OpaqueTypeConstraint<'tcx>>,
         probe: &inspect::Probe<'tcx>,
     ) {
         let mut shallow_certainty = None;
         for step in &probe.steps {
             match step {
                 &inspect::ProbeStep::AddGoal(source, goal) => nested_goals.push((source, goal)),
+                &inspect::ProbeStep::RecordOpaqueTypeConstraint(constraint) => {
+                    opaque_type_constraints.push(constraint)
+                }
                 inspect::ProbeStep::NestedProbe(ref probe) => {
                     // Nested probes have to prove goals added in their parent
                     // but do not leak them, so we truncate the added goals
-                    // afterwards.
+                    // and opaque type constraints afterwards.
                     let num_goals = nested_goals.len();
-                    self.candidates_recur(candidates, nested_goals, probe);
+                    let num_opaque_type_constraints = opaque_type_constraints.len();
+                    self.candidates_recur(
+                        candidates,
+                        nested_goals,
+                        opaque_type_constraints,
+                        probe,
+                    );
                     nested_goals.truncate(num_goals);
+                    opaque_type_constraints.truncate(num_opaque_type_constraints);
                 }
                 inspect::ProbeStep::MakeCanonicalResponse { shallow_certainty: c } => {
                     assert_eq!(shallow_certainty.replace(*c), None);
                 }
-                inspect::ProbeStep::EvaluateGoals(_) => (),
+                inspect::ProbeStep::EvaluateGoals(_)
+                | inspect::ProbeStep::EnterUniverse { .. } => (),
             }
         }
 
         match probe.kind {
             inspect::ProbeKind::NormalizedSelfTyAssembly
             | inspect::ProbeKind::UnsizeAssembly
-            | inspect::ProbeKind::UpcastProjectionCompatibility => (),
+            | inspect::ProbeKind::UpcastProjectionCompatibility
+            | inspect::ProbeKind::ExplicitNegativeImpl { .. }
+            | inspect::ProbeKind::ShadowedByGlobalWhereBound { .. } => (),
 
             // We add a candidate even for the root evaluation if there
             // is only one way to prove a given goal, e.g. for `WellFormed`.
@@ -279,8 +457,10 @@ fn candidates_recur(
                 if let Some(shallow_certainty) = shallow_certainty {
                     candidates.push(InspectCandidate {
                         goal: self,
+                        index: candidates.len(),
                         kind: probe.kind,
                         nested_goals: nested_goals.clone(),
+                        opaque_type_constraints: opaque_type_constraints.clone(),
                         final_state: probe.final_state,
                         result,
                         shallow_certainty,
@@ -309,11 +489,82 @@ pub fn candidates(&'a self) -> Vec<InspectCandidate<'a, 'tcx>> {
         };
 
         let mut nested_goals = vec![];
-        self.candidates_recur(&mut candidates, &mut nested_goals, &last_eval_step.evaluation);
+        let mut opaque_type_constraints = vec![];
+        self.candidates_recur(
+            &mut candidates,
+            &mut nested_goals,
+            &mut opaque_type_constraints,
+            &last_eval_step.evaluation,
+        );
 
         candidates
     }
 
+    /// Returns the explicit negative impl, e.g. `impl !Send for Foo`, which caused
+    /// this positive trait goal to fail, if there is one.
+    ///
+    /// Explicit negative impls are not candidates, so they are not returned
+    /// by [`Self::candidates`].
+    pub fn explicit_negative_impl(&self) -> Option<DefId> {
+        fn search_probe(probe: &inspect::Probe<'_>) -> Option<DefId> {
+            for step in &probe.steps {
+                let inspect::ProbeStep::NestedProbe(probe) = step else {
+                    continue;
+                };
+
+                if let inspect::ProbeKind::ExplicitNegativeImpl { impl_def_id, result: Ok(response) } =
+                    probe.kind
+                    && response.value.certainty == Certainty::Yes
+                    && response.has_no_inference_or_external_constraints()
+                {
+                    return Some(impl_def_id);
+                }
+
+                if let Some(impl_def_id) = search_probe(probe) {
+                    return Some(impl_def_id);
+                }
+            }
+
+            None
+        }
+
+        match self.evaluation_kind {
+            inspect::CanonicalGoalEvaluationKind::Evaluation { revisions } => {
+                search_probe(&revisions.last()?.evaluation)
+            }
+            inspect::CanonicalGoalEvaluationKind::Overflow
+            | inspect::CanonicalGoalEvaluationKind::CycleInStack
+            | inspect::CanonicalGoalEvaluationKind::ProvisionalCacheHit => None,
+        }
+    }
+
+    /// Returns the global where-bound which caused the impl candidates of this goal
+    /// to be discarded, if there is one. See `-Zsolver-global-where-bounds`.
+    pub fn shadowed_by_global_where_bound(&self) -> Option<ty::Clause<'tcx>> {
+        fn search_probe(probe: &inspect::Probe<'_>) -> Option<usize> {
+            probe.steps.iter().find_map(|step| {
+                let inspect::ProbeStep::NestedProbe(probe) = step else {
+                    return None;
+                };
+
+                match probe.kind {
+                    inspect::ProbeKind::ShadowedByGlobalWhereBound { param_env_index } => {
+                        Some(param_env_index)
+                    }
+                    _ => search_probe(probe),
+                }
+            })
+        }
+
+        let inspect::CanonicalGoalEvaluationKind::Evaluation { revisions } =
+            self.evaluation_kind
+        else {
+            return None;
+        };
+        let param_env_index = search_probe(&revisions.last()?.evaluation)?;
+        Some(self.goal.param_env.caller_bounds()[param_env_index])
+    }
+
     /// Returns the single candidate applicable for the current goal, if it exists.
     ///
     /// Returns `None` if there are either no or multiple applicable candidates.
@@ -325,15 +576,60 @@ pub fn unique_applicable_candidate(&'a self) -> Option<InspectCandidate<'a, 'tcx
         candidates.pop().filter(|_| candidates.is_empty())
     }
 
+    /// For ambiguous goals, returns the type inference variables which are still
+    /// unconstrained in the leaf goals causing the ambiguity. Constraining these
+    /// variables, e.g. via a type annotation, is necessary for this goal to make
+    /// progress.
+    ///
+    /// Only inference variables which already existed before inspecting this goal
+    /// are returned, as variables created while instantiating nested goals are
+    /// meaningless outside of the proof tree.
+    pub fn blocking_infer_vars(&self, span: Span) -> Vec<Ty<'tcx>> {
+        let Ok(Certainty::Maybe(MaybeCause::Ambiguity(_))) = self.result() else {
+            return vec![];
+        };
+
+        let infcx = self.infcx;
+        let num_ty_vars = infcx.num_ty_vars();
+        infcx.probe(|_| {
+            let mut visitor =
+                BlockingGoals { span, result: Err(NoSolution), blocking_goals: vec![] };
+            visitor.visit_goal(self);
+
+            let mut infer_vars = FxIndexSet::default();
+            for goal in visitor.blocking_goals {
+                let goal = infcx.resolve_vars_if_possible(goal);
+                for ty in goal.predicate.walk().filter_map(|arg| arg.as_type()) {
+                    if let ty::Infer(ty::TyVar(vid)) = *ty.kind()
+                        && vid.as_usize() < num_ty_vars
+                    {
+                        infer_vars.insert(ty);
+                    }
+                }
+            }
+            infer_vars.into_iter().collect()
+        })
+    }
+
+    /// Whether the budget of nested evaluations has been exhausted while
+    /// visiting the proof tree of this goal. If so, some nested goals have
+    /// not been visited.
+    pub fn budget_exhausted(&self) -> bool {
+        self.budget.get() == 0
+    }
+
     fn new(
         infcx: &'a InferCtxt<'tcx>,
         depth: usize,
-        root: inspect::GoalEvaluation<'tcx>,
+        budget: Rc<Cell<usize>>,
+        root: &inspect::GoalEvaluation<'tcx>,
         normalizes_to_term_hack: Option<NormalizesToTermHack<'tcx>>,
         source: GoalSource,
+        saved: SavedInspectGoal<'tcx>,
     ) -> Self {
         let inspect::GoalEvaluation { uncanonicalized_goal, kind, evaluation } = root;
         let inspect::GoalEvaluationKind::Root { orig_values } = kind else { unreachable!() };
+        let uncanonicalized_goal = *uncanonicalized_goal;
 
         let result = evaluation.result.and_then(|ok| {
             if let Some(term_hack) = normalizes_to_term_hack {
@@ -348,17 +644,51 @@ fn new(
         InspectGoal {
             infcx,
             depth,
-            orig_values,
+            budget,
+            orig_values: orig_values.clone(),
             goal: uncanonicalized_goal.fold_with(&mut EagerResolver::new(infcx)),
+            max_input_universe: evaluation.goal.max_universe,
             result,
-            evaluation_kind: evaluation.kind,
+            evaluation_kind: evaluation.kind.clone(),
             normalizes_to_term_hack,
             source,
+            saved,
+        }
+    }
+
+    fn new_root(
+        infcx: &'a InferCtxt<'tcx>,
+        budget: Rc<Cell<usize>>,
+        root: Lrc<inspect::GoalEvaluation<'tcx>>,
+    ) -> Self {
+        let saved = SavedInspectGoal { root: Lrc::clone(&root), path: vec![] };
+        InspectGoal::new(infcx, 0, budget, &root, None, GoalSource::Misc, saved)
+    }
+
+    /// Instantiates the nested goals along `path` and visits the goal at its end.
+    fn visit_path<V: ProofTreeVisitor<'tcx>>(
+        &self,
+        path: &[(usize, usize)],
+        visitor: &mut V,
+        visit: impl FnOnce(&InspectGoal<'_, 'tcx>, &mut V) -> V::Result,
+    ) -> V::Result {
+        let Some((&(candidate_index, nested_index), rest)) = path.split_first() else {
+            return visit(self, visitor);
+        };
+
+        // Proof trees are deterministic, so the candidates and nested goals
+        // are the same as when the handle has been saved. We only fail to
+        // instantiate the nested goals if the budget is exhausted.
+        let candidates = self.candidates();
+        let nested_goals = candidates[candidate_index].instantiate_nested_goals(visitor.span());
+        match nested_goals.get(nested_index) {
+            Some(goal) => goal.visit_path(rest, visitor, visit),
+            None => V::Result::output(),
         }
     }
 
     pub(crate) fn visit_with<V: ProofTreeVisitor<'tcx>>(&self, visitor: &mut V) -> V::Result {
-        if self.depth < visitor.config().max_depth {
+        if self.depth < visitor.config().max_depth && !self.budget_exhausted() {
             try_visit!(visitor.visit_goal(self));
         }
 
@@ -373,7 +703,7 @@ pub trait ProofTreeVisitor<'tcx> {
     fn span(&self) -> Span;
 
     fn config(&self) -> InspectConfig {
-        InspectConfig { max_depth: 10 }
+        InspectConfig::default()
     }
 
     fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) -> Self::Result;
@@ -387,7 +717,136 @@ fn visit_proof_tree<V: ProofTreeVisitor<'tcx>>(
         visitor: &mut V,
     ) -> V::Result {
         let (_, proof_tree) = self.evaluate_root_goal(goal, GenerateProofTree::Yes);
-        let proof_tree = proof_tree.unwrap();
-        visitor.visit_goal(&InspectGoal::new(self, 0, proof_tree, None, GoalSource::Misc))
+        let proof_tree = Lrc::new(proof_tree.unwrap());
+        let budget = Rc::new(Cell::new(visitor.config().max_nested_evaluations));
+        visitor.visit_goal(&InspectGoal::new_root(self, budget, proof_tree))
+    }
+
+    /// Visits a previously computed proof tree, e.g. the one stored in a
+    /// `FulfillmentError`, instead of evaluating the goal again. The proof
+    /// tree is shared with the visited goals and never copied.
+    ///
+    /// The proof tree has to be computed by `self` and there must not
+    /// have been a rollback to a snapshot taken before computing it.
+    fn visit_existing_proof_tree<V: ProofTreeVisitor<'tcx>>(
+        &self,
+        proof_tree: &Lrc<inspect::GoalEvaluation<'tcx>>,
+        visitor: &mut V,
+    ) -> V::Result {
+        let proof_tree = Lrc::clone(proof_tree);
+        let budget = Rc::new(Cell::new(visitor.config().max_nested_evaluations));
+        visitor.visit_goal(&InspectGoal::new_root(self, budget, proof_tree))
+    }
+
+    /// Resumes visiting a proof tree from a goal saved via [`InspectGoal::save`],
+    /// without visiting the rest of the proof tree again.
+    ///
+    /// This reinstantiates the nested goals on the path from the root to the saved
+    /// goal inside of a probe, so all inference constraints are rolled back afterwards.
+    /// The same requirements as for [`Self::visit_existing_proof_tree`] apply to the
+    /// root of the proof tree.
+    fn visit_saved_goal<V: ProofTreeVisitor<'tcx>>(
+        &self,
+        saved: &SavedInspectGoal<'tcx>,
+        visitor: &mut V,
+    ) -> V::Result {
+        self.probe(|_| {
+            let budget = Rc::new(Cell::new(visitor.config().max_nested_evaluations));
+            let root = InspectGoal::new_root(self, budget, saved.root.clone());
+            root.visit_path(&saved.path, visitor, |goal, visitor| visitor.visit_goal(goal))
+        })
+    }
+
+    /// Resumes visiting a proof tree from a candidate saved via [`InspectCandidate::save`],
+    /// visiting all nested goals of that candidate. See [`Self::visit_saved_goal`].
+    fn visit_saved_candidate<V: ProofTreeVisitor<'tcx>>(
+        &self,
+        saved: &SavedInspectCandidate<'tcx>,
+        visitor: &mut V,
+    ) -> V::Result {
+        self.probe(|_| {
+            let budget = Rc::new(Cell::new(visitor.config().max_nested_evaluations));
+            let root = InspectGoal::new_root(self, budget, saved.goal.root.clone());
+            root.visit_path(&saved.goal.path, visitor, |goal, visitor| {
+                goal.candidates()[saved.index].visit_nested_no_probe(visitor)
+            })
+        })
+    }
+
+    /// Evaluates `goal` and returns its certainty. If the goal is ambiguous,
+    /// this also returns the leaf goals which keep it from holding, i.e. the
+    /// ambiguous nested goals which do not have a unique applicable candidate
+    /// or whose candidate does not have any ambiguous nested goals itself.
+    ///
+    /// This instantiates the nested goals of the proof tree and should
+    /// therefore only be used inside of a probe.
+    fn evaluate_goal_with_blocking_goals(
+        &self,
+        goal: Goal<'tcx, ty::Predicate<'tcx>>,
+        span: Span,
+    ) -> (Result<Certainty, NoSolution>, Vec<Goal<'tcx, ty::Predicate<'tcx>>>) {
+        let mut visitor = BlockingGoals { span, result: Err(NoSolution), blocking_goals: vec![] };
+        self.visit_proof_tree(goal, &mut visitor);
+        (visitor.result, visitor.blocking_goals)
+    }
+
+    /// Returns the nested goals, together with their source, of the unique applicable
+    /// candidate used to prove `goal`. This only returns the first level of nested goals,
+    /// their own nested goals can be computed by calling this function again.
+    ///
+    /// Returns `None` if there is no unique applicable candidate, e.g. because the goal
+    /// does not hold or is ambiguous.
+    ///
+    /// This does not constrain any existing inference variables. Inference variables
+    /// created while instantiating the nested goals are replaced with fresh ones.
+    fn nested_goals_of_unique_candidate(
+        &self,
+        goal: Goal<'tcx, ty::Predicate<'tcx>>,
+        span: Span,
+    ) -> Option<Vec<(GoalSource, Goal<'tcx, ty::Predicate<'tcx>>)>> {
+        let mut sources = vec![];
+        let goals = self
+            .fudge_inference_if_ok(|| {
+                let (_, proof_tree) = self.evaluate_root_goal(goal, GenerateProofTree::Yes);
+                let budget = Rc::new(Cell::new(InspectConfig::default().max_nested_evaluations));
+                let root = InspectGoal::new_root(self, budget, Lrc::new(proof_tree.unwrap()));
+                let candidate = root.unique_applicable_candidate().ok_or(())?;
+                let nested_goals = candidate.instantiate_nested_goals(span);
+                sources = nested_goals.iter().map(|nested| nested.source()).collect();
+                Ok(nested_goals.iter().map(|nested| nested.goal()).collect::<Vec<_>>())
+            })
+            .ok()?;
+        Some(sources.into_iter().zip(goals).collect())
+    }
+}
+
+struct BlockingGoals<'tcx> {
+    span: Span,
+    result: Result<Certainty, NoSolution>,
+    blocking_goals: Vec<Goal<'tcx, ty::Predicate<'tcx>>>,
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for BlockingGoals<'tcx> {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) {
+        if goal.depth == 0 {
+            self.result = goal.result();
+        }
+
+        let Ok(Certainty::Maybe(_)) = goal.result() else {
+            return;
+        };
+
+        let num_blocking_goals = self.blocking_goals.len();
+        if let Some(candidate) = goal.unique_applicable_candidate() {
+            candidate.visit_nested_no_probe(self);
+        }
+
+        if self.blocking_goals.len() == num_blocking_goals {
+            self.blocking_goals.push(goal.goal());
+        }
     }
 }
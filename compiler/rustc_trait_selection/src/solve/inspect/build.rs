@@ -11,7 +11,7 @@
 use rustc_middle::traits::solve::{
     CanonicalInput, Certainty, Goal, GoalSource, QueryInput, QueryResult,
 };
-use rustc_middle::ty::{self, TyCtxt};
+use rustc_middle::ty::{self, Ty, TyCtxt};
 use rustc_session::config::DumpSolverProofTree;
 
 use crate::solve::eval_ctxt::canonical;
@@ -241,6 +241,11 @@ enum WipProbeStep<'tcx> {
     AddGoal(GoalSource, inspect::CanonicalState<'tcx, Goal<'tcx, ty::Predicate<'tcx>>>),
     EvaluateGoals(WipAddedGoalsEvaluation<'tcx>),
     NestedProbe(WipProbe<'tcx>),
+    RecordOpaqueTypeConstraint(inspect::CanonicalState<'tcx, (ty::OpaqueTypeKey<'tcx>, Ty<'tcx>)>),
+    EnterUniverse {
+        universe: ty::UniverseIndex,
+        bound_vars: &'tcx ty::List<ty::BoundVariableKind>,
+    },
     MakeCanonicalResponse { shallow_certainty: Certainty },
 }
 
@@ -250,6 +255,12 @@ fn finalize(self) -> inspect::ProbeStep<'tcx> {
             WipProbeStep::AddGoal(source, goal) => inspect::ProbeStep::AddGoal(source, goal),
             WipProbeStep::EvaluateGoals(eval) => inspect::ProbeStep::EvaluateGoals(eval.finalize()),
             WipProbeStep::NestedProbe(probe) => inspect::ProbeStep::NestedProbe(probe.finalize()),
+            WipProbeStep::RecordOpaqueTypeConstraint(constraint) => {
+                inspect::ProbeStep::RecordOpaqueTypeConstraint(constraint)
+            }
+            WipProbeStep::EnterUniverse { universe, bound_vars } => {
+                inspect::ProbeStep::EnterUniverse { universe, bound_vars }
+            }
             WipProbeStep::MakeCanonicalResponse { shallow_certainty } => {
                 inspect::ProbeStep::MakeCanonicalResponse { shallow_certainty }
             }
@@ -534,6 +545,48 @@ pub fn add_goal(
         }
     }
 
+    pub fn record_opaque_type_constraint(
+        &mut self,
+        infcx: &InferCtxt<'tcx>,
+        max_input_universe: ty::UniverseIndex,
+        opaque_type_key: ty::OpaqueTypeKey<'tcx>,
+        hidden_ty: Ty<'tcx>,
+    ) {
+        match self.as_mut() {
+            // Applying the response of a root goal constrains opaque types
+            // outside of any goal evaluation, so we don't record it.
+            None | Some(DebugSolver::Root) => {}
+            Some(DebugSolver::GoalEvaluationStep(state)) => {
+                let constraint = canonical::make_canonical_state(
+                    infcx,
+                    &state.var_values,
+                    max_input_universe,
+                    (opaque_type_key, hidden_ty),
+                );
+                state
+                    .current_evaluation_scope()
+                    .steps
+                    .push(WipProbeStep::RecordOpaqueTypeConstraint(constraint))
+            }
+            _ => bug!(),
+        }
+    }
+
+    pub fn enter_universe(
+        &mut self,
+        universe: ty::UniverseIndex,
+        bound_vars: &'tcx ty::List<ty::BoundVariableKind>,
+    ) {
+        match self.as_mut() {
+            None => {}
+            Some(DebugSolver::GoalEvaluationStep(state)) => state
+                .current_evaluation_scope()
+                .steps
+                .push(WipProbeStep::EnterUniverse { universe, bound_vars }),
+            _ => bug!(),
+        }
+    }
+
     pub fn make_canonical_response(&mut self, shallow_certainty: Certainty) {
         match self.as_mut() {
             Some(DebugSolver::GoalEvaluationStep(state)) => {
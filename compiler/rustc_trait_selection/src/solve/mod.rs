@@ -15,11 +15,13 @@
 //! about it on zulip.
 use rustc_hir::def_id::DefId;
 use rustc_infer::infer::canonical::{Canonical, CanonicalVarValues};
+use rustc_infer::infer::InferCtxt;
 use rustc_infer::traits::query::NoSolution;
 use rustc_macros::extension;
 use rustc_middle::infer::canonical::CanonicalVarInfos;
 use rustc_middle::traits::solve::{
-    CanonicalResponse, Certainty, ExternalConstraintsData, Goal, GoalSource, QueryResult, Response,
+    AmbiguityCause, CanonicalResponse, Certainty, ExternalConstraintsData, Goal, GoalSource,
+    MaybeCause, QueryResult, Response,
 };
 use rustc_middle::ty::{self, AliasRelationDirection, Ty, TyCtxt, UniverseIndex};
 use rustc_middle::ty::{
@@ -27,20 +29,32 @@
 };
 
 mod alias_relate;
+pub mod ambiguity_report;
 mod assembly;
 mod eval_ctxt;
 mod fulfill;
+pub mod goal_summary;
 pub mod inspect;
 mod normalize;
 mod normalizes_to;
 mod project_goals;
 mod search_graph;
+mod solver_session;
+pub mod span_profile;
 mod trait_goals;
 
-pub use eval_ctxt::{EvalCtxt, GenerateProofTree, InferCtxtEvalExt, InferCtxtSelectExt};
-pub use fulfill::FulfillmentCtxt;
+pub use eval_ctxt::{
+    CandidateDiscardReason, CandidateExplanation, CandidatePreferenceRule, EvalCtxt,
+    GenerateProofTree, InferCtxtEvalExt, InferCtxtSelectExt,
+};
+pub use fulfill::{FulfillmentCtxt, FulfillmentStorage, SelectAndResolveRegionsError};
+pub use search_graph::{Interrupted, SolverCancellationToken};
+pub(crate) use eval_ctxt::provide;
 pub(crate) use normalize::deeply_normalize_for_diagnostics;
-pub use normalize::{deeply_normalize, deeply_normalize_with_skipped_universes};
+pub use normalize::{
+    deeply_normalize, deeply_normalize_with_fulfillment_ctxt,
+    deeply_normalize_with_skipped_universes,
+};
 
 /// How many fixpoint iterations we should attempt inside of the solver before bailing
 /// with overflow.
@@ -54,7 +68,7 @@
 const FIXPOINT_STEP_LIMIT: usize = 8;
 
 #[derive(Debug, Clone, Copy)]
-enum SolverMode {
+pub enum SolverMode {
     /// Ordinary trait solving, using everywhere except for coherence.
     Normal,
     /// Trait solving during coherence. There are a few notable differences
@@ -66,6 +80,14 @@ enum SolverMode {
     Coherence,
 }
 
+impl SolverMode {
+    /// The mode used when evaluating goals in `infcx`, unless a different
+    /// mode is requested explicitly.
+    pub fn for_infcx(infcx: &InferCtxt<'_>) -> SolverMode {
+        if infcx.intercrate { SolverMode::Coherence } else { SolverMode::Normal }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum GoalEvaluationKind {
     Root,
@@ -123,7 +145,9 @@ fn compute_subtype_goal(
         goal: Goal<'tcx, SubtypePredicate<'tcx>>,
     ) -> QueryResult<'tcx> {
         if goal.predicate.a.is_ty_var() && goal.predicate.b.is_ty_var() {
-            self.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS)
+            self.evaluate_added_goals_and_make_canonical_response(Certainty::ambiguous(
+                AmbiguityCause::UnresolvedType,
+            ))
         } else {
             self.sub(goal.param_env, goal.predicate.a, goal.predicate.b)?;
             self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
@@ -145,7 +169,7 @@ fn compute_well_formed_goal(
     ) -> QueryResult<'tcx> {
         match self.well_formed_goals(goal.param_env, goal.predicate) {
             Some(goals) => {
-                self.add_goals(GoalSource::Misc, goals);
+                self.add_goals(GoalSource::WellFormed, goals);
                 self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
             }
             None => self.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS),
@@ -170,12 +194,14 @@ fn compute_const_evaluatable_goal(
                 if let Some(_normalized) = self.try_const_eval_resolve(param_env, uv, ct.ty()) {
                     self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
                 } else {
-                    self.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS)
+                    self.evaluate_added_goals_and_make_canonical_response(Certainty::ambiguous(
+                        AmbiguityCause::UnresolvedConst,
+                    ))
                 }
             }
-            ty::ConstKind::Infer(_) => {
-                self.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS)
-            }
+            ty::ConstKind::Infer(_) => self.evaluate_added_goals_and_make_canonical_response(
+                Certainty::ambiguous(AmbiguityCause::UnresolvedConst),
+            ),
             ty::ConstKind::Placeholder(_) | ty::ConstKind::Value(_) | ty::ConstKind::Error(_) => {
                 self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
             }
@@ -189,6 +215,18 @@ fn compute_const_evaluatable_goal(
         }
     }
 
+    /// The type relation emits `AliasRelate` instead of `ConstEquate` goals when
+    /// using the new solver. `ConstEquate` goals may still get registered directly
+    /// with `feature(generic_const_exprs)`, in which case we equate both constants.
+    #[instrument(level = "debug", skip(self))]
+    fn compute_const_equate_goal(
+        &mut self,
+        Goal { param_env, predicate: (a, b) }: Goal<'tcx, (ty::Const<'tcx>, ty::Const<'tcx>)>,
+    ) -> QueryResult<'tcx> {
+        self.eq(param_env, a, b)?;
+        self.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
+    }
+
     #[instrument(level = "debug", skip(self), ret)]
     fn compute_const_arg_has_type_goal(
         &mut self,
@@ -247,13 +285,17 @@ fn flounder(&mut self, responses: &[CanonicalResponse<'tcx>]) -> QueryResult<'tc
             return Err(NoSolution);
         }
 
-        let Certainty::Maybe(maybe_cause) =
+        let Certainty::Maybe(mut maybe_cause) =
             responses.iter().fold(Certainty::AMBIGUOUS, |certainty, response| {
                 certainty.unify_with(response.value.certainty)
             })
         else {
             bug!("expected flounder response to be ambiguous")
         };
+        // We only flounder with multiple responses if we were unable to merge them.
+        if responses.len() > 1 && matches!(maybe_cause, MaybeCause::Ambiguity(_)) {
+            maybe_cause = MaybeCause::Ambiguity(AmbiguityCause::MultipleCandidates);
+        }
 
         Ok(self.make_ambiguous_response_no_constraints(maybe_cause))
     }
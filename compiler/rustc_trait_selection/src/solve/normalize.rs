@@ -36,17 +36,50 @@ pub fn deeply_normalize_with_skipped_universes<'tcx, T: TypeFoldable<TyCtxt<'tcx
     value: T,
     universes: Vec<Option<UniverseIndex>>,
 ) -> Result<T, Vec<FulfillmentError<'tcx>>> {
-    let fulfill_cx = FulfillmentCtxt::new(at.infcx);
-    let mut folder = NormalizationFolder { at, fulfill_cx, depth: 0, universes };
+    let mut fulfill_cx = FulfillmentCtxt::new(at.infcx);
+    let mut folder = NormalizationFolder {
+        at,
+        fulfill_cx: &mut fulfill_cx,
+        depth: 0,
+        universes,
+        defer_ambiguity: false,
+    };
+
+    value.try_fold_with(&mut folder)
+}
+
+/// Deeply normalize all aliases in `value` by registering the necessary goals
+/// in an existing `fulfill_cx`.
+///
+/// Unlike [`deeply_normalize`], this supports inference variables in `value`.
+/// Aliases whose normalization is still ambiguous get replaced with a fresh
+/// inference variable and the goal relating them stays pending in `fulfill_cx`.
+/// Errors returned by this function may also come from obligations which were
+/// already pending in `fulfill_cx`.
+///
+/// Aliases with escaping bound vars are not normalized, as we would otherwise
+/// leak placeholders into the pending obligations.
+pub fn deeply_normalize_with_fulfillment_ctxt<'tcx, T: TypeFoldable<TyCtxt<'tcx>>>(
+    at: At<'_, 'tcx>,
+    value: T,
+    fulfill_cx: &mut FulfillmentCtxt<'tcx>,
+) -> Result<T, Vec<FulfillmentError<'tcx>>> {
+    assert!(!value.has_escaping_bound_vars());
+    let value = at.infcx.resolve_vars_if_possible(value);
+    let mut folder =
+        NormalizationFolder { at, fulfill_cx, depth: 0, universes: vec![], defer_ambiguity: true };
 
     value.try_fold_with(&mut folder)
 }
 
 struct NormalizationFolder<'me, 'tcx> {
     at: At<'me, 'tcx>,
-    fulfill_cx: FulfillmentCtxt<'tcx>,
+    fulfill_cx: &'me mut FulfillmentCtxt<'tcx>,
     depth: usize,
     universes: Vec<Option<UniverseIndex>>,
+    /// Whether to keep ambiguous goals pending in `fulfill_cx`
+    /// instead of eagerly reporting them as errors.
+    defer_ambiguity: bool,
 }
 
 impl<'tcx> NormalizationFolder<'_, 'tcx> {
@@ -88,19 +121,33 @@ fn normalize_alias_ty(
         );
 
         self.fulfill_cx.register_predicate_obligation(infcx, obligation);
-        let errors = self.fulfill_cx.select_all_or_error(infcx);
-        if !errors.is_empty() {
-            return Err(errors);
+        self.select()?;
+
+        let ty = infcx.resolve_vars_if_possible(new_infer_ty);
+        // The alias is still ambiguous, its goal remains pending in the
+        // fulfillment context.
+        if ty.is_ty_var() {
+            self.depth -= 1;
+            return Ok(ty);
         }
 
         // Alias is guaranteed to be fully structurally resolved,
         // so we can super fold here.
-        let ty = infcx.resolve_vars_if_possible(new_infer_ty);
         let result = ty.try_super_fold_with(self)?;
         self.depth -= 1;
         Ok(result)
     }
 
+    fn select(&mut self) -> Result<(), Vec<FulfillmentError<'tcx>>> {
+        let infcx = self.at.infcx;
+        let errors = if self.defer_ambiguity {
+            self.fulfill_cx.select_where_possible(infcx)
+        } else {
+            self.fulfill_cx.select_all_or_error(infcx)
+        };
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
     fn normalize_unevaluated_const(
         &mut self,
         ty: Ty<'tcx>,
@@ -136,12 +183,9 @@ fn normalize_unevaluated_const(
 
         let result = if infcx.predicate_may_hold(&obligation) {
             self.fulfill_cx.register_predicate_obligation(infcx, obligation);
-            let errors = self.fulfill_cx.select_all_or_error(infcx);
-            if !errors.is_empty() {
-                return Err(errors);
-            }
+            self.select()?;
             let ct = infcx.resolve_vars_if_possible(new_infer_ct);
-            ct.try_fold_with(self)?
+            if ct.is_ct_infer() { ct } else { ct.try_fold_with(self)? }
         } else {
             ty::Const::new_unevaluated(tcx, uv, ty).try_super_fold_with(self)?
         };
@@ -171,6 +215,9 @@ fn try_fold_binder<T: TypeFoldable<TyCtxt<'tcx>>>(
     #[instrument(level = "debug", skip(self), ret)]
     fn try_fold_ty(&mut self, ty: Ty<'tcx>) -> Result<Ty<'tcx>, Self::Error> {
         let infcx = self.at.infcx;
+        // Normalizing an alias may constrain inference variables which
+        // we have not yet folded.
+        let ty = if self.defer_ambiguity { infcx.shallow_resolve(ty) } else { ty };
         debug_assert_eq!(ty, infcx.shallow_resolve(ty));
         if !ty.has_aliases() {
             return Ok(ty);
@@ -178,7 +225,9 @@ fn try_fold_ty(&mut self, ty: Ty<'tcx>) -> Result<Ty<'tcx>, Self::Error> {
 
         let ty::Alias(..) = *ty.kind() else { return ty.try_super_fold_with(self) };
 
-        if ty.has_escaping_bound_vars() {
+        if ty.has_escaping_bound_vars() && self.defer_ambiguity {
+            ty.try_super_fold_with(self)
+        } else if ty.has_escaping_bound_vars() {
             let (ty, mapped_regions, mapped_types, mapped_consts) =
                 BoundVarReplacer::replace_bound_vars(infcx, &mut self.universes, ty);
             let result = ensure_sufficient_stack(|| self.normalize_alias_ty(ty))?;
@@ -198,6 +247,7 @@ fn try_fold_ty(&mut self, ty: Ty<'tcx>) -> Result<Ty<'tcx>, Self::Error> {
     #[instrument(level = "debug", skip(self), ret)]
     fn try_fold_const(&mut self, ct: ty::Const<'tcx>) -> Result<ty::Const<'tcx>, Self::Error> {
         let infcx = self.at.infcx;
+        let ct = if self.defer_ambiguity { infcx.shallow_resolve_const(ct) } else { ct };
         debug_assert_eq!(ct, infcx.shallow_resolve_const(ct));
         if !ct.has_aliases() {
             return Ok(ct);
@@ -208,7 +258,9 @@ fn try_fold_const(&mut self, ct: ty::Const<'tcx>) -> Result<ty::Const<'tcx>, Sel
             _ => return ct.try_super_fold_with(self),
         };
 
-        if uv.has_escaping_bound_vars() {
+        if uv.has_escaping_bound_vars() && self.defer_ambiguity {
+            ct.try_super_fold_with(self)
+        } else if uv.has_escaping_bound_vars() {
             let (uv, mapped_regions, mapped_types, mapped_consts) =
                 BoundVarReplacer::replace_bound_vars(infcx, &mut self.universes, uv);
             let result = ensure_sufficient_stack(|| self.normalize_unevaluated_const(ct.ty(), uv))?;
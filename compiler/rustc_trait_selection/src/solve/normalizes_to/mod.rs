@@ -2,13 +2,13 @@
 
 use super::assembly::structural_traits::AsyncCallableRelevantTypes;
 use super::assembly::{self, structural_traits, Candidate};
-use super::{EvalCtxt, GoalSource};
+use super::{EvalCtxt, GoalSource, SolverMode};
 use rustc_hir::def::DefKind;
 use rustc_hir::def_id::DefId;
 use rustc_hir::LangItem;
 use rustc_infer::traits::query::NoSolution;
 use rustc_infer::traits::solve::inspect::ProbeKind;
-use rustc_infer::traits::solve::MaybeCause;
+use rustc_infer::traits::solve::{AmbiguityCause, MaybeCause};
 use rustc_infer::traits::specialization_graph::LeafDef;
 use rustc_infer::traits::Reveal;
 use rustc_middle::traits::solve::{CandidateSource, Certainty, Goal, QueryResult};
@@ -176,9 +176,31 @@ fn consider_impl_candidate(
         let impl_polarity = impl_trait_header.polarity;
         match impl_polarity {
             ty::ImplPolarity::Negative => return Err(NoSolution),
-            ty::ImplPolarity::Reservation => {
-                unimplemented!("reservation impl for trait with assoc item: {:?}", goal)
-            }
+            // Reservation impls are not real impls, so we never normalize using them.
+            // Similar to trait goals, they are ambiguous during coherence however.
+            ty::ImplPolarity::Reservation => match ecx.solver_mode() {
+                SolverMode::Coherence => {
+                    return ecx.probe_trait_candidate(CandidateSource::Impl(impl_def_id)).enter(
+                        |ecx| {
+                            let impl_args = ecx.fresh_args_for_item(impl_def_id);
+                            let impl_trait_ref =
+                                impl_trait_header.trait_ref.instantiate(tcx, impl_args);
+                            ecx.eq(goal.param_env, goal_trait_ref, impl_trait_ref)?;
+                            let where_clause_bounds = tcx
+                                .predicates_of(impl_def_id)
+                                .instantiate(tcx, impl_args)
+                                .predicates
+                                .into_iter()
+                                .map(|pred| goal.with(tcx, pred));
+                            ecx.add_goals(GoalSource::ImplWhereBound, where_clause_bounds);
+                            ecx.evaluate_added_goals_and_make_canonical_response(
+                                Certainty::ambiguous(AmbiguityCause::ReservationImpl(impl_def_id)),
+                            )
+                        },
+                    );
+                }
+                SolverMode::Normal => return Err(NoSolution),
+            },
             ty::ImplPolarity::Positive => {}
         };
 
@@ -364,7 +386,7 @@ fn consider_builtin_fn_trait_candidates(
             )? {
                 Some(tupled_inputs_and_output) => tupled_inputs_and_output,
                 None => {
-                    return ecx.forced_ambiguity(MaybeCause::Ambiguity);
+                    return ecx.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::Unknown));
                 }
             };
         let output_is_sized_pred = tupled_inputs_and_output.map_bound(|(_, output)| {
@@ -496,7 +518,7 @@ fn consider_builtin_async_fn_kind_helper_candidate(
 
         // Bail if the upvars haven't been constrained.
         if tupled_upvars_ty.expect_ty().is_ty_var() {
-            return ecx.forced_ambiguity(MaybeCause::Ambiguity);
+            return ecx.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::UnresolvedType));
         }
 
         let Some(closure_kind) = closure_fn_kind_ty.expect_ty().to_opt_closure_kind() else {
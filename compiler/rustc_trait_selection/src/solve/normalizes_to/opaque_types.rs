@@ -2,7 +2,7 @@
 //! behaves differently depending on the param-env's reveal mode and whether
 //! the opaque is in a defining scope.
 use rustc_middle::traits::query::NoSolution;
-use rustc_middle::traits::solve::{Certainty, Goal, QueryResult};
+use rustc_middle::traits::solve::{AmbiguityCause, Certainty, Goal, QueryResult};
 use rustc_middle::traits::Reveal;
 use rustc_middle::ty;
 use rustc_middle::ty::util::NotUniqueParam;
@@ -33,7 +33,7 @@ pub(super) fn normalize_opaque_type(
                 match self.tcx().uses_unique_placeholders_ignoring_regions(opaque_ty.args) {
                     Err(NotUniqueParam::NotParam(param)) if param.is_non_region_infer() => {
                         return self.evaluate_added_goals_and_make_canonical_response(
-                            Certainty::AMBIGUOUS,
+                            Certainty::ambiguous(AmbiguityCause::UndefinedOpaque),
                         );
                     }
                     Err(_) => {
@@ -59,6 +59,7 @@ pub(super) fn normalize_opaque_type(
                 }
 
                 // Otherwise, define a new opaque type
+                self.record_opaque_type_constraint(opaque_type_key, expected);
                 self.insert_hidden_type(opaque_type_key, goal.param_env, expected)?;
                 self.add_item_bounds_for_hidden_type(
                     opaque_ty.def_id,
@@ -77,7 +78,9 @@ pub(super) fn normalize_opaque_type(
                     goal.param_env,
                     expected,
                 );
-                self.evaluate_added_goals_and_make_canonical_response(Certainty::AMBIGUOUS)
+                self.evaluate_added_goals_and_make_canonical_response(Certainty::ambiguous(
+                    AmbiguityCause::UndefinedOpaque,
+                ))
             }
             (Reveal::All, _) => {
                 // FIXME: Add an assertion that opaque type storage is empty.
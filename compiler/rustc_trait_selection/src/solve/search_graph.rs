@@ -13,6 +13,8 @@
 use rustc_middle::ty::TyCtxt;
 use rustc_session::Limit;
 use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 rustc_index::newtype_index! {
     #[orderable]
@@ -98,8 +100,45 @@ fn is_empty(&self) -> bool {
     }
 }
 
+/// Allows cancelling the evaluation of goals, e.g. from another thread, when their
+/// result is no longer needed. This is checked whenever the solver starts to evaluate
+/// a nested goal, see [`InferCtxtEvalExt::evaluate_root_goal_cancellable`].
+///
+/// [`InferCtxtEvalExt::evaluate_root_goal_cancellable`]:
+///     super::InferCtxtEvalExt::evaluate_root_goal_cancellable
+#[derive(Debug, Clone, Default)]
+pub struct SolverCancellationToken(Arc<AtomicBool>);
+
+impl SolverCancellationToken {
+    pub fn new() -> SolverCancellationToken {
+        SolverCancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The evaluation of a goal has been interrupted via a [`SolverCancellationToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interrupted;
+
 pub(super) struct SearchGraph<'tcx> {
     mode: SolverMode,
+    cancellation: Option<SolverCancellationToken>,
+    /// Whether we stopped proving goals as `cancellation` has been cancelled.
+    /// The results computed by this search graph are incomplete in this case.
+    interrupted: bool,
+    /// The number of goals computed for the current root goal, which is limited
+    /// by `-Zsolver-step-limit`.
+    steps: usize,
+    /// Whether the current root goal exceeded the step limit. Similar to cancellation,
+    /// the results computed for it are incomplete in this case.
+    reached_step_limit: bool,
     /// The stack of goals currently being computed.
     ///
     /// An element is *deeper* in the stack if its index is *lower*.
@@ -119,6 +158,10 @@ impl<'tcx> SearchGraph<'tcx> {
     pub(super) fn new(mode: SolverMode) -> SearchGraph<'tcx> {
         Self {
             mode,
+            cancellation: None,
+            interrupted: false,
+            steps: 0,
+            reached_step_limit: false,
             stack: Default::default(),
             provisional_cache: Default::default(),
             cycle_participants: Default::default(),
@@ -129,6 +172,23 @@ pub(super) fn solver_mode(&self) -> SolverMode {
         self.mode
     }
 
+    pub(super) fn set_cancellation(&mut self, cancellation: SolverCancellationToken) {
+        self.cancellation = Some(cancellation);
+    }
+
+    pub(super) fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Whether root goals may be evaluated via the `evaluate_root_canonical_goal_in_new_solver`
+    /// query instead of this search graph. The query uses its own search graph, which always
+    /// uses `SolverMode::Normal` and cannot be cancelled, so interrupting the evaluation would
+    /// not be observable here. Overflow and the step limit apply to each root goal separately,
+    /// so they are handled the same by both search graphs.
+    pub(super) fn can_use_root_goal_query(&self) -> bool {
+        matches!(self.mode, SolverMode::Normal) && self.cancellation.is_none()
+    }
+
     /// Update the stack and reached depths on cache hits.
     #[instrument(level = "debug", skip(self))]
     fn on_cache_hit(&mut self, additional_depth: usize, encountered_overflow: bool) {
@@ -256,6 +316,12 @@ pub(super) fn with_new_goal(
         inspect: &mut ProofTreeBuilder<'tcx>,
         mut prove_goal: impl FnMut(&mut Self, &mut ProofTreeBuilder<'tcx>) -> QueryResult<'tcx>,
     ) -> QueryResult<'tcx> {
+        // The step limit applies to each root goal separately.
+        if self.stack.is_empty() {
+            self.steps = 0;
+            self.reached_step_limit = false;
+        }
+
         // Check for overflow.
         let Some(available_depth) = Self::allowed_depth_for_nested(tcx, &self.stack) else {
             if let Some(last) = self.stack.raw.last_mut() {
@@ -266,9 +332,18 @@ pub(super) fn with_new_goal(
             return Self::response_no_constraints(tcx, input, Certainty::overflow(true));
         };
 
+        // Stop proving goals once we've been cancelled. The result does not matter
+        // as the caller discards it anyways, we only have to make sure that it does
+        // not end up in the global cache.
+        if self.interrupted || self.cancellation.as_ref().is_some_and(|c| c.is_cancelled()) {
+            self.interrupted = true;
+            inspect.goal_evaluation_kind(inspect::WipCanonicalGoalEvaluationKind::Overflow);
+            return Self::response_no_constraints(tcx, input, Certainty::overflow(false));
+        }
+
         // Try to fetch the goal from the global cache.
         'global: {
-            let Some(CacheData { result, proof_tree, reached_depth, encountered_overflow }) =
+            let Some(CacheData { result, proof_tree, reached_depth, encountered_overflow, steps }) =
                 self.global_cache(tcx).get(
                     tcx,
                     input,
@@ -295,10 +370,30 @@ pub(super) fn with_new_goal(
                 }
             }
 
+            // Using the cache entry must not change whether we reach the step limit,
+            // so we account for all goals computed while evaluating the cached goal.
+            if self.exceeds_step_limit(tcx, steps) {
+                debug!(?input, "reached step limit");
+                inspect.goal_evaluation_kind(inspect::WipCanonicalGoalEvaluationKind::Overflow);
+                return Self::response_no_constraints(tcx, input, Certainty::step_limit_overflow());
+            }
+
             self.on_cache_hit(reached_depth, encountered_overflow);
+            self.steps += steps;
             return result;
         }
 
+        // Give up once the root goal required computing too many goals. This
+        // results in ambiguity instead of hanging on goals with an exponential
+        // number of candidates which never reach the recursion limit.
+        if self.exceeds_step_limit(tcx, 1) {
+            debug!(?input, "reached step limit");
+            inspect.goal_evaluation_kind(inspect::WipCanonicalGoalEvaluationKind::Overflow);
+            return Self::response_no_constraints(tcx, input, Certainty::step_limit_overflow());
+        }
+        let steps_before = self.steps;
+        self.steps += 1;
+
         // Check whether the goal is in the provisional cache.
         // The provisional result may rely on the path to its cycle roots,
         // so we have to check the path of the current goal matches that of
@@ -458,6 +553,10 @@ pub(super) fn with_new_goal(
             } else {
                 entry.with_inductive_stack = Some(DetachedEntry { head, result });
             }
+        } else if self.interrupted || self.reached_step_limit {
+            // The result may depend on goals we did not prove, so we must not cache it.
+            self.provisional_cache.remove(&input);
+            self.cycle_participants.clear();
         } else {
             self.provisional_cache.remove(&input);
             let reached_depth = final_entry.reached_depth.as_usize() - self.stack.len();
@@ -476,6 +575,7 @@ pub(super) fn with_new_goal(
                 proof_tree,
                 reached_depth,
                 final_entry.encountered_overflow,
+                self.steps - steps_before,
                 cycle_participants,
                 dep_node,
                 result,
@@ -485,6 +585,19 @@ pub(super) fn with_new_goal(
         result
     }
 
+    /// Whether computing `steps` additional goals for the current root goal
+    /// exceeds `-Zsolver-step-limit`. Once this is the case, we stop proving
+    /// any further goals for this root goal.
+    fn exceeds_step_limit(&mut self, tcx: TyCtxt<'tcx>, steps: usize) -> bool {
+        let Some(step_limit) = tcx.sess.opts.unstable_opts.solver_step_limit else {
+            return false;
+        };
+        if self.reached_step_limit || self.steps + steps > step_limit {
+            self.reached_step_limit = true;
+        }
+        self.reached_step_limit
+    }
+
     fn response_no_constraints(
         tcx: TyCtxt<'tcx>,
         goal: CanonicalInput<'tcx>,
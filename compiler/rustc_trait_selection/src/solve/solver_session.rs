@@ -0,0 +1,213 @@
+//! Recording and replaying the goals of the fulfillment contexts of the new solver,
+//! see `-Zrecord-solver-session` and `-Zreplay-solver-session`.
+//!
+//! A recorded session contains one line per event, with the following tab-separated
+//! fields:
+//! - `register`, the path of the body, the hash and the goal, whenever an obligation
+//!   gets registered,
+//! - `evaluate`, the path of the body, the hash, the outcome and the goal, whenever
+//!   the goal of an obligation has been evaluated.
+//!
+//! The hash is the stable hash of the canonicalized goal, see `goal_summary::goal_hash`,
+//! and the goal is printed in its canonical form. Events are written right away, so the
+//! session of a compilation which ICEs ends with the goal responsible for the ICE.
+//!
+//! When replaying a session, each goal mentioned by the session is evaluated again right
+//! before the fulfillment context evaluates it, but in isolation: using a fresh inference
+//! context and without the hidden types of opaque types defined by the surrounding body.
+//! Differences to the recorded outcome are reported as warnings. Removing lines from the
+//! session restricts the goals which get replayed, which allows bisecting changes to the
+//! solver at the level of individual goals.
+//!
+//! Replaying requires compiling the same crate as when recording the session, as goals are
+//! only matched by their hash and never reconstructed from the session itself. This is
+//! intentional: goals refer to the items of the crate and its dependencies, so evaluating
+//! them requires their impls and where-clauses anyway. Encoding the goals themselves would
+//! also require a decoder mapping `DefPathHash`es back to the items of a compilation, as
+//! used by incremental compilation, without removing the need for the crate. Shrinking a
+//! reproducer therefore still happens at the level of the crate, while this narrows down
+//! which of its goals behave differently.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use rustc_data_structures::stable_hasher::{Hash64, HashStable, StableHasher};
+use rustc_errors::ErrorGuaranteed;
+use rustc_hir::def_id::LocalDefId;
+use rustc_infer::infer::canonical::{Canonical, OriginalQueryValues};
+use rustc_infer::infer::{InferCtxt, TyCtxtInferExt};
+use rustc_infer::traits::query::NoSolution;
+use rustc_middle::traits::solve::{Certainty, Goal, GoalOutcome, ReplayedSolverSession};
+use rustc_middle::ty::{self, ParamEnvAnd, TyCtxt};
+use rustc_span::Span;
+
+use super::eval_ctxt::GenerateProofTree;
+use super::{InferCtxtEvalExt, SolverMode};
+
+pub(super) fn is_replaying(tcx: TyCtxt<'_>) -> bool {
+    tcx.sess.opts.unstable_opts.replay_solver_session.is_some()
+}
+
+/// Records that an obligation with the given `goal` has been registered.
+pub(super) fn record_registered<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    body_id: LocalDefId,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+) {
+    let tcx = infcx.tcx;
+    let Some(path) = &tcx.sess.opts.unstable_opts.record_solver_session else {
+        return;
+    };
+
+    let (_, hash, description) = canonical_goal(infcx, goal);
+    let body = tcx.def_path_str(body_id);
+    write_event(tcx, path, format!("register\t{body}\t{hash}\t{description}\n"));
+}
+
+/// Records the outcome of evaluating the root goal `goal`.
+pub(super) fn record_evaluated<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    body_id: LocalDefId,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+    result: Result<Certainty, NoSolution>,
+) {
+    let tcx = infcx.tcx;
+    let Some(path) = &tcx.sess.opts.unstable_opts.record_solver_session else {
+        return;
+    };
+
+    let (_, hash, description) = canonical_goal(infcx, goal);
+    let body = tcx.def_path_str(body_id);
+    let outcome = GoalOutcome::from_result(result).as_str();
+    write_event(tcx, path, format!("evaluate\t{body}\t{hash}\t{outcome}\t{description}\n"));
+}
+
+/// Evaluates `goal` in isolation if it is part of the replayed session, warning if
+/// its outcome differs from the recorded one.
+pub(super) fn replay_goal<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    solver_mode: SolverMode,
+    body_id: LocalDefId,
+    span: Span,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+) {
+    let tcx = infcx.tcx;
+    let Some(path) = &tcx.sess.opts.unstable_opts.replay_solver_session else {
+        return;
+    };
+
+    let (canonical, hash, description) = canonical_goal(infcx, goal);
+    let body = tcx.def_path_str(body_id);
+    // We must not hold the lock while evaluating the goal, as this may
+    // replay other goals, e.g. when evaluating constants.
+    let recorded = {
+        let mut session = tcx.new_solver_session_replay.lock();
+        let Ok(session) = session.get_or_insert_with(|| load_session(tcx, path)) else {
+            return;
+        };
+        match session.get(&(body, hash)) {
+            Some(&recorded) => recorded,
+            None => return,
+        }
+    };
+
+    let (replay_infcx, ParamEnvAnd { param_env, value: predicate }, _) = tcx
+        .infer_ctxt()
+        .with_next_trait_solver(true)
+        .intercrate(solver_mode == SolverMode::Coherence)
+        .build_with_canonical(span, &canonical);
+    let result = replay_infcx.probe(|_| {
+        let goal = Goal::new(tcx, param_env, predicate);
+        let (result, _) =
+            replay_infcx.evaluate_root_goal_in_mode(goal, solver_mode, GenerateProofTree::Never);
+        result.map(|(_, certainty)| certainty)
+    });
+    let outcome = GoalOutcome::from_result(result);
+    if let Some(recorded) = recorded
+        && recorded != outcome
+    {
+        tcx.dcx().span_warn(
+            span,
+            format!(
+                "replaying `{description}` resulted in `{}` instead of the recorded `{}`",
+                outcome.as_str(),
+                recorded.as_str(),
+            ),
+        );
+    }
+}
+
+/// Canonicalizes `goal`, returning it together with its stable hash and description.
+fn canonical_goal<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+) -> (Canonical<'tcx, ParamEnvAnd<'tcx, ty::Predicate<'tcx>>>, String, String) {
+    let canonical = infcx.canonicalize_query(
+        goal.param_env.and(goal.predicate),
+        &mut OriginalQueryValues::default(),
+    );
+    let hash: Hash64 = infcx.tcx.with_stable_hashing_context(|mut hcx| {
+        let mut hasher = StableHasher::new();
+        canonical.hash_stable(&mut hcx, &mut hasher);
+        hasher.finish()
+    });
+    // Each event is stored on a single line, so we have to get rid of any line
+    // breaks and tabs in the description of the goal.
+    let description =
+        canonical.value.value.to_string().replace(|c: char| c == '\t' || c == '\n', " ");
+    (canonical, format!("{:016x}", hash.as_u64()), description)
+}
+
+fn write_event(tcx: TyCtxt<'_>, path: &Path, event: String) {
+    let mut session = tcx.new_solver_session_log.lock();
+    let file = session.get_or_insert_with(|| {
+        File::create(path).map_err(|err| {
+            tcx.dcx().err(format!(
+                "failed to create the solver session `{}`: {err}",
+                path.display()
+            ))
+        })
+    });
+    if let Ok(file) = file
+        && let Err(err) = file.write_all(event.as_bytes())
+    {
+        let guar = tcx.dcx().err(format!(
+            "failed to write to the solver session `{}`: {err}",
+            path.display()
+        ));
+        *session = Some(Err(guar));
+    }
+}
+
+fn load_session(tcx: TyCtxt<'_>, path: &Path) -> Result<ReplayedSolverSession, ErrorGuaranteed> {
+    let session = fs::read_to_string(path).map_err(|err| {
+        tcx.dcx()
+            .err(format!("failed to read the solver session `{}`: {err}", path.display()))
+    })?;
+    let malformed = |line: &str| {
+        tcx.dcx().err(format!(
+            "malformed solver session `{}`: unexpected line `{line}`",
+            path.display()
+        ))
+    };
+
+    let mut goals = ReplayedSolverSession::default();
+    for line in session.lines().filter(|line| !line.is_empty()) {
+        let mut parts = line.split('\t');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("register"), Some(body), Some(hash)) => {
+                goals.entry((body.to_owned(), hash.to_owned())).or_insert(None);
+            }
+            (Some("evaluate"), Some(body), Some(hash)) => {
+                let outcome = parts.next().and_then(GoalOutcome::from_str);
+                let Some(outcome) = outcome else {
+                    return Err(malformed(line));
+                };
+                goals.insert((body.to_owned(), hash.to_owned()), Some(outcome));
+            }
+            _ => return Err(malformed(line)),
+        }
+    }
+    Ok(goals)
+}
@@ -0,0 +1,72 @@
+//! Attributes the time spent by the new solver to the spans of the evaluated
+//! obligations, see `-Zsolver-span-profile`.
+//!
+//! This allows finding the expressions and impls responsible for slow type
+//! checking without having to post-process the output of `-Zself-profile`.
+
+use std::fmt::Write;
+use std::fs;
+use std::time::Duration;
+
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+pub(super) fn is_enabled(tcx: TyCtxt<'_>) -> bool {
+    tcx.sess.opts.unstable_opts.solver_span_profile.is_some()
+}
+
+/// Records that evaluating a root goal whose obligation has the given `span` took `time`.
+pub(super) fn record_evaluation(tcx: TyCtxt<'_>, span: Span, time: Duration) {
+    let mut profile = tcx.new_solver_span_profile.lock();
+    let (total, evaluations) = profile.entry(span).or_default();
+    *total += time;
+    *evaluations += 1;
+}
+
+/// Writes the time spent evaluating the obligations of each span as a JSON array,
+/// starting with the most expensive span.
+pub fn emit_span_profile(tcx: TyCtxt<'_>) {
+    let Some(path) = &tcx.sess.opts.unstable_opts.solver_span_profile else {
+        return;
+    };
+
+    let mut entries: Vec<_> = tcx.new_solver_span_profile.lock().drain(..).collect();
+    entries.sort_by(|(_, (a, _)), (_, (b, _))| b.cmp(a));
+
+    let source_map = tcx.sess.source_map();
+    let mut report = String::from("[\n");
+    for (i, (span, (total, evaluations))) in entries.into_iter().enumerate() {
+        if i > 0 {
+            report.push_str(",\n");
+        }
+        let span = escape_json(&source_map.span_to_embeddable_string(span));
+        let time_ns = total.as_nanos();
+        let _ = write!(
+            report,
+            "  {{\"span\": \"{span}\", \"time_ns\": {time_ns}, \"evaluations\": {evaluations}}}",
+        );
+    }
+    report.push_str("\n]\n");
+
+    if let Err(err) = fs::write(path, report) {
+        tcx.dcx().err(format!(
+            "failed to write the solver span profile to `{}`: {err}",
+            path.display()
+        ));
+    }
+}
+
+fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -4,12 +4,12 @@
 
 use super::assembly::structural_traits::AsyncCallableRelevantTypes;
 use super::assembly::{self, structural_traits, Candidate};
-use super::{EvalCtxt, GoalSource, SolverMode};
+use super::{CanonicalResponseExt, EvalCtxt, GoalSource, SolverMode};
 use rustc_data_structures::fx::FxIndexSet;
 use rustc_hir::def_id::DefId;
 use rustc_hir::{LangItem, Movability};
 use rustc_infer::traits::query::NoSolution;
-use rustc_infer::traits::solve::MaybeCause;
+use rustc_infer::traits::solve::{AmbiguityCause, MaybeCause};
 use rustc_middle::traits::solve::inspect::ProbeKind;
 use rustc_middle::traits::solve::{CandidateSource, Certainty, Goal, QueryResult};
 use rustc_middle::traits::{BuiltinImplSource, Reveal};
@@ -58,7 +58,9 @@ fn consider_impl_candidate(
             // In intercrate mode, this is ambiguous. But outside of intercrate,
             // it's not a real impl.
             (ty::ImplPolarity::Reservation, _) => match ecx.solver_mode() {
-                SolverMode::Coherence => Certainty::AMBIGUOUS,
+                SolverMode::Coherence => {
+                    Certainty::ambiguous(AmbiguityCause::ReservationImpl(impl_def_id))
+                }
                 SolverMode::Normal => return Err(NoSolution),
             },
 
@@ -184,9 +186,10 @@ fn consider_trait_alias_candidate(
             let nested_obligations = tcx
                 .predicates_of(goal.predicate.def_id())
                 .instantiate(tcx, goal.predicate.trait_ref.args);
-            // FIXME(-Znext-solver=coinductive): Should this be `GoalSource::ImplWhereBound`?
+            // FIXME(-Znext-solver=coinductive): Should this be treated like
+            // `GoalSource::ImplWhereBound`?
             ecx.add_goals(
-                GoalSource::Misc,
+                GoalSource::TraitAliasBound,
                 nested_obligations.predicates.into_iter().map(|p| goal.with(tcx, p)),
             );
             ecx.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
@@ -239,7 +242,7 @@ fn consider_builtin_pointer_like_candidate(
         let key = tcx.erase_regions(goal.param_env.and(goal.predicate.self_ty()));
         // But if there are inference variables, we have to wait until it's resolved.
         if key.has_non_region_infer() {
-            return ecx.forced_ambiguity(MaybeCause::Ambiguity);
+            return ecx.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::UnresolvedType));
         }
 
         if let Ok(layout) = tcx.layout_of(key)
@@ -302,7 +305,7 @@ fn consider_builtin_fn_trait_candidates(
             )? {
                 Some(a) => a,
                 None => {
-                    return ecx.forced_ambiguity(MaybeCause::Ambiguity);
+                    return ecx.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::Unknown));
                 }
             };
         let output_is_sized_pred = tupled_inputs_and_output.map_bound(|(_, output)| {
@@ -672,9 +675,9 @@ fn consider_structural_builtin_unsize_candidates(
             match (a_ty.kind(), b_ty.kind()) {
                 (ty::Infer(ty::TyVar(..)), ..) => bug!("unexpected infer {a_ty:?} {b_ty:?}"),
 
-                (_, ty::Infer(ty::TyVar(..))) => {
-                    result_to_single(ecx.forced_ambiguity(MaybeCause::Ambiguity))
-                }
+                (_, ty::Infer(ty::TyVar(..))) => result_to_single(
+                    ecx.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::UnresolvedType)),
+                ),
 
                 // Trait upcasting, or `dyn Trait + Auto + 'a` -> `dyn Trait + 'b`.
                 (
@@ -792,14 +795,14 @@ fn consider_builtin_unsize_to_dyn_candidate(
             // Check that the type implements all of the predicates of the trait object.
             // (i.e. the principal, all of the associated types match, and any auto traits)
             ecx.add_goals(
-                GoalSource::ImplWhereBound,
+                GoalSource::UnsizeMetadata,
                 b_data.iter().map(|pred| goal.with(tcx, pred.with_self_ty(tcx, a_ty))),
             );
 
             // The type must be `Sized` to be unsized.
             if let Some(sized_def_id) = tcx.lang_items().sized_trait() {
                 ecx.add_goal(
-                    GoalSource::ImplWhereBound,
+                    GoalSource::UnsizeMetadata,
                     goal.with(tcx, ty::TraitRef::new(tcx, sized_def_id, [a_ty])),
                 );
             } else {
@@ -980,7 +983,7 @@ fn consider_builtin_struct_unsize(
         // types.
         self.eq(goal.param_env, unsized_a_ty, b_ty)?;
         self.add_goal(
-            GoalSource::ImplWhereBound,
+            GoalSource::UnsizeTailField,
             goal.with(
                 tcx,
                 ty::TraitRef::new(
@@ -1023,7 +1026,7 @@ fn consider_builtin_tuple_unsize(
 
         // Similar to ADTs, require that we can unsize the tail.
         self.add_goal(
-            GoalSource::ImplWhereBound,
+            GoalSource::UnsizeTailField,
             goal.with(
                 tcx,
                 ty::TraitRef::new(
@@ -1053,7 +1056,7 @@ fn disqualify_auto_trait_candidate_due_to_possible_impl(
             // we probably don't want to treat an `impl !AutoTrait for i32` as
             // disqualifying the built-in auto impl for `i64: AutoTrait` either.
             ty::Infer(ty::IntVar(_) | ty::FloatVar(_)) => {
-                Some(self.forced_ambiguity(MaybeCause::Ambiguity))
+                Some(self.forced_ambiguity(MaybeCause::Ambiguity(AmbiguityCause::UnresolvedType)))
             }
 
             // These types cannot be structurally decomposed into constituent
@@ -1166,12 +1169,69 @@ fn probe_and_evaluate_goal_for_constituent_tys(
         })
     }
 
+    /// Returns an explicit negative impl, e.g. `impl !Send for Foo`, which
+    /// applies to the positive `trait_ref`.
+    ///
+    /// Unlike the mere absence of a positive impl, such an impl is a promise
+    /// by its author that the positive impl will never exist. Each negative impl
+    /// we consider gets its own `ProbeKind::ExplicitNegativeImpl` probe so that
+    /// diagnostics are able to point to it.
+    pub(super) fn explicit_negative_impl_for(
+        &mut self,
+        param_env: ty::ParamEnv<'tcx>,
+        trait_ref: ty::TraitRef<'tcx>,
+    ) -> Option<DefId> {
+        let tcx = self.tcx();
+        let mut negative_impls = vec![];
+        tcx.for_each_relevant_impl(trait_ref.def_id, trait_ref.self_ty(), |impl_def_id| {
+            if tcx.impl_polarity(impl_def_id) == ty::ImplPolarity::Negative {
+                negative_impls.push(impl_def_id);
+            }
+        });
+
+        negative_impls.into_iter().find(|&impl_def_id| {
+            self.probe(|&result| ProbeKind::ExplicitNegativeImpl { impl_def_id, result })
+                .enter(|ecx| {
+                    let impl_args = ecx.fresh_args_for_item(impl_def_id);
+                    let impl_trait_ref =
+                        tcx.impl_trait_ref(impl_def_id).unwrap().instantiate(tcx, impl_args);
+                    ecx.eq(param_env, trait_ref, impl_trait_ref)?;
+                    let where_clause_bounds = tcx
+                        .predicates_of(impl_def_id)
+                        .instantiate(tcx, impl_args)
+                        .predicates
+                        .into_iter()
+                        .map(|pred| Goal::new(tcx, param_env, pred));
+                    ecx.add_goals(GoalSource::ImplWhereBound, where_clause_bounds);
+                    ecx.evaluate_added_goals_and_make_canonical_response(Certainty::Yes)
+                })
+                // The negative impl only rules out the goal if it applies
+                // without having to guess any inference variables.
+                .is_ok_and(|response| {
+                    response.value.certainty == Certainty::Yes
+                        && response.has_no_inference_or_external_constraints()
+                })
+        })
+    }
+
     #[instrument(level = "debug", skip(self))]
     pub(super) fn compute_trait_goal(
         &mut self,
         goal: Goal<'tcx, TraitPredicate<'tcx>>,
     ) -> QueryResult<'tcx> {
         let candidates = self.assemble_and_evaluate_candidates(goal);
-        self.merge_candidates(candidates)
+        let result = self.merge_candidates(candidates);
+
+        // If the goal does not hold, also record any explicit negative impl
+        // which applies in the proof tree. This is only used by diagnostics,
+        // so we don't bother if we aren't building a proof tree.
+        if result.is_err()
+            && goal.predicate.polarity == ty::PredicatePolarity::Positive
+            && !self.inspect.is_noop()
+        {
+            let _ = self.explicit_negative_impl_for(goal.param_env, goal.predicate.trait_ref);
+        }
+
+        result
     }
 }
@@ -5,13 +5,18 @@
 
 use crate::errors::UnableToConstructConstantValue;
 use crate::infer::region_constraints::{Constraint, RegionConstraintData};
+use crate::solve::inspect::{InspectGoal, ProofTreeInferCtxtExt, ProofTreeVisitor};
 use crate::traits::project::ProjectAndUnifyResult;
+use crate::traits::query::evaluate_obligation::InferCtxtExt as _;
 
 use rustc_data_structures::fx::{FxIndexMap, FxIndexSet, IndexEntry};
 use rustc_data_structures::unord::UnordSet;
 use rustc_infer::infer::DefineOpaqueTypes;
 use rustc_middle::mir::interpret::ErrorHandled;
+use rustc_middle::traits::query::NoSolution;
+use rustc_middle::traits::solve::{Certainty, Goal};
 use rustc_middle::ty::{Region, RegionVid};
+use rustc_span::DUMMY_SP;
 
 use std::collections::VecDeque;
 use std::iter;
@@ -79,7 +84,8 @@ pub fn find_auto_trait_generics<A>(
 
         let trait_ref = ty::TraitRef::new(tcx, trait_did, [ty]);
 
-        let infcx = tcx.infer_ctxt().build();
+        let infcx =
+            tcx.infer_ctxt().with_next_trait_solver(tcx.next_trait_solver_globally()).build();
         let mut selcx = SelectionContext::new(&infcx);
         for polarity in [ty::PredicatePolarity::Positive, ty::PredicatePolarity::Negative] {
             let result = selcx.select(&Obligation::new(
@@ -99,7 +105,8 @@ pub fn find_auto_trait_generics<A>(
             }
         }
 
-        let infcx = tcx.infer_ctxt().build();
+        let infcx =
+            tcx.infer_ctxt().with_next_trait_solver(tcx.next_trait_solver_globally()).build();
         let mut fresh_preds = FxIndexSet::default();
 
         // Due to the way projections are handled by SelectionContext, we need to run
@@ -135,17 +142,34 @@ pub fn find_auto_trait_generics<A>(
         // traits::project will see that 'T: SomeTrait' is in our ParamEnv, allowing
         // SelectionContext to return it back to us.
 
-        let Some((new_env, user_env)) =
-            self.evaluate_predicates(&infcx, trait_did, ty, orig_env, orig_env, &mut fresh_preds)
-        else {
-            return AutoTraitResult::NegativeImpl;
-        };
+        //
+        // None of this applies to the new solver, which instead computes the bounds by
+        // walking the proof tree of the auto trait goal, see
+        // `evaluate_predicates_in_new_solver`.
+
+        let (full_env, full_user_env) = if infcx.next_trait_solver() {
+            let Some(envs) = self.evaluate_predicates_in_new_solver(&infcx, trait_did, ty, orig_env)
+            else {
+                return AutoTraitResult::NegativeImpl;
+            };
+            envs
+        } else {
+            let Some((new_env, user_env)) = self.evaluate_predicates(
+                &infcx,
+                trait_did,
+                ty,
+                orig_env,
+                orig_env,
+                &mut fresh_preds,
+            ) else {
+                return AutoTraitResult::NegativeImpl;
+            };
 
-        let (full_env, full_user_env) = self
-            .evaluate_predicates(&infcx, trait_did, ty, new_env, user_env, &mut fresh_preds)
-            .unwrap_or_else(|| {
-                panic!("Failed to fully process: {ty:?} {trait_did:?} {orig_env:?}")
-            });
+            self.evaluate_predicates(&infcx, trait_did, ty, new_env, user_env, &mut fresh_preds)
+                .unwrap_or_else(|| {
+                    panic!("Failed to fully process: {ty:?} {trait_did:?} {orig_env:?}")
+                })
+        };
 
         debug!(
             "find_auto_trait_generics({:?}): fulfilling \
@@ -300,6 +324,16 @@ fn evaluate_predicates(
                     }
                 }
                 Ok(None) => {}
+                // The new solver does not treat explicit negative impls as candidates
+                // for positive goals, but reports them as an error instead.
+                Err(SelectionError::ExplicitNegativeImpl(impl_def_id)) => {
+                    debug!(
+                        "evaluate_nested_obligations: found explicit negative impl \
+                         {:?}, bailing out",
+                        impl_def_id
+                    );
+                    return None;
+                }
                 Err(SelectionError::Unimplemented) => {
                     if self.is_param_no_infer(pred.skip_binder().trait_ref.args) {
                         already_visited.remove(&pred);
@@ -340,6 +374,95 @@ fn evaluate_predicates(
         Some((new_env, final_user_env))
     }
 
+    /// Computes the bounds for our synthesized impl when using the new solver.
+    ///
+    /// Instead of selecting the nested obligations of each predicate ourselves, we
+    /// evaluate the auto trait goal and walk its proof tree, collecting the failing
+    /// goals on generic parameters, see `MissingAutoTraitBounds`. These get added to
+    /// the `ParamEnv` until the goal holds. As the proof tree only contains the
+    /// candidates actually considered by the solver, this does not suffer from the
+    /// `T: IntoIterator` issue described in `evaluate_predicates`.
+    ///
+    /// Finally, we remove the added bounds which are implied by the remaining ones,
+    /// so that the synthesized impl only has a minimal set of where-clauses.
+    fn evaluate_predicates_in_new_solver(
+        &self,
+        infcx: &InferCtxt<'tcx>,
+        trait_did: DefId,
+        ty: Ty<'tcx>,
+        orig_env: ty::ParamEnv<'tcx>,
+    ) -> Option<(ty::ParamEnv<'tcx>, ty::ParamEnv<'tcx>)> {
+        let tcx = infcx.tcx;
+        let trait_ref = ty::TraitRef::new(tcx, trait_did, [ty]);
+        let orig_preds = orig_env.caller_bounds().iter().map(|c| c.as_predicate());
+        let param_env_with = |added_preds: &FxIndexSet<ty::Predicate<'tcx>>| {
+            let preds = elaborate(tcx, orig_preds.clone().chain(added_preds.iter().copied()));
+            ty::ParamEnv::new(
+                tcx.mk_clauses_from_iter(preds.filter_map(|p| p.as_clause())),
+                orig_env.reveal(),
+            )
+        };
+
+        let mut added_preds = FxIndexSet::default();
+        loop {
+            let goal = Goal::new(tcx, param_env_with(&added_preds), trait_ref);
+            let mut visitor = MissingAutoTraitBounds::new(self);
+            infcx.probe(|_| infcx.visit_proof_tree(goal, &mut visitor));
+            if visitor.impossible {
+                debug!(
+                    "evaluate_predicates_in_new_solver: {:?} can never implement {:?}",
+                    ty, trait_did
+                );
+                return None;
+            }
+
+            let num_added_preds = added_preds.len();
+            added_preds.extend(visitor.bounds);
+            if added_preds.len() == num_added_preds {
+                // Ambiguity is ignored, as with the old solver, but if the goal still
+                // fails there is nothing else we could add to the `ParamEnv`.
+                if let Some(Err(NoSolution)) = visitor.root_result {
+                    debug!(
+                        "evaluate_predicates_in_new_solver: unable to prove {:?} with {:?}",
+                        trait_ref, added_preds
+                    );
+                    return None;
+                }
+                break;
+            }
+        }
+
+        // Remove the bounds which are not necessary for the goal to hold, e.g.
+        // `T: Clone` if we've also added `T: Copy`. We try to remove the bounds
+        // added last first, as they tend to be more specific.
+        for pred in added_preds.clone().into_iter().rev() {
+            let mut remaining_preds = added_preds.clone();
+            remaining_preds.shift_remove(&pred);
+            let obligation = Obligation::new(
+                tcx,
+                ObligationCause::dummy(),
+                param_env_with(&remaining_preds),
+                trait_ref,
+            );
+            if infcx.predicate_must_hold_modulo_regions(&obligation) {
+                added_preds = remaining_preds;
+            }
+        }
+
+        let full_env = param_env_with(&added_preds);
+        let user_env = ty::ParamEnv::new(
+            tcx.mk_clauses_from_iter(orig_preds.chain(added_preds).filter_map(|p| p.as_clause())),
+            orig_env.reveal(),
+        );
+        debug!(
+            "evaluate_predicates_in_new_solver(ty={:?}, trait_did={:?}): succeeded with \
+             '{:?}' '{:?}'",
+            ty, trait_did, full_env, user_env
+        );
+
+        Some((full_env, user_env))
+    }
+
     /// This method is designed to work around the following issue:
     /// When we compute auto trait bounds, we repeatedly call `SelectionContext.select`,
     /// progressively building a `ParamEnv` based on the results we get.
@@ -684,6 +807,22 @@ fn evaluate_nested_obligations(
                     // and turn them into an explicit negative impl for our type.
                     debug!("Projecting and unifying projection predicate {:?}", predicate);
 
+                    // The new solver handles nested obligations of the projection
+                    // itself, so we only have to check whether projecting fails.
+                    if selcx.infcx.next_trait_solver() {
+                        let ocx = ObligationCtxt::new(selcx.infcx);
+                        ocx.register_obligation(obligation.with(self.tcx, p));
+                        if ocx.select_where_possible().iter().any(|e| e.is_true_error()) {
+                            debug!(
+                                "evaluate_nested_obligations: Unable to project predicate \
+                                 '{:?}' '{:?}', bailing out",
+                                ty, predicate
+                            );
+                            return false;
+                        }
+                        continue;
+                    }
+
                     match project::poly_project_and_unify_type(selcx, &obligation.with(self.tcx, p))
                     {
                         ProjectAndUnifyResult::MismatchedProjectionTypes(e) => {
@@ -821,3 +960,89 @@ pub fn clean_pred(
         infcx.freshen(p)
     }
 }
+
+/// Collects the bounds on generic parameters whose absence causes an auto trait
+/// goal to fail, see `AutoTraitFinder::evaluate_predicates_in_new_solver`.
+struct MissingAutoTraitBounds<'a, 'tcx> {
+    finder: &'a AutoTraitFinder<'tcx>,
+    root_result: Option<Result<Certainty, NoSolution>>,
+    bounds: Vec<ty::Predicate<'tcx>>,
+    /// Whether the goal fails in a way which cannot be fixed by adding
+    /// bounds, e.g. because of an explicit negative impl.
+    impossible: bool,
+}
+
+impl<'a, 'tcx> MissingAutoTraitBounds<'a, 'tcx> {
+    fn new(finder: &'a AutoTraitFinder<'tcx>) -> Self {
+        MissingAutoTraitBounds { finder, root_result: None, bounds: vec![], impossible: false }
+    }
+
+    fn push(&mut self, bound: ty::Predicate<'tcx>) {
+        // Bounds mentioning inference variables cannot be shown to the user.
+        if !bound.has_infer() && !self.bounds.contains(&bound) {
+            self.bounds.push(bound);
+        }
+    }
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for MissingAutoTraitBounds<'_, 'tcx> {
+    fn span(&self) -> Span {
+        DUMMY_SP
+    }
+
+    fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) {
+        let result = goal.result();
+        self.root_result.get_or_insert(result);
+        // Like `evaluate_predicates`, we ignore ambiguous goals.
+        if result.is_ok() || self.impossible {
+            return;
+        }
+
+        let predicate = goal.infcx().resolve_vars_if_possible(goal.goal().predicate);
+        match predicate.kind().skip_binder() {
+            ty::PredicateKind::Clause(ty::ClauseKind::Trait(trait_pred))
+                if trait_pred.polarity == ty::PredicatePolarity::Positive
+                    && self.finder.is_param_no_infer(trait_pred.trait_ref.args) =>
+            {
+                self.push(predicate);
+                return;
+            }
+            ty::PredicateKind::Clause(ty::ClauseKind::Projection(projection))
+                if self.finder.is_param_no_infer(projection.projection_ty.args)
+                    && !self.finder.is_self_referential_projection(
+                        predicate.kind().rebind(projection),
+                    ) =>
+            {
+                self.push(predicate);
+                return;
+            }
+            _ => {}
+        }
+
+        if goal.explicit_negative_impl().is_some() {
+            self.impossible = true;
+            return;
+        }
+
+        match &goal.candidates()[..] {
+            [] => self.impossible = true,
+            [candidate] => candidate.visit_nested_in_probe(self),
+            candidates => {
+                // Use the candidate which requires the fewest additional bounds.
+                let bounds = candidates
+                    .iter()
+                    .filter_map(|candidate| {
+                        let mut visitor = MissingAutoTraitBounds::new(self.finder);
+                        visitor.root_result = Some(result);
+                        candidate.visit_nested_in_probe(&mut visitor);
+                        (!visitor.impossible).then_some(visitor.bounds)
+                    })
+                    .min_by_key(|bounds| bounds.len());
+                match bounds {
+                    Some(bounds) => bounds.into_iter().for_each(|bound| self.push(bound)),
+                    None => self.impossible = true,
+                }
+            }
+        }
+    }
+}
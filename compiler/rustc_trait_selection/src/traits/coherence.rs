@@ -1101,6 +1101,16 @@ fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) {
             {
                 proj.projection_ty.trait_ref(infcx.tcx)
             }
+            // Projection goals get lowered to `NormalizesTo` goals in the new
+            // solver, so we also have to look at their unknowable candidates.
+            Some(ty::PredicateKind::NormalizesTo(normalizes_to))
+                if matches!(
+                    infcx.tcx.def_kind(normalizes_to.alias.def_id),
+                    DefKind::AssocTy | DefKind::AssocConst
+                ) =>
+            {
+                normalizes_to.alias.trait_ref(infcx.tcx)
+            }
             _ => return,
         };
 
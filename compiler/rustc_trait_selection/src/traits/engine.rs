@@ -13,22 +13,25 @@
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_infer::infer::at::ToTrace;
 use rustc_infer::infer::canonical::{
-    Canonical, CanonicalQueryResponse, CanonicalVarValues, QueryResponse,
+    Canonical, CanonicalQueryResponse, CanonicalVarValues, QueryOutlivesConstraint, QueryResponse,
 };
 use rustc_infer::infer::outlives::env::OutlivesEnvironment;
 use rustc_infer::infer::RegionResolutionError;
 use rustc_infer::infer::{DefineOpaqueTypes, InferCtxt, InferOk};
 use rustc_infer::traits::{
-    FulfillmentError, Obligation, ObligationCause, PredicateObligation, TraitEngineExt as _,
+    CanonicalObligations, FulfillmentError, Obligation, ObligationCause, PredicateObligation,
+    TraitEngineExt as _,
 };
 use rustc_macros::extension;
 use rustc_middle::arena::ArenaAllocatable;
 use rustc_middle::traits::query::NoSolution;
+use rustc_middle::traits::solve::Goal;
 use rustc_middle::ty::error::TypeError;
 use rustc_middle::ty::ToPredicate;
 use rustc_middle::ty::TypeFoldable;
 use rustc_middle::ty::Variance;
 use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::Span;
 
 #[extension(pub trait TraitEngineExt<'tcx>)]
 impl<'tcx> dyn TraitEngine<'tcx> {
@@ -74,6 +77,29 @@ pub fn register_obligations(
         }
     }
 
+    /// Registers `obligations` as having been created while processing `parent`.
+    /// This allows errors to point to `parent` instead of the nested obligations.
+    pub fn register_nested_obligations(
+        &self,
+        parent: &PredicateObligation<'tcx>,
+        obligations: impl IntoIterator<Item = PredicateObligation<'tcx>>,
+    ) {
+        for obligation in obligations {
+            self.engine
+                .borrow_mut()
+                .register_nested_predicate_obligation(self.infcx, obligation, parent)
+        }
+    }
+
+    /// Registers goals which all share the same `cause`, see `TraitEngine::register_goals`.
+    pub fn register_goals(
+        &self,
+        cause: &ObligationCause<'tcx>,
+        goals: Vec<Goal<'tcx, ty::Predicate<'tcx>>>,
+    ) {
+        self.engine.borrow_mut().register_goals(self.infcx, cause, goals);
+    }
+
     pub fn register_infer_ok_obligations<T>(&self, infer_ok: InferOk<'tcx, T>) -> T {
         let InferOk { value, obligations } = infer_ok;
         self.engine.borrow_mut().register_predicate_obligations(self.infcx, obligations);
@@ -100,6 +126,23 @@ pub fn register_bound(
         });
     }
 
+    pub fn register_projection_equality(
+        &self,
+        cause: ObligationCause<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+        alias: ty::AliasTy<'tcx>,
+        term: ty::Term<'tcx>,
+    ) {
+        let tcx = self.infcx.tcx;
+        let projection = ty::ProjectionPredicate { projection_ty: alias, term };
+        self.register_obligation(Obligation {
+            cause,
+            recursion_depth: 0,
+            param_env,
+            predicate: ty::Binder::dummy(projection).to_predicate(tcx),
+        });
+    }
+
     pub fn normalize<T: TypeFoldable<TyCtxt<'tcx>>>(
         &self,
         cause: &ObligationCause<'tcx>,
@@ -195,6 +238,29 @@ pub fn select_all_or_error(&self) -> Vec<FulfillmentError<'tcx>> {
         self.engine.borrow_mut().select_all_or_error(self.infcx)
     }
 
+    /// Returns the errors for the obligations which are still pending without
+    /// removing them from this context, see `TraitEngine::peek_remaining_errors`.
+    #[must_use]
+    pub fn peek_remaining_errors(&self) -> Vec<FulfillmentError<'tcx>> {
+        self.engine.borrow().peek_remaining_errors(self.infcx)
+    }
+
+    /// Enables tracking the outlives constraints of the obligations of this
+    /// `ObligationCtxt`, see [`ObligationCtxt::outlives_constraints_by_root`].
+    pub fn track_outlives_constraints(&self) {
+        self.engine.borrow_mut().track_outlives_constraints();
+    }
+
+    /// Returns the outlives constraints which resulted from proving the
+    /// obligations of this `ObligationCtxt`, together with the root obligation
+    /// they are attributable to. This is only tracked by the new solver and
+    /// has to be enabled via [`ObligationCtxt::track_outlives_constraints`].
+    pub fn outlives_constraints_by_root(
+        &self,
+    ) -> Vec<(PredicateObligation<'tcx>, QueryOutlivesConstraint<'tcx>)> {
+        self.engine.borrow().outlives_constraints_by_root()
+    }
+
     /// Returns the not-yet-processed and stalled obligations from the
     /// `ObligationCtxt`.
     ///
@@ -207,6 +273,25 @@ pub fn into_pending_obligations(self) -> Vec<PredicateObligation<'tcx>> {
         self.engine.borrow().pending_obligations()
     }
 
+    /// Returns the not-yet-processed and stalled obligations in canonical form,
+    /// so that they can be registered in an `ObligationCtxt` of a different
+    /// `InferCtxt` using [`ObligationCtxt::register_canonical_obligations`].
+    #[must_use]
+    pub fn into_canonical_pending_obligations(self) -> CanonicalObligations<'tcx> {
+        self.engine.borrow().canonical_pending_obligations(self.infcx)
+    }
+
+    /// Registers obligations taken from a different `InferCtxt` using
+    /// [`ObligationCtxt::into_canonical_pending_obligations`], returning the
+    /// inference variables their canonical variables got instantiated with.
+    pub fn register_canonical_obligations(
+        &self,
+        span: Span,
+        obligations: &CanonicalObligations<'tcx>,
+    ) -> CanonicalVarValues<'tcx> {
+        self.engine.borrow_mut().register_canonical_obligations(self.infcx, span, obligations)
+    }
+
     /// Resolves regions and reports errors.
     ///
     /// Takes ownership of the context as doing trait solving afterwards
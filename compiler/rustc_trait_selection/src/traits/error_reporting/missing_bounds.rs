@@ -0,0 +1,98 @@
+//! Uses the proof trees of the new solver to find the bounds on generic parameters
+//! whose absence causes a goal to fail. E.g. given `impl<T: Clone> Foo for Vec<T>`,
+//! the goal `Vec<T>: Foo` fails because of the missing `T: Clone` bound.
+
+use rustc_infer::infer::InferCtxt;
+use rustc_middle::traits::solve::Goal;
+use rustc_middle::ty::{self, Ty, TypeVisitableExt};
+use rustc_span::symbol::Symbol;
+use rustc_span::Span;
+
+use crate::solve::inspect::{InspectGoal, ProofTreeInferCtxtExt, ProofTreeVisitor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct MissingParamBound<'tcx> {
+    /// The missing trait bound, e.g. `T: Iterator`.
+    pub trait_pred: ty::PolyTraitPredicate<'tcx>,
+    /// For associated type equality bounds, e.g. `T: Iterator<Item = u32>`,
+    /// the name of the associated type and the type it has to be equal to.
+    pub assoc_ty: Option<(Symbol, Ty<'tcx>)>,
+}
+
+/// Walks the proof tree of the failing `goal` and returns the bounds on generic
+/// parameters which would make it hold. We only descend into goals with a single
+/// candidate, as it is otherwise unclear which candidate the user intended to use.
+///
+/// This instantiates the nested goals of the proof tree and should therefore
+/// only be used inside of a probe.
+pub(super) fn missing_param_bounds<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    goal: Goal<'tcx, ty::Predicate<'tcx>>,
+    span: Span,
+) -> Vec<MissingParamBound<'tcx>> {
+    let mut visitor = MissingParamBounds { span, bounds: vec![] };
+    infcx.visit_proof_tree(goal, &mut visitor);
+    visitor.bounds
+}
+
+struct MissingParamBounds<'tcx> {
+    span: Span,
+    bounds: Vec<MissingParamBound<'tcx>>,
+}
+
+impl<'tcx> MissingParamBounds<'tcx> {
+    fn push(&mut self, bound: MissingParamBound<'tcx>) {
+        if !bound.trait_pred.has_infer() && !self.bounds.contains(&bound) {
+            self.bounds.push(bound);
+        }
+    }
+}
+
+impl<'tcx> ProofTreeVisitor<'tcx> for MissingParamBounds<'tcx> {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn visit_goal(&mut self, goal: &InspectGoal<'_, 'tcx>) {
+        if goal.result().is_ok() {
+            return;
+        }
+
+        let tcx = goal.infcx().tcx;
+        match goal.goal().predicate.kind().no_bound_vars() {
+            Some(ty::PredicateKind::Clause(ty::ClauseKind::Trait(trait_pred)))
+                if trait_pred.polarity == ty::PredicatePolarity::Positive
+                    && matches!(trait_pred.self_ty().kind(), ty::Param(_)) =>
+            {
+                self.push(MissingParamBound {
+                    trait_pred: ty::Binder::dummy(trait_pred),
+                    assoc_ty: None,
+                });
+                return;
+            }
+            Some(ty::PredicateKind::Clause(ty::ClauseKind::Projection(projection)))
+                if matches!(projection.self_ty().kind(), ty::Param(_)) =>
+            {
+                if let Some(term) = projection.term.ty()
+                    && !term.has_infer()
+                {
+                    let trait_ref = projection.projection_ty.trait_ref(tcx);
+                    let name = tcx.associated_item(projection.projection_ty.def_id).name;
+                    self.push(MissingParamBound {
+                        trait_pred: ty::Binder::dummy(ty::TraitPredicate {
+                            trait_ref,
+                            polarity: ty::PredicatePolarity::Positive,
+                        }),
+                        assoc_ty: Some((name, term)),
+                    });
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        if let [candidate] = &goal.candidates()[..] {
+            candidate.visit_nested_in_probe(self);
+        }
+    }
+}
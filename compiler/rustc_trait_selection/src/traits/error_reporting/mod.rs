@@ -2,6 +2,7 @@
 
 pub mod ambiguity;
 mod infer_ctxt_ext;
+mod missing_bounds;
 pub mod on_unimplemented;
 pub mod suggestions;
 mod type_err_ctxt_ext;
@@ -12,8 +13,9 @@
 use rustc_hir as hir;
 use rustc_hir::def_id::DefId;
 use rustc_hir::intravisit::Visitor;
-use rustc_middle::traits::solve::Goal;
+use rustc_middle::traits::solve::{inspect, Goal};
 use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_session::config::SolverProofTreeFormat;
 use rustc_span::Span;
 use std::io::Write;
 use std::ops::ControlFlow;
@@ -192,8 +194,15 @@ pub fn dump_proof_tree<'tcx>(o: &Obligation<'tcx, ty::Predicate<'tcx>>, infcx: &
             .evaluate_root_goal(goal, GenerateProofTree::Yes)
             .1
             .expect("proof tree should have been generated");
-        let mut lock = std::io::stdout().lock();
-        let _ = lock.write_fmt(format_args!("{tree:?}\n"));
-        let _ = lock.flush();
+        print_proof_tree(infcx.tcx, &tree);
     });
 }
+
+pub fn print_proof_tree<'tcx>(tcx: TyCtxt<'tcx>, tree: &inspect::GoalEvaluation<'tcx>) {
+    let mut lock = std::io::stdout().lock();
+    let _ = match tcx.sess.opts.unstable_opts.dump_solver_proof_tree_format {
+        SolverProofTreeFormat::Debug => lock.write_fmt(format_args!("{tree:?}\n")),
+        SolverProofTreeFormat::Stable => lock.write_all(tree.format_stable(tcx).as_bytes()),
+    };
+    let _ = lock.flush();
+}
@@ -1,4 +1,4 @@
-use super::{ObligationCauseCode, PredicateObligation};
+use super::{ObligationCause, ObligationCauseCode, PredicateObligation};
 use crate::infer::error_reporting::TypeErrCtxt;
 use rustc_ast::AttrArgs;
 use rustc_ast::AttrArgsEq;
@@ -11,7 +11,7 @@
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_macros::{extension, LintDiagnostic};
 use rustc_middle::ty::GenericArgsRef;
-use rustc_middle::ty::{self, GenericParamDefKind, TyCtxt};
+use rustc_middle::ty::{self, GenericParamDefKind, ToPolyTraitRef, TyCtxt};
 use rustc_parse_format::{ParseMode, Parser, Piece, Position};
 use rustc_session::lint::builtin::UNKNOWN_OR_MALFORMED_DIAGNOSTIC_ATTRIBUTES;
 use rustc_span::symbol::{kw, sym, Symbol};
@@ -108,6 +108,45 @@ fn describe_enclosure(&self, def_id: LocalDefId) -> Option<&'static str> {
         }
     }
 
+    /// Returns the on-unimplemented note of the closest trait in the derived-cause chain
+    /// of `obligation` which has a custom message, label or notes, starting with
+    /// `trait_ref` itself.
+    ///
+    /// With the new solver, the reported obligation is often a nested where-bound,
+    /// so the most specific note may be attached to any trait along the chain.
+    fn on_unimplemented_note_in_cause_chain(
+        &self,
+        mut trait_ref: ty::PolyTraitRef<'tcx>,
+        obligation: &PredicateObligation<'tcx>,
+        long_ty_file: &mut Option<PathBuf>,
+    ) -> Option<OnUnimplementedNote> {
+        let mut obligation = obligation.clone();
+        loop {
+            let note = self.on_unimplemented_note(trait_ref, &obligation, long_ty_file);
+            if note.message.is_some() || note.label.is_some() || !note.notes.is_empty() {
+                return Some(note);
+            }
+
+            let (parent_code, parent_trait_pred) = {
+                let mut code = obligation.cause.code();
+                loop {
+                    let (parent_code, parent_trait_pred) = code.parent()?;
+                    if let Some(parent_trait_pred) = parent_trait_pred {
+                        break (parent_code.clone(), parent_trait_pred);
+                    }
+                    code = parent_code;
+                }
+            };
+
+            let parent_trait_pred = self.resolve_vars_if_possible(parent_trait_pred);
+            trait_ref = parent_trait_pred.to_poly_trait_ref();
+            let cause =
+                ObligationCause::new(obligation.cause.span, obligation.cause.body_id, parent_code);
+            obligation = obligation.with(self.tcx, parent_trait_pred);
+            obligation.cause = cause;
+        }
+    }
+
     fn on_unimplemented_note(
         &self,
         trait_ref: ty::PolyTraitRef<'tcx>,
@@ -129,7 +168,11 @@ fn on_unimplemented_note(
         match obligation.cause.code() {
             ObligationCauseCode::BuiltinDerivedObligation(..)
             | ObligationCauseCode::ImplDerivedObligation(..)
-            | ObligationCauseCode::WellFormedDerivedObligation(..) => {}
+            | ObligationCauseCode::WellFormedDerivedObligation(..)
+            | ObligationCauseCode::UnsizeTailField(..)
+            | ObligationCauseCode::UnsizeMetadata(..)
+            | ObligationCauseCode::TraitAliasBound(..)
+            | ObligationCauseCode::ObjectCandidateBound(..) => {}
             _ => {
                 // this is a "direct", user-specified, rather than derived,
                 // obligation.
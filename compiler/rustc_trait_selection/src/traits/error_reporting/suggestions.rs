@@ -243,7 +243,7 @@ fn suggest_restricting_param_bound(
         &self,
         err: &mut Diag<'_>,
         trait_pred: ty::PolyTraitPredicate<'tcx>,
-        associated_ty: Option<(&'static str, Ty<'tcx>)>,
+        associated_ty: Option<(&str, Ty<'tcx>)>,
         mut body_id: LocalDefId,
     ) {
         if trait_pred.skip_binder().polarity != ty::PredicatePolarity::Positive {
@@ -2736,7 +2736,9 @@ fn note_obligation_cause_code<G: EmissionGuarantee, T>(
             | ObligationCauseCode::DropImpl
             | ObligationCauseCode::ConstParam(_)
             | ObligationCauseCode::ReferenceOutlivesReferent(..)
-            | ObligationCauseCode::ObjectTypeBound(..) => {}
+            | ObligationCauseCode::ObjectTypeBound(..)
+            | ObligationCauseCode::ClosureSignature(_)
+            | ObligationCauseCode::CoroutineInterior(_) => {}
             ObligationCauseCode::RustCall => {
                 if let Some(pred) = predicate.to_opt_poly_trait_pred()
                     && Some(pred.def_id()) == tcx.lang_items().sized_trait()
@@ -3440,6 +3442,148 @@ fn note_obligation_cause_code<G: EmissionGuarantee, T>(
                     )
                 });
             }
+            ObligationCauseCode::InstantiatedHigherRanked {
+                higher_ranked_predicate,
+                ref parent_code,
+            } => {
+                let higher_ranked_predicate = self.resolve_vars_if_possible(higher_ranked_predicate);
+                err.note(format!(
+                    "required for the higher-ranked bound `{higher_ranked_predicate}` \
+                     to hold for all bound variables",
+                ));
+                // #74711: avoid a stack overflow
+                ensure_sufficient_stack(|| {
+                    self.note_obligation_cause_code(
+                        body_id,
+                        err,
+                        higher_ranked_predicate,
+                        param_env,
+                        parent_code,
+                        obligated_types,
+                        seen_requirements,
+                    )
+                });
+            }
+            ObligationCauseCode::UnsizeTailField(ref data)
+            | ObligationCauseCode::UnsizeMetadata(ref data) => {
+                let parent_trait_ref = self.resolve_vars_if_possible(data.parent_trait_pred);
+                let trait_ref = parent_trait_ref.skip_binder().trait_ref;
+                let (source, target) = (trait_ref.self_ty(), trait_ref.args.type_at(1));
+                let msg = with_forced_trimmed_paths!(format!(
+                    "required for `{source}` to be unsized to `{target}`"
+                ));
+                match (cause_code, source.kind()) {
+                    (ObligationCauseCode::UnsizeTailField(_), ty::Adt(def, _))
+                        if def.is_struct() =>
+                    {
+                        let tail_field = def.non_enum_variant().tail();
+                        err.span_note(
+                            tcx.def_span(tail_field.did),
+                            format!("{msg}, as its field `{}` has to be unsized", tail_field.name),
+                        );
+                    }
+                    (ObligationCauseCode::UnsizeTailField(_), _) => {
+                        err.note(format!("{msg}, as its last field has to be unsized"));
+                    }
+                    _ => {
+                        err.note(msg);
+                    }
+                }
+                let parent_predicate = parent_trait_ref;
+                // #74711: avoid a stack overflow
+                ensure_sufficient_stack(|| {
+                    self.note_obligation_cause_code(
+                        body_id,
+                        err,
+                        parent_predicate,
+                        param_env,
+                        &data.parent_code,
+                        obligated_types,
+                        seen_requirements,
+                    )
+                });
+            }
+            ObligationCauseCode::TraitAliasBound(ref data) => {
+                let parent_trait_ref = self.resolve_vars_if_possible(data.parent_trait_pred);
+                let alias_def_id = parent_trait_ref.def_id();
+                err.span_note(
+                    tcx.def_span(alias_def_id),
+                    format!("required by trait alias `{}`", tcx.def_path_str(alias_def_id)),
+                );
+                let parent_predicate = parent_trait_ref;
+                // #74711: avoid a stack overflow
+                ensure_sufficient_stack(|| {
+                    self.note_obligation_cause_code(
+                        body_id,
+                        err,
+                        parent_predicate,
+                        param_env,
+                        &data.parent_code,
+                        obligated_types,
+                        seen_requirements,
+                    )
+                });
+            }
+            ObligationCauseCode::ObjectCandidateBound(ref data) => {
+                let parent_trait_ref =
+                    self.resolve_vars_if_possible(data.derived.parent_trait_pred);
+                let self_ty = parent_trait_ref.skip_binder().self_ty();
+                err.note(with_forced_trimmed_paths!(format!(
+                    "required for `{self_ty}` to implement `{}`",
+                    parent_trait_ref.print_modifiers_and_trait_path(),
+                )));
+                if let [principal, .., _] = &data.supertrait_path[..] {
+                    let path = data
+                        .supertrait_path
+                        .windows(2)
+                        .map(|pair| {
+                            with_forced_trimmed_paths!(format!(
+                                "`{}: {}`",
+                                pair[0].print_only_trait_path(),
+                                pair[1].print_only_trait_path()
+                            ))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    err.note(with_forced_trimmed_paths!(format!(
+                        "`{self_ty}` implements `{}` as it is a supertrait of `{}`: {path}",
+                        parent_trait_ref.print_modifiers_and_trait_path(),
+                        principal.print_only_trait_path(),
+                    )));
+                }
+                let parent_predicate = parent_trait_ref;
+                // #74711: avoid a stack overflow
+                ensure_sufficient_stack(|| {
+                    self.note_obligation_cause_code(
+                        body_id,
+                        err,
+                        parent_predicate,
+                        param_env,
+                        &data.derived.parent_code,
+                        obligated_types,
+                        seen_requirements,
+                    )
+                });
+            }
+            ObligationCauseCode::CoerceGoal { step, source, target, ref parent_code } => {
+                let (source, target) = self.resolve_vars_if_possible((source, target));
+                err.note(format!(
+                    "cannot {} `{source}` to `{target}` in this coercion",
+                    step.descr()
+                ));
+                // #74711: avoid a stack overflow
+                ensure_sufficient_stack(|| {
+                    self.note_obligation_cause_code(
+                        body_id,
+                        err,
+                        predicate,
+                        param_env,
+                        parent_code,
+                        obligated_types,
+                        seen_requirements,
+                    )
+                });
+            }
             ObligationCauseCode::TypeAlias(ref nested, span, def_id) => {
                 // #74711: avoid a stack overflow
                 ensure_sufficient_stack(|| {
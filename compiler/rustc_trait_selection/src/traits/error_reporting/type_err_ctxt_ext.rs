@@ -9,6 +9,7 @@
 use crate::infer::type_variable::TypeVariableOrigin;
 use crate::infer::InferCtxtExt as _;
 use crate::infer::{self, InferCtxt};
+use crate::solve::{ambiguity_report, deeply_normalize_for_diagnostics};
 use crate::traits::error_reporting::infer_ctxt_ext::InferCtxtExt;
 use crate::traits::error_reporting::{ambiguity, ambiguity::CandidateSource::*};
 use crate::traits::query::evaluate_obligation::InferCtxtExt as _;
@@ -16,8 +17,8 @@
 use crate::traits::NormalizeExt;
 use crate::traits::{
     elaborate, FulfillmentError, FulfillmentErrorCode, MismatchedProjectionTypes, Obligation,
-    ObligationCause, ObligationCauseCode, ObligationCtxt, Overflow, PredicateObligation,
-    SelectionError, SignatureMismatch, TraitNotObjectSafe,
+    ObligationCause, ObligationCauseCode, ObligationCtxt, Overflow, OverflowChain,
+    PredicateObligation, SelectionError, SignatureMismatch, TraitNotObjectSafe,
 };
 use core::ops::ControlFlow;
 use rustc_data_structures::fx::{FxHashMap, FxIndexMap};
@@ -33,8 +34,10 @@
 use rustc_infer::infer::error_reporting::TypeErrCtxt;
 use rustc_infer::infer::{InferOk, TypeTrace};
 use rustc_macros::extension;
+use rustc_middle::error::StashedFulfillmentError;
 use rustc_middle::traits::select::OverflowError;
-use rustc_middle::traits::SignatureMismatchData;
+use rustc_middle::traits::solve::{AmbiguityCause, Goal};
+use rustc_middle::traits::{ObjectCandidateBoundCause, SignatureMismatchData, WellFormedLoc};
 use rustc_middle::ty::abstract_const::NotConstEvaluatable;
 use rustc_middle::ty::error::{ExpectedFound, TypeError};
 use rustc_middle::ty::fold::{BottomUpFolder, TypeFolder, TypeSuperFoldable};
@@ -52,8 +55,9 @@
 use std::fmt;
 use std::iter;
 
+use super::missing_bounds::missing_param_bounds;
 use super::{
-    dump_proof_tree, ArgKind, CandidateSimilarity, FindExprBySpan, FindTypeParam,
+    dump_proof_tree, print_proof_tree, ArgKind, CandidateSimilarity, FindExprBySpan, FindTypeParam,
     GetSafeTransmuteErrorAndReason, HasNumericInferVisitor, ImplCandidate, UnsatisfiedConst,
 };
 
@@ -171,6 +175,31 @@ struct ErrorDescriptor<'tcx> {
             }
         }
 
+        // The fulfillment context may have marked errors as redundant with another
+        // error sharing the same root cause. Suppress them if that error is reported,
+        // either now or by a previous call to this function.
+        for (index, error) in errors.iter().enumerate() {
+            let Some(primary) = error.redundant_with else {
+                continue;
+            };
+            let mut span = error.obligation.cause.span;
+            let expn_data = span.ctxt().outer_expn_data();
+            if let ExpnKind::Desugaring(_) = expn_data.kind {
+                span = expn_data.call_site;
+            }
+            let primary_is_reported = error_map.get(&span).is_some_and(|error_set| {
+                error_set.iter().any(|error2| {
+                    error2.predicate == primary
+                        && error2.index != Some(index)
+                        && error2.index.map_or(true, |index2| !is_suppressed[index2])
+                })
+            });
+            if primary_is_reported {
+                info!("skipping {:?} (redundant with {:?})", error.obligation, primary);
+                is_suppressed[index] = true;
+            }
+        }
+
         let mut reported = None;
 
         for from_expansion in [false, true] {
@@ -201,6 +230,71 @@ struct ErrorDescriptor<'tcx> {
         reported.unwrap_or_else(|| self.dcx().delayed_bug("failed to report fulfillment errors"))
     }
 
+    /// Like [`Self::report_fulfillment_errors`], but stashes the diagnostics for unimplemented
+    /// trait predicates together with their structure instead of emitting them. This lets a
+    /// later, more precise check either enrich or supersede them, see
+    /// `TyCtxt::stash_fulfillment_error`. The stashed diagnostics are built the same way as
+    /// by [`Self::report_selection_error`].
+    ///
+    /// Errors which still mention inference variables, whose span already has a stashed
+    /// fulfillment error, or which are not unimplemented trait predicates are reported
+    /// right away instead.
+    fn stash_fulfillment_errors(&self, errors: Vec<FulfillmentError<'tcx>>) -> ErrorGuaranteed {
+        let tcx = self.tcx;
+        let mut reported = None;
+        let mut unstashed = vec![];
+        for error in errors {
+            let span = error.obligation.cause.span;
+            let predicate =
+                tcx.erase_regions(self.resolve_vars_if_possible(error.obligation.predicate));
+            let root_predicate =
+                tcx.erase_regions(self.resolve_vars_if_possible(error.root_obligation.predicate));
+            let derived_chain: Vec<_> = iter::successors(
+                error.obligation.cause.code().parent(),
+                |&(code, _)| code.parent(),
+            )
+            .filter_map(|(_, parent_trait_pred)| parent_trait_pred)
+            .map(|parent_trait_pred| {
+                tcx.erase_regions(self.resolve_vars_if_possible(parent_trait_pred))
+            })
+            .collect();
+
+            let stashable = match error.code {
+                FulfillmentErrorCode::SelectionError(SelectionError::Unimplemented) => {
+                    predicate.as_trait_clause().is_some()
+                        && !predicate.has_infer()
+                        && !predicate.references_error()
+                        && !root_predicate.has_infer()
+                        && !derived_chain.has_infer()
+                        && !tcx.has_stashed_fulfillment_error(span)
+                }
+                _ => false,
+            };
+            if !stashable {
+                unstashed.push(error);
+                continue;
+            }
+
+            let stashed = StashedFulfillmentError { predicate, root_predicate, derived_chain };
+            let FulfillmentError { obligation, root_obligation, .. } = error;
+            reported = Some(
+                match self.build_selection_error(
+                    obligation,
+                    &root_obligation,
+                    &SelectionError::Unimplemented,
+                ) {
+                    Ok(err) => tcx.stash_fulfillment_error(span, stashed, err),
+                    Err(guar) => guar,
+                },
+            );
+        }
+
+        if !unstashed.is_empty() {
+            reported = Some(self.report_fulfillment_errors(unstashed));
+        }
+        reported.unwrap_or_else(|| self.dcx().delayed_bug("failed to stash fulfillment errors"))
+    }
+
     /// Reports that an overflow has occurred and halts compilation. We
     /// halt compilation unconditionally because it is important that
     /// overflows never be masked -- they basically represent computations
@@ -344,6 +438,7 @@ fn report_overflow_no_abort(
         &self,
         obligation: PredicateObligation<'tcx>,
         suggest_increasing_limit: bool,
+        overflow_chain: Option<&OverflowChain<'tcx>>,
     ) -> ErrorGuaranteed {
         let obligation = self.resolve_vars_if_possible(obligation);
         let mut err = self.build_overflow_error(
@@ -351,6 +446,39 @@ fn report_overflow_no_abort(
             obligation.cause.span,
             suggest_increasing_limit,
         );
+        if let Some(chain) = overflow_chain
+            && chain.goals.len() > 1
+        {
+            let mut msg = "the overflow was caused by this chain of requirements:".to_owned();
+            for goal in &chain.goals {
+                let goal = self.resolve_vars_if_possible(*goal);
+                msg.push_str(&format!("\n    `{goal}`"));
+            }
+            if chain.truncated {
+                msg.push_str("\n    ...");
+            }
+            err.note(msg);
+        }
+        self.note_obligation_cause(&mut err, &obligation);
+        self.point_at_returns_when_relevant(&mut err, &obligation);
+        err.emit()
+    }
+
+    /// Reports that the new solver gave up on `obligation` as proving it required
+    /// computing more goals than allowed by `-Zsolver-step-limit`.
+    fn report_step_limit_overflow(&self, obligation: PredicateObligation<'tcx>) -> ErrorGuaranteed {
+        let obligation = self.resolve_vars_if_possible(obligation);
+        let mut err = struct_span_code_err!(
+            self.dcx(),
+            obligation.cause.span,
+            E0275,
+            "the requirement `{}` is too complex to solve",
+            obligation.predicate,
+        );
+        if let Some(step_limit) = self.tcx.sess.opts.unstable_opts.solver_step_limit {
+            err.note(format!("proving it required computing more than {step_limit} goals"));
+        }
+        err.help("consider increasing the limit with `-Zsolver-step-limit`");
         self.note_obligation_cause(&mut err, &obligation);
         self.point_at_returns_when_relevant(&mut err, &obligation);
         err.emit()
@@ -361,10 +489,25 @@ fn report_overflow_no_abort(
     /// then it should be the same as `obligation`.
     fn report_selection_error(
         &self,
-        mut obligation: PredicateObligation<'tcx>,
+        obligation: PredicateObligation<'tcx>,
         root_obligation: &PredicateObligation<'tcx>,
         error: &SelectionError<'tcx>,
     ) -> ErrorGuaranteed {
+        match self.build_selection_error(obligation, root_obligation, error) {
+            Ok(err) => err.emit(),
+            Err(guar) => guar,
+        }
+    }
+
+    /// Builds the diagnostic reported by [`Self::report_selection_error`] without
+    /// emitting it. Returns an `ErrorGuaranteed` instead if an error has already
+    /// been reported for `obligation`, or if it has been reported in another way.
+    fn build_selection_error(
+        &self,
+        mut obligation: PredicateObligation<'tcx>,
+        root_obligation: &PredicateObligation<'tcx>,
+        error: &SelectionError<'tcx>,
+    ) -> Result<Diag<'tcx>, ErrorGuaranteed> {
         let tcx = self.tcx;
 
         if tcx.sess.opts.unstable_opts.next_solver.map(|c| c.dump_tree).unwrap_or_default()
@@ -376,7 +519,9 @@ fn report_selection_error(
         let mut span = obligation.cause.span;
 
         let mut err = match *error {
-            SelectionError::Unimplemented => {
+            SelectionError::Unimplemented
+            | SelectionError::ImplObligationFailed { .. }
+            | SelectionError::ExplicitNegativeImpl(_) => {
                 // If this obligation was generated as a result of well-formedness checking, see if we
                 // can get a better error message by performing HIR-based well-formedness checking.
                 if let ObligationCauseCode::WellFormed(Some(wf_loc)) =
@@ -399,19 +544,18 @@ fn report_selection_error(
                 } = *obligation.cause.code()
                 {
                     debug!("ObligationCauseCode::CompareImplItemObligation");
-                    return self.report_extra_impl_obligation(
+                    return Ok(self.report_extra_impl_obligation(
                         span,
                         impl_item_def_id,
                         trait_item_def_id,
                         &format!("`{}`", obligation.predicate),
-                    )
-                    .emit()
+                    ));
                 }
 
                 // Report a const-param specific error
                 if let ObligationCauseCode::ConstParam(ty) = *obligation.cause.code().peel_derives()
                 {
-                    return self.report_const_param_not_wf(ty, &obligation).emit();
+                    return Ok(self.report_const_param_not_wf(ty, &obligation));
                 }
 
                 let bound_predicate = obligation.predicate.kind();
@@ -472,7 +616,7 @@ fn report_selection_error(
                             &obligation,
                             trait_ref,
                         ) {
-                            return guar;
+                            return Err(guar);
                         }
 
                         // FIXME(effects)
@@ -480,12 +624,12 @@ fn report_selection_error(
 
                         if let Err(guar) = trait_predicate.error_reported()
                         {
-                            return guar;
+                            return Err(guar);
                         }
                         // Silence redundant errors on binding acccess that are already
                         // reported on the binding definition (#56607).
                         if let Err(guar) = self.fn_arg_obligation(&obligation) {
-                            return guar;
+                            return Err(guar);
                         }
                         let mut file = None;
                         let (post_message, pre_message, type_def) = self
@@ -512,7 +656,20 @@ fn report_selection_error(
                             notes,
                             parent_label,
                             append_const_msg,
-                        } = self.on_unimplemented_note(trait_ref, o, &mut long_ty_file);
+                        } = if self.next_trait_solver() {
+                            // The new solver reports nested where-bounds, so prefer the most
+                            // specific note along the cause chain of the leaf obligation.
+                            self.on_unimplemented_note_in_cause_chain(
+                                trait_predicate.to_poly_trait_ref(),
+                                &obligation,
+                                &mut long_ty_file,
+                            )
+                            .unwrap_or_else(|| {
+                                self.on_unimplemented_note(trait_ref, o, &mut long_ty_file)
+                            })
+                        } else {
+                            self.on_unimplemented_note(trait_ref, o, &mut long_ty_file)
+                        };
                         let have_alt_message = message.is_some() || label.is_some();
                         let is_try_conversion = self.is_try_conversion(span, trait_ref.def_id());
                         let is_unsize =
@@ -552,9 +709,9 @@ fn report_selection_error(
                                 span,
                             ) {
                                 GetSafeTransmuteErrorAndReason::Silent => {
-                                    return self.dcx().span_delayed_bug(
+                                    return Err(self.dcx().span_delayed_bug(
                                         span, "silent safe transmute error"
-                                    );
+                                    ));
                                 }
                                 GetSafeTransmuteErrorAndReason::Error {
                                     err_msg,
@@ -623,7 +780,7 @@ fn report_selection_error(
                             have_alt_message,
                         ) {
                             self.note_obligation_cause(&mut err, &obligation);
-                            return err.emit();
+                            return Ok(err);
                         }
 
                         file_note.map(|note| err.note(note));
@@ -724,11 +881,11 @@ fn report_selection_error(
                         }
 
                         if self.suggest_add_clone_to_arg(&obligation, &mut err, trait_predicate) {
-                            return err.emit();
+                            return Ok(err);
                         }
 
                         if self.suggest_impl_trait(&mut err, &obligation, trait_predicate) {
-                            return err.emit();
+                            return Ok(err);
                         }
 
                         if is_unsize {
@@ -818,7 +975,7 @@ fn report_selection_error(
                                 Some(sym::Debug | sym::Display)
                             )
                         {
-                            return err.emit();
+                            return Ok(err);
                         }
 
 
@@ -956,14 +1113,35 @@ fn report_selection_error(
                     expected_trait_ref,
                 ) {
                     Ok(err) => err,
-                    Err(guar) => return guar,
+                    Err(guar) => return Err(guar),
                 }
             }
 
-            SelectionError::OpaqueTypeAutoTraitLeakageUnknown(def_id) => return self.report_opaque_type_auto_trait_leakage(
+            SelectionError::OpaqueTypeAutoTraitLeakageUnknown(def_id) => return Err(self.report_opaque_type_auto_trait_leakage(
                 &obligation,
                 def_id,
-            ),
+            )),
+
+            SelectionError::ConstArgHasWrongType { ct, evaluated, expected_ty } => {
+                let msg = match evaluated {
+                    Some(evaluated) => format!(
+                        "the constant `{ct}` evaluates to `{evaluated}`, \
+                        but a value of type `{expected_ty}` is required"
+                    ),
+                    None => format!("the constant `{ct}` is not of type `{expected_ty}`"),
+                };
+                let mut diag = self.dcx().struct_span_err(span, msg);
+                self.note_type_err(
+                    &mut diag,
+                    &obligation.cause,
+                    None,
+                    None,
+                    TypeError::Sorts(ty::error::ExpectedFound::new(true, expected_ty, ct.ty())),
+                    false,
+                    false,
+                );
+                diag
+            }
 
             TraitNotObjectSafe(did) => {
                 let violations = self.tcx.object_safety_violations(did);
@@ -978,7 +1156,7 @@ fn report_selection_error(
             SelectionError::NotConstEvaluatable(NotConstEvaluatable::MentionsParam) => {
                 match self.report_not_const_evaluatable_error(&obligation, span) {
                     Ok(err) => err,
-                    Err(guar) => return guar,
+                    Err(guar) => return Err(guar),
                 }
             }
 
@@ -987,7 +1165,7 @@ fn report_selection_error(
             // Already reported.
             Overflow(OverflowError::Error(guar)) => {
                 self.set_tainted_by_errors(guar);
-                return guar
+                return Err(guar)
             },
 
             Overflow(_) => {
@@ -995,9 +1173,68 @@ fn report_selection_error(
             }
         };
 
-        self.note_obligation_cause(&mut err, &obligation);
+        if let SelectionError::ExplicitNegativeImpl(impl_def_id) = *error {
+            err.span_note(
+                self.tcx.def_span(impl_def_id),
+                "the trait is explicitly not implemented by this negative impl",
+            );
+        }
+
+        // With the new solver, we descend into the nested goals of `WellFormed` goals
+        // and report the failing where-bound. Mention which field it is required for
+        // when noting the bound instead of only noting the bound itself.
+        let wf_field_bound = if self.next_trait_solver()
+            && let ObligationCauseCode::WellFormed(Some(WellFormedLoc::Ty(field_def_id))) =
+                *root_obligation.cause.code().peel_derives()
+            && self.tcx.def_kind(field_def_id) == DefKind::Field
+        {
+            match *obligation.cause.code() {
+                ObligationCauseCode::BindingObligation(item_def_id, span) => {
+                    Some((item_def_id, Some(span), field_def_id))
+                }
+                ObligationCauseCode::ItemObligation(item_def_id) => {
+                    Some((item_def_id, None, field_def_id))
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        if matches!(
+            error,
+            SelectionError::Unimplemented | SelectionError::ImplObligationFailed { .. }
+        ) {
+            self.suggest_missing_param_bounds_from_proof_tree(
+                &mut err,
+                root_obligation,
+                Some(obligation.predicate),
+            );
+        }
+
+        if let Some((item_def_id, bound_span, field_def_id)) = wf_field_bound {
+            let item_name = with_forced_trimmed_paths!(self.tcx.def_path_str(item_def_id));
+            let msg = format!(
+                "required by a bound in `{item_name}` for the type of the field `{}` \
+                to be well-formed",
+                self.tcx.item_name(field_def_id.to_def_id()),
+            );
+            match bound_span {
+                Some(span) => {
+                    let mut multispan = MultiSpan::from(span);
+                    multispan
+                        .push_span_label(span, format!("required by this bound in `{item_name}`"));
+                    err.span_note(multispan, msg);
+                }
+                None => {
+                    err.span_note(self.tcx.def_span(item_def_id), msg);
+                }
+            }
+        } else {
+            self.note_obligation_cause(&mut err, &obligation);
+        }
         self.point_at_returns_when_relevant(&mut err, &obligation);
-        err.emit()
+        Ok(err)
     }
 
     fn emit_specialized_closure_kind_error(
@@ -1436,6 +1673,41 @@ fn report_const_param_not_wf(
 
 #[extension(pub(super) trait InferCtxtPrivExt<'tcx>)]
 impl<'tcx> TypeErrCtxt<'_, 'tcx> {
+    /// With the new solver, walk the proof tree of the failing `obligation` and suggest
+    /// adding the bounds on generic parameters whose absence caused it to fail. The
+    /// `reported` predicate has already been handled by the caller and is skipped.
+    fn suggest_missing_param_bounds_from_proof_tree(
+        &self,
+        err: &mut Diag<'_>,
+        obligation: &PredicateObligation<'tcx>,
+        reported: Option<ty::Predicate<'tcx>>,
+    ) {
+        if !self.next_trait_solver() || obligation.predicate.has_escaping_bound_vars() {
+            return;
+        }
+
+        let reported = reported.map(|pred| self.resolve_vars_if_possible(pred));
+        self.probe(|_| {
+            let goal = Goal::new(self.tcx, obligation.param_env, obligation.predicate);
+            for bound in missing_param_bounds(self, goal, obligation.cause.span) {
+                if bound.assoc_ty.is_none()
+                    && reported.is_some_and(|pred| {
+                        pred.to_opt_poly_trait_pred() == Some(bound.trait_pred)
+                    })
+                {
+                    continue;
+                }
+
+                self.suggest_restricting_param_bound(
+                    err,
+                    bound.trait_pred,
+                    bound.assoc_ty.map(|(name, ty)| (name.as_str(), ty)),
+                    obligation.cause.body_id,
+                );
+            }
+        });
+    }
+
     fn can_match_trait(
         &self,
         goal: ty::TraitPredicate<'tcx>,
@@ -1501,7 +1773,14 @@ fn report_fulfillment_error(&self, error: &FulfillmentError<'tcx>) -> ErrorGuara
         if self.tcx.sess.opts.unstable_opts.next_solver.map(|c| c.dump_tree).unwrap_or_default()
             == DumpSolverProofTree::OnError
         {
-            dump_proof_tree(&error.root_obligation, self.infcx);
+            match &error.proof_tree {
+                Some(proof_tree) => print_proof_tree(self.tcx, proof_tree),
+                None => dump_proof_tree(&error.root_obligation, self.infcx),
+            }
+        }
+
+        if ambiguity_report::is_enabled(self.tcx) {
+            ambiguity_report::emit_ambiguity_report(self.infcx, error);
         }
 
         match error.code {
@@ -1515,19 +1794,50 @@ fn report_fulfillment_error(&self, error: &FulfillmentError<'tcx>) -> ErrorGuara
                 self.report_projection_error(&error.obligation, e)
             }
             FulfillmentErrorCode::Ambiguity { overflow: None } => {
-                self.maybe_report_ambiguity(&error.obligation)
+                self.maybe_report_ambiguity(
+                    &error.obligation,
+                    &error.blocking_infer_vars,
+                    error.ambiguity_cause,
+                )
+            }
+            FulfillmentErrorCode::Ambiguity { overflow: Some(_) } if error.reached_step_limit => {
+                self.report_step_limit_overflow(error.obligation.clone())
             }
             FulfillmentErrorCode::Ambiguity { overflow: Some(suggest_increasing_limit) } => {
-                self.report_overflow_no_abort(error.obligation.clone(), suggest_increasing_limit)
+                self.report_overflow_no_abort(
+                    error.obligation.clone(),
+                    suggest_increasing_limit,
+                    error.overflow_chain.as_ref(),
+                )
             }
-            FulfillmentErrorCode::SubtypeError(ref expected_found, ref err) => self
-                .report_mismatched_types(
-                    &error.obligation.cause,
+            FulfillmentErrorCode::SubtypeError(ref expected_found, ref err) => {
+                // Report failing `Coerce` goals using the cause of the coercion itself,
+                // only mentioning the adjustment it required.
+                let mut cause = error.obligation.cause.clone();
+                let mut coercion_step = None;
+                if let ObligationCauseCode::CoerceGoal { step, source, target, ref parent_code } =
+                    *error.obligation.cause.code()
+                {
+                    coercion_step = Some((step, source, target));
+                    let parent_code = (**parent_code).clone();
+                    cause = ObligationCause::new(cause.span, cause.body_id, parent_code);
+                }
+                let mut diag = self.report_mismatched_types(
+                    &cause,
                     expected_found.expected,
                     expected_found.found,
                     *err,
-                )
-                .emit(),
+                );
+                if let Some((step, source, target)) = coercion_step {
+                    let (source, target) = self.resolve_vars_if_possible((source, target));
+                    let step = step.descr();
+                    diag.span_label(
+                        cause.span,
+                        format!("cannot {step} `{source}` to `{target}` in this coercion"),
+                    );
+                }
+                diag.emit()
+            }
             FulfillmentErrorCode::ConstEquateError(ref expected_found, ref err) => {
                 let mut diag = self.report_mismatched_consts(
                     &error.obligation.cause,
@@ -1732,11 +2042,47 @@ fn report_projection_error(
                 true,
                 false,
             );
+            self.note_alias_relate_normalization(&mut diag, obligation.param_env, predicate);
+            self.suggest_missing_param_bounds_from_proof_tree(&mut diag, obligation, None);
             self.note_obligation_cause(&mut diag, obligation);
             diag.emit()
         })
     }
 
+    /// For failing `AliasRelate` goals, notes both sides of the goal together with
+    /// the terms they normalize to, as the mismatch may only become apparent
+    /// after normalization.
+    fn note_alias_relate_normalization(
+        &self,
+        diag: &mut Diag<'_>,
+        param_env: ty::ParamEnv<'tcx>,
+        predicate: ty::Predicate<'tcx>,
+    ) {
+        let Some(ty::PredicateKind::AliasRelate(lhs, rhs, _)) = predicate.kind().no_bound_vars()
+        else {
+            return;
+        };
+
+        let describe = |term: ty::Term<'tcx>| {
+            if term.to_alias_ty(self.tcx).is_none() {
+                return format!("`{term}`");
+            }
+
+            let normalized = deeply_normalize_for_diagnostics(self, param_env, term);
+            if normalized == term {
+                format!("`{term}` (could not be normalized)")
+            } else {
+                format!("`{term}` (normalized to `{normalized}`)")
+            }
+        };
+
+        with_forced_trimmed_paths!(diag.note(format!(
+            "{} is incompatible with {}",
+            describe(lhs),
+            describe(rhs)
+        )));
+    }
+
     fn maybe_detailed_projection_msg(
         &self,
         pred: ty::ProjectionPredicate<'tcx>,
@@ -2213,7 +2559,11 @@ fn get_parent_trait_ref(
         code: &ObligationCauseCode<'tcx>,
     ) -> Option<(Ty<'tcx>, Option<Span>)> {
         match code {
-            ObligationCauseCode::BuiltinDerivedObligation(data) => {
+            ObligationCauseCode::BuiltinDerivedObligation(data)
+            | ObligationCauseCode::ObjectCandidateBound(box ObjectCandidateBoundCause {
+                derived: data,
+                ..
+            }) => {
                 let parent_trait_ref = self.resolve_vars_if_possible(data.parent_trait_pred);
                 match self.get_parent_trait_ref(&data.parent_code) {
                     Some(t) => Some(t),
@@ -2300,7 +2650,12 @@ fn mk_trait_obligation_with_new_self_ty(
     }
 
     #[instrument(skip(self), level = "debug")]
-    fn maybe_report_ambiguity(&self, obligation: &PredicateObligation<'tcx>) -> ErrorGuaranteed {
+    fn maybe_report_ambiguity(
+        &self,
+        obligation: &PredicateObligation<'tcx>,
+        blocking_infer_vars: &[Ty<'tcx>],
+        ambiguity_cause: Option<AmbiguityCause>,
+    ) -> ErrorGuaranteed {
         // Unable to successfully determine, probably means
         // insufficient type information, but could mean
         // ambiguous impls. The latter *ought* to be a
@@ -2373,8 +2728,14 @@ fn maybe_report_ambiguity(&self, obligation: &PredicateObligation<'tcx>) -> Erro
 
                 // Pick the first generic parameter that still contains inference variables as the one
                 // we're going to emit an error for. If there are none (see above), fall back to
-                // a more general error.
-                let arg = data.trait_ref.args.iter().find(|s| s.has_non_region_infer());
+                // a more general error. With the new solver, we prefer the inference variables which
+                // actually block this obligation from making progress.
+                let arg = blocking_infer_vars
+                    .iter()
+                    .map(|&ty| self.resolve_vars_if_possible(ty))
+                    .find(|ty| ty.is_ty_var())
+                    .map(Into::into)
+                    .or_else(|| data.trait_ref.args.iter().find(|s| s.has_non_region_infer()));
 
                 let mut err = if let Some(arg) = arg {
                     self.emit_inference_failure_err(
@@ -2703,6 +3064,15 @@ fn maybe_report_ambiguity(&self, obligation: &PredicateObligation<'tcx>) -> Erro
                 .with_span_label(span, format!("cannot satisfy `{predicate}`"))
             }
         };
+        // Explain why a reservation impl does not apply, as it looks like a normal impl.
+        if let Some(AmbiguityCause::ReservationImpl(impl_def_id)) = ambiguity_cause
+            && let Some(message) = self
+                .tcx
+                .get_attr(impl_def_id, sym::rustc_reservation_impl)
+                .and_then(|a| a.value_str())
+        {
+            err.span_note(self.tcx.def_span(impl_def_id), message.to_string());
+        }
         self.note_obligation_cause(&mut err, obligation);
         err.emit()
     }
@@ -144,6 +144,16 @@ fn collect_remaining_errors(
             .collect()
     }
 
+    fn peek_remaining_errors(&self, _infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
+        self.pending_obligations()
+            .into_iter()
+            .map(|obligation| {
+                let code = FulfillmentErrorCode::Ambiguity { overflow: None };
+                FulfillmentError::new(obligation.clone(), code, obligation)
+            })
+            .collect()
+    }
+
     fn select_where_possible(&mut self, infcx: &InferCtxt<'tcx>) -> Vec<FulfillmentError<'tcx>> {
         let selcx = SelectionContext::new(infcx);
         self.select(selcx)
@@ -546,6 +546,7 @@ fn visit_const(&mut self, ct: ty::Const<'tcx>) -> Self::Result {
 pub fn provide(providers: &mut Providers) {
     object_safety::provide(providers);
     vtable::provide(providers);
+    crate::solve::provide(providers);
     *providers = Providers {
         specialization_graph_of: specialize::specialization_graph_provider,
         specializes: specialize::specializes,
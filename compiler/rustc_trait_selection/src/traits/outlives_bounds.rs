@@ -1,4 +1,7 @@
 use crate::infer::InferCtxt;
+use crate::traits::query::type_op::implied_outlives_bounds::{
+    compute_implied_outlives_bounds_compat_inner, compute_implied_outlives_bounds_inner,
+};
 use crate::traits::{ObligationCause, ObligationCtxt};
 use rustc_data_structures::fx::FxIndexSet;
 use rustc_infer::infer::resolve::OpportunisticRegionResolver;
@@ -51,6 +54,13 @@ fn implied_outlives_bounds<'a, 'tcx>(
     // an example.
     assert!(!ty.has_non_region_infer());
 
+    // With the new solver, we compute the implied bounds locally instead of going
+    // through the canonical query, which uses the old solver. This keeps the implied
+    // bounds consistent with the rest of type checking.
+    if infcx.next_trait_solver() {
+        return implied_outlives_bounds_with_next_solver(infcx, param_env, body_id, ty, compat);
+    }
+
     let mut canonical_var_values = OriginalQueryValues::default();
     let canonical_ty = infcx.canonicalize_query(param_env.and(ty), &mut canonical_var_values);
     let implied_bounds_result = if compat {
@@ -111,6 +121,43 @@ fn implied_outlives_bounds<'a, 'tcx>(
     bounds
 }
 
+/// Computes the implied bounds of `ty` in the current inference context using
+/// the new solver. Any region obligations from normalization are registered in
+/// `infcx` with `body_id`, same as when instantiating the query response.
+fn implied_outlives_bounds_with_next_solver<'tcx>(
+    infcx: &InferCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body_id: LocalDefId,
+    ty: Ty<'tcx>,
+    compat: bool,
+) -> Vec<OutlivesBound<'tcx>> {
+    let span = infcx.tcx.def_span(body_id);
+    let ocx = ObligationCtxt::new(infcx);
+    let result = if compat {
+        compute_implied_outlives_bounds_compat_inner(&ocx, param_env, ty)
+    } else {
+        compute_implied_outlives_bounds_inner(&ocx, param_env, ty)
+    };
+    let Ok(bounds) = result else {
+        return vec![];
+    };
+
+    let errors = ocx.select_all_or_error();
+    if !errors.is_empty() {
+        infcx.dcx().span_delayed_bug(
+            span,
+            format!("implied_outlives_bounds failed to solve obligations: {errors:?}"),
+        );
+        return vec![];
+    }
+
+    let mut bounds = infcx.resolve_vars_if_possible(bounds);
+    // Because of #109628, we may have unexpected placeholders. Ignore them!
+    // FIXME(#109628): panic in this case once the issue is fixed.
+    bounds.retain(|bound| !bound.has_placeholders());
+    bounds
+}
+
 #[extension(pub trait InferCtxtExt<'a, 'tcx>)]
 impl<'a, 'tcx: 'a> InferCtxt<'tcx> {
     /// Do *NOT* call this directly.
@@ -1,10 +1,10 @@
 use rustc_macros::extension;
+use rustc_middle::traits::solve::{Certainty, Goal, NoSolution};
 
 use crate::infer::canonical::OriginalQueryValues;
 use crate::infer::InferCtxt;
-use crate::traits::{
-    EvaluationResult, ObligationCtxt, OverflowError, PredicateObligation, SelectionContext,
-};
+use crate::solve::{GenerateProofTree, InferCtxtEvalExt};
+use crate::traits::{EvaluationResult, OverflowError, PredicateObligation, SelectionContext};
 
 #[extension(pub trait InferCtxtExt<'tcx>)]
 impl<'tcx> InferCtxt<'tcx> {
@@ -67,24 +67,7 @@ fn evaluate_obligation(
         let param_env = obligation.param_env;
 
         if self.next_trait_solver() {
-            self.probe(|snapshot| {
-                let ocx = ObligationCtxt::new(self);
-                ocx.register_obligation(obligation.clone());
-                let mut result = EvaluationResult::EvaluatedToOk;
-                for error in ocx.select_all_or_error() {
-                    if error.is_true_error() {
-                        return Ok(EvaluationResult::EvaluatedToErr);
-                    } else {
-                        result = result.max(EvaluationResult::EvaluatedToAmbig);
-                    }
-                }
-                if self.opaque_types_added_in_snapshot(snapshot) {
-                    result = result.max(EvaluationResult::EvaluatedToOkModuloOpaqueTypes);
-                } else if self.region_constraints_added_in_snapshot(snapshot) {
-                    result = result.max(EvaluationResult::EvaluatedToOkModuloRegions);
-                }
-                Ok(result)
-            })
+            Ok(self.evaluate_obligation_in_new_solver(obligation))
         } else {
             assert!(!self.intercrate);
             let c_pred =
@@ -93,6 +76,34 @@ fn evaluate_obligation(
         }
     }
 
+    /// Evaluates `obligation` as a root goal in the new solver. This only
+    /// canonicalizes the goal and uses the global cache of the solver, so
+    /// unlike using an `ObligationCtxt` it does not have to track nested
+    /// obligations or build a proof tree.
+    ///
+    /// Overflow is treated as ambiguity by the new solver, so this never
+    /// returns an `OverflowError`.
+    fn evaluate_obligation_in_new_solver(
+        &self,
+        obligation: &PredicateObligation<'tcx>,
+    ) -> EvaluationResult {
+        assert!(self.next_trait_solver());
+        self.probe(|snapshot| {
+            let goal = Goal::new(self.tcx, obligation.param_env, obligation.predicate);
+            let mut result = match self.evaluate_root_goal(goal, GenerateProofTree::Never).0 {
+                Ok((_, Certainty::Yes)) => EvaluationResult::EvaluatedToOk,
+                Ok((_, Certainty::Maybe(_))) => EvaluationResult::EvaluatedToAmbig,
+                Err(NoSolution) => return EvaluationResult::EvaluatedToErr,
+            };
+            if self.opaque_types_added_in_snapshot(snapshot) {
+                result = result.max(EvaluationResult::EvaluatedToOkModuloOpaqueTypes);
+            } else if self.region_constraints_added_in_snapshot(snapshot) {
+                result = result.max(EvaluationResult::EvaluatedToOkModuloRegions);
+            }
+            result
+        })
+    }
+
     /// Helper function that canonicalizes and runs the query. If an
     /// overflow results, we re-run it in the local context so we can
     /// report a nice error.
@@ -74,7 +74,13 @@ pub fn compute_implied_outlives_bounds_inner<'tcx>(
     ty: Ty<'tcx>,
 ) -> Result<Vec<OutlivesBound<'tcx>>, NoSolution> {
     let normalize_op = |ty| {
-        let ty = ocx.normalize(&ObligationCause::dummy(), param_env, ty);
+        // Need to manually normalize in the new solver as `ocx.normalize` does not.
+        let ty = if ocx.infcx.next_trait_solver() {
+            solve::deeply_normalize(ocx.infcx.at(&ObligationCause::dummy(), param_env), ty)
+                .map_err(|_errs| NoSolution)?
+        } else {
+            ocx.normalize(&ObligationCause::dummy(), param_env, ty)
+        };
         if !ocx.select_all_or_error().is_empty() {
             return Err(NoSolution);
         }
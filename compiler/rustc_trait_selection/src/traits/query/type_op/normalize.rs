@@ -29,8 +29,11 @@ fn perform_locally_with_next_solver(
         ocx: &ObligationCtxt<'_, 'tcx>,
         key: ParamEnvAnd<'tcx, Self>,
     ) -> Result<Self::QueryResponse, NoSolution> {
-        // FIXME(-Znext-solver): shouldn't be using old normalizer
-        Ok(ocx.normalize(&ObligationCause::dummy(), key.param_env, key.value.value))
+        // `ocx.normalize` does not normalize anything in the new solver, so we
+        // have to deeply normalize here. Otherwise borrowck would end up using
+        // unnormalized types, unlike typeck.
+        ocx.deeply_normalize(&ObligationCause::dummy(), key.param_env, key.value.value)
+            .map_err(|_| NoSolution)
     }
 }
 
@@ -1,7 +1,11 @@
 use crate::errors::DumpVTableEntries;
-use crate::traits::{impossible_predicates, is_vtable_safe_method};
+use crate::traits::{
+    impossible_predicates, is_vtable_safe_method, supertraits, Obligation, ObligationCause,
+    SelectionContext,
+};
 use rustc_hir::def_id::DefId;
 use rustc_hir::lang_items::LangItem;
+use rustc_infer::infer::TyCtxtInferExt;
 use rustc_infer::traits::util::PredicateSet;
 use rustc_infer::traits::ImplSource;
 use rustc_middle::query::Providers;
@@ -13,6 +17,7 @@
 use smallvec::{smallvec, SmallVec};
 
 use std::fmt::Debug;
+use std::iter;
 use std::ops::ControlFlow;
 
 #[derive(Clone, Debug)]
@@ -240,6 +245,36 @@ fn vtable_entries<'tcx>(
 ) -> &'tcx [VtblEntry<'tcx>] {
     debug!("vtable_entries({:?})", trait_ref);
 
+    let entries = if tcx.next_trait_solver_globally() {
+        let entries = new_solver_vtable_entries(tcx, trait_ref);
+        if tcx.sess.opts.unstable_opts.verify_next_solver_vtables {
+            let old_entries = vtable_entries_from_segments(tcx, trait_ref);
+            if entries != old_entries {
+                bug!(
+                    "vtable mismatch for `{trait_ref}`: new solver computed {entries:#?}, \
+                    old solver computed {old_entries:#?}"
+                );
+            }
+        }
+        entries
+    } else {
+        vtable_entries_from_segments(tcx, trait_ref)
+    };
+
+    if tcx.has_attr(trait_ref.def_id(), sym::rustc_dump_vtable) {
+        let sp = tcx.def_span(trait_ref.def_id());
+        dump_vtable_entries(tcx, sp, trait_ref, &entries);
+    }
+
+    tcx.arena.alloc_from_iter(entries)
+}
+
+/// Computes the vtable entries of `trait_ref` by walking its supertraits,
+/// see [`prepare_vtable_segments`].
+fn vtable_entries_from_segments<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_ref: ty::PolyTraitRef<'tcx>,
+) -> Vec<VtblEntry<'tcx>> {
     let mut entries = vec![];
 
     let vtable_segment_callback = |segment| -> ControlFlow<()> {
@@ -248,57 +283,7 @@ fn vtable_entries<'tcx>(
                 entries.extend(TyCtxt::COMMON_VTABLE_ENTRIES);
             }
             VtblSegment::TraitOwnEntries { trait_ref, emit_vptr } => {
-                let existential_trait_ref = trait_ref
-                    .map_bound(|trait_ref| ty::ExistentialTraitRef::erase_self_ty(tcx, trait_ref));
-
-                // Lookup the shape of vtable for the trait.
-                let own_existential_entries =
-                    tcx.own_existential_vtable_entries(existential_trait_ref.def_id());
-
-                let own_entries = own_existential_entries.iter().copied().map(|def_id| {
-                    debug!("vtable_entries: trait_method={:?}", def_id);
-
-                    // The method may have some early-bound lifetimes; add regions for those.
-                    let args = trait_ref.map_bound(|trait_ref| {
-                        GenericArgs::for_item(tcx, def_id, |param, _| match param.kind {
-                            GenericParamDefKind::Lifetime => tcx.lifetimes.re_erased.into(),
-                            GenericParamDefKind::Type { .. }
-                            | GenericParamDefKind::Const { .. } => {
-                                trait_ref.args[param.index as usize]
-                            }
-                        })
-                    });
-
-                    // The trait type may have higher-ranked lifetimes in it;
-                    // erase them if they appear, so that we get the type
-                    // at some particular call site.
-                    let args =
-                        tcx.normalize_erasing_late_bound_regions(ty::ParamEnv::reveal_all(), args);
-
-                    // It's possible that the method relies on where-clauses that
-                    // do not hold for this particular set of type parameters.
-                    // Note that this method could then never be called, so we
-                    // do not want to try and codegen it, in that case (see #23435).
-                    let predicates = tcx.predicates_of(def_id).instantiate_own(tcx, args);
-                    if impossible_predicates(
-                        tcx,
-                        predicates.map(|(predicate, _)| predicate).collect(),
-                    ) {
-                        debug!("vtable_entries: predicates do not hold");
-                        return VtblEntry::Vacant;
-                    }
-
-                    let instance = ty::Instance::resolve_for_vtable(
-                        tcx,
-                        ty::ParamEnv::reveal_all(),
-                        def_id,
-                        args,
-                    )
-                    .expect("resolution failed during building vtable representation");
-                    VtblEntry::Method(instance)
-                });
-
-                entries.extend(own_entries);
+                entries.extend(own_vtable_entries(tcx, trait_ref));
 
                 if emit_vptr {
                     entries.push(VtblEntry::TraitVPtr(trait_ref));
@@ -311,12 +296,179 @@ fn vtable_entries<'tcx>(
 
     let _ = prepare_vtable_segments(tcx, trait_ref, vtable_segment_callback);
 
-    if tcx.has_attr(trait_ref.def_id(), sym::rustc_dump_vtable) {
-        let sp = tcx.def_span(trait_ref.def_id());
-        dump_vtable_entries(tcx, sp, trait_ref, &entries);
+    entries
+}
+
+/// Computes the vtable entries of `trait_ref` from the goals evaluated by the new solver,
+/// which is used with `-Znext-solver=globally`.
+///
+/// The own entries of each supertrait start at the `vtable_base` of the object candidate
+/// used to prove `dyn Trait: Supertrait`, and its vptr is stored in the `vtable_vptr_slot`
+/// of the trait upcasting candidate used to prove `dyn Trait: Unsize<dyn Supertrait>`.
+/// This way, the vtable is guaranteed to match the layout assumed by method calls on and
+/// upcasting coercions of trait objects.
+///
+/// Auto traits don't have any methods and we never upcast to them, so their vptrs are
+/// never read. These are the only slots not covered by the goals above, so we fill them
+/// in the order used by [`prepare_vtable_segments`].
+fn new_solver_vtable_entries<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_ref: ty::PolyTraitRef<'tcx>,
+) -> Vec<VtblEntry<'tcx>> {
+    let source = object_ty_for_vtable(tcx, trait_ref);
+    let erased_source = tcx.erase_regions(source);
+
+    let mut entries: Vec<Option<VtblEntry<'tcx>>> =
+        TyCtxt::COMMON_VTABLE_ENTRIES.iter().copied().map(Some).collect();
+    let mut insert = |idx: usize, entry: VtblEntry<'tcx>| {
+        if entries.len() <= idx {
+            entries.resize(idx + 1, None);
+        }
+        if let Some(prev) = entries[idx].replace(entry) {
+            bug!("vtable slot {idx} of `{trait_ref}` used by both {prev:?} and {entry:?}");
+        }
+    };
+
+    let mut vtable_len = TyCtxt::COMMON_VTABLE_ENTRIES.len();
+    for supertrait in supertraits(tcx, trait_ref) {
+        if tcx.trait_is_auto(supertrait.def_id()) {
+            continue;
+        }
+
+        let goal = tcx.normalize_erasing_late_bound_regions(
+            ty::ParamEnv::reveal_all(),
+            supertrait.map_bound(|supertrait| supertrait.with_self_ty(tcx, erased_source)),
+        );
+        let vtable_base = match tcx.codegen_select_candidate((ty::ParamEnv::reveal_all(), goal)) {
+            Ok(&ImplSource::Builtin(BuiltinImplSource::Object { vtable_base }, _)) => vtable_base,
+            otherwise => bug!("expected object candidate for `{goal}`, got {otherwise:?}"),
+        };
+
+        let mut num_own_entries = 0;
+        for (idx, entry) in own_vtable_entries(tcx, supertrait).enumerate() {
+            insert(vtable_base + idx, entry);
+            num_own_entries += 1;
+        }
+        // The own entries of `trait_ref` itself are always at the end of its vtable.
+        if supertrait == trait_ref {
+            vtable_len = vtable_base + num_own_entries;
+        } else {
+            let target = tcx.erase_regions(object_ty_for_vtable(tcx, supertrait));
+            if let Some(vptr_slot) =
+                tcx.vtable_trait_upcasting_coercion_new_vptr_slot((erased_source, target))
+            {
+                insert(vptr_slot, VtblEntry::TraitVPtr(supertrait));
+            }
+        }
     }
 
-    tcx.arena.alloc_from_iter(entries)
+    let mut auto_traits = vec![];
+    let _ = prepare_vtable_segments(tcx, trait_ref, |segment| {
+        if let VtblSegment::TraitOwnEntries { trait_ref, emit_vptr: true } = segment
+            && tcx.trait_is_auto(trait_ref.def_id())
+        {
+            auto_traits.push(trait_ref);
+        }
+        ControlFlow::<()>::Continue(())
+    });
+
+    entries.resize(vtable_len, None);
+    let mut auto_traits = auto_traits.into_iter();
+    let entries = entries
+        .into_iter()
+        .enumerate()
+        .map(|(idx, entry)| match entry {
+            Some(entry) => entry,
+            None => match auto_traits.next() {
+                Some(auto_trait) => VtblEntry::TraitVPtr(auto_trait),
+                None => bug!("vtable slot {idx} of `{trait_ref}` is not used"),
+            },
+        })
+        .collect();
+    if let Some(auto_trait) = auto_traits.next() {
+        bug!("no vtable slot left for the vptr of `{auto_trait}` in the vtable of `{trait_ref}`");
+    }
+    entries
+}
+
+/// Returns the vtable entries for the methods of `trait_ref`, excluding its supertraits.
+fn own_vtable_entries<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_ref: ty::PolyTraitRef<'tcx>,
+) -> impl Iterator<Item = VtblEntry<'tcx>> + 'tcx {
+    let existential_trait_ref =
+        trait_ref.map_bound(|trait_ref| ty::ExistentialTraitRef::erase_self_ty(tcx, trait_ref));
+
+    // Lookup the shape of vtable for the trait.
+    let own_existential_entries =
+        tcx.own_existential_vtable_entries(existential_trait_ref.def_id());
+
+    own_existential_entries.iter().copied().map(move |def_id| {
+        debug!("vtable_entries: trait_method={:?}", def_id);
+
+        // The method may have some early-bound lifetimes; add regions for those.
+        let args = trait_ref.map_bound(|trait_ref| {
+            GenericArgs::for_item(tcx, def_id, |param, _| match param.kind {
+                GenericParamDefKind::Lifetime => tcx.lifetimes.re_erased.into(),
+                GenericParamDefKind::Type { .. } | GenericParamDefKind::Const { .. } => {
+                    trait_ref.args[param.index as usize]
+                }
+            })
+        });
+
+        // The trait type may have higher-ranked lifetimes in it;
+        // erase them if they appear, so that we get the type
+        // at some particular call site.
+        let args = tcx.normalize_erasing_late_bound_regions(ty::ParamEnv::reveal_all(), args);
+
+        // It's possible that the method relies on where-clauses that
+        // do not hold for this particular set of type parameters.
+        // Note that this method could then never be called, so we
+        // do not want to try and codegen it, in that case (see #23435).
+        let predicates = tcx.predicates_of(def_id).instantiate_own(tcx, args);
+        if impossible_predicates(tcx, predicates.map(|(predicate, _)| predicate).collect()) {
+            debug!("vtable_entries: predicates do not hold");
+            return VtblEntry::Vacant;
+        }
+
+        let instance =
+            ty::Instance::resolve_for_vtable(tcx, ty::ParamEnv::reveal_all(), def_id, args)
+                .expect("resolution failed during building vtable representation");
+        VtblEntry::Method(instance)
+    })
+}
+
+/// Creates the trait object type `dyn Trait<Assoc = ..>` for the vtable of `trait_ref`,
+/// specifying the normalized associated types of `trait_ref` and its supertraits.
+fn object_ty_for_vtable<'tcx>(tcx: TyCtxt<'tcx>, trait_ref: ty::PolyTraitRef<'tcx>) -> Ty<'tcx> {
+    let principal = trait_ref.map_bound(|trait_ref| {
+        ty::ExistentialPredicate::Trait(ty::ExistentialTraitRef::erase_self_ty(tcx, trait_ref))
+    });
+    let mut projections: Vec<_> = supertraits(tcx, trait_ref)
+        .flat_map(|supertrait| {
+            tcx.associated_items(supertrait.def_id())
+                .in_definition_order()
+                .filter(|item| item.kind == ty::AssocKind::Type)
+                .map(move |assoc_ty| {
+                    supertrait.map_bound(|supertrait| {
+                        let alias_ty = ty::AliasTy::new(tcx, assoc_ty.def_id, supertrait.args);
+                        let term = tcx.normalize_erasing_regions(
+                            ty::ParamEnv::reveal_all(),
+                            alias_ty.to_ty(tcx),
+                        );
+                        ty::ExistentialPredicate::Projection(ty::ExistentialProjection {
+                            def_id: assoc_ty.def_id,
+                            args: ty::ExistentialTraitRef::erase_self_ty(tcx, supertrait).args,
+                            term: term.into(),
+                        })
+                    })
+                })
+        })
+        .collect();
+    projections.sort_by(|a, b| a.skip_binder().stable_cmp(tcx, &b.skip_binder()));
+    let predicates = tcx
+        .mk_poly_existential_predicates_from_iter(iter::once(principal).chain(projections));
+    Ty::new_dynamic(tcx, predicates, tcx.lifetimes.re_erased, ty::Dyn)
 }
 
 /// Find slot base for trait methods within vtable entries of another trait
@@ -381,9 +533,42 @@ pub(crate) fn vtable_trait_upcasting_coercion_new_vptr_slot<'tcx>(
 
     let trait_ref = ty::TraitRef::new(tcx, unsize_trait_did, [source, target]);
 
-    match tcx.codegen_select_candidate((ty::ParamEnv::reveal_all(), trait_ref)) {
-        Ok(ImplSource::Builtin(BuiltinImplSource::TraitUpcasting { vtable_vptr_slot }, _)) => {
-            *vtable_vptr_slot
+    // With `-Znext-solver=globally`, this uses the new solver, which computes
+    // the vptr slot while walking the vtable of the source trait object.
+    let vtable_vptr_slot =
+        match tcx.codegen_select_candidate((ty::ParamEnv::reveal_all(), trait_ref)) {
+            Ok(ImplSource::Builtin(BuiltinImplSource::TraitUpcasting { vtable_vptr_slot }, _)) => {
+                *vtable_vptr_slot
+            }
+            otherwise => bug!("expected TraitUpcasting candidate, got {otherwise:?}"),
+        };
+
+    if tcx.next_trait_solver_globally() && tcx.sess.opts.unstable_opts.verify_next_solver_vtables
+    {
+        let old_vtable_vptr_slot = old_solver_vtable_vptr_slot(tcx, trait_ref);
+        if vtable_vptr_slot != old_vtable_vptr_slot {
+            bug!(
+                "vptr slot mismatch for `{trait_ref}`: new solver computed \
+                {vtable_vptr_slot:?}, old solver computed {old_vtable_vptr_slot:?}"
+            );
+        }
+    }
+
+    vtable_vptr_slot
+}
+
+/// Computes the vptr slot of the `Unsize` goal `trait_ref` using the old solver.
+/// This is used to check that the new solver computes the same vtable layout.
+fn old_solver_vtable_vptr_slot<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    trait_ref: ty::TraitRef<'tcx>,
+) -> Option<usize> {
+    let infcx = tcx.infer_ctxt().ignoring_regions().with_next_trait_solver(false).build();
+    let obligation =
+        Obligation::new(tcx, ObligationCause::dummy(), ty::ParamEnv::reveal_all(), trait_ref);
+    match SelectionContext::new(&infcx).select(&obligation) {
+        Ok(Some(ImplSource::Builtin(BuiltinImplSource::TraitUpcasting { vtable_vptr_slot }, _))) => {
+            vtable_vptr_slot
         }
         otherwise => bug!("expected TraitUpcasting candidate, got {otherwise:?}"),
     }
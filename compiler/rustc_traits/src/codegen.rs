@@ -10,7 +10,8 @@
 use rustc_middle::ty::{self, TyCtxt, TypeVisitableExt};
 use rustc_trait_selection::traits::error_reporting::TypeErrCtxtExt;
 use rustc_trait_selection::traits::{
-    ImplSource, Obligation, ObligationCause, ObligationCtxt, SelectionContext, Unimplemented,
+    ExplicitNegativeImpl, ImplSource, Obligation, ObligationCause, ObligationCtxt,
+    SelectionContext, Unimplemented,
 };
 use tracing::debug;
 
@@ -30,7 +31,14 @@ pub fn codegen_select_candidate<'tcx>(
 
     // Do the initial selection for the obligation. This yields the
     // shallow result we are looking for -- that is, what specific impl.
-    let infcx = tcx.infer_ctxt().ignoring_regions().build();
+    //
+    // With `-Znext-solver=globally`, we use the new solver here as well, so that
+    // codegen agrees with the impls selected during type checking.
+    let infcx = tcx
+        .infer_ctxt()
+        .ignoring_regions()
+        .with_next_trait_solver(tcx.next_trait_solver_globally())
+        .build();
     let mut selcx = SelectionContext::new(&infcx);
 
     let obligation_cause = ObligationCause::dummy();
@@ -39,7 +47,9 @@ pub fn codegen_select_candidate<'tcx>(
     let selection = match selcx.select(&obligation) {
         Ok(Some(selection)) => selection,
         Ok(None) => return Err(CodegenObligationError::Ambiguity),
-        Err(Unimplemented) => return Err(CodegenObligationError::Unimplemented),
+        Err(Unimplemented | ExplicitNegativeImpl(_)) => {
+            return Err(CodegenObligationError::Unimplemented);
+        }
         Err(e) => {
             bug!("Encountered error `{:?}` selecting `{:?}` during codegen", e, trait_ref)
         }
@@ -64,8 +74,15 @@ pub fn codegen_select_candidate<'tcx>(
         // Cycle errors are the only post-monomorphization errors possible; emit them now so
         // `rustc_ty_utils::resolve_associated_item` doesn't return `None` post-monomorphization.
         for err in errors {
-            if let FulfillmentErrorCode::Cycle(cycle) = err.code {
-                infcx.err_ctxt().report_overflow_obligation_cycle(&cycle);
+            match err.code {
+                FulfillmentErrorCode::Cycle(ref cycle) => {
+                    infcx.err_ctxt().report_overflow_obligation_cycle(cycle);
+                }
+                // The new solver reports overflow as ambiguity instead of a cycle.
+                FulfillmentErrorCode::Ambiguity { overflow: Some(_) } => {
+                    infcx.err_ctxt().report_fulfillment_errors(vec![err]);
+                }
+                _ => {}
             }
         }
         return Err(CodegenObligationError::FulfillmentError);
@@ -0,0 +1,30 @@
+// Check that adding an impl which is not used to prove any goal of a body does not
+// cause the body to be type-checked again with the new solver, as the results of
+// its root goals do not change.
+
+//@ revisions: rpass1 rpass2
+//@ compile-flags: -Z query-dep-graph -Znext-solver
+
+#![feature(rustc_attrs)]
+#![allow(dead_code)]
+
+trait Trait {}
+
+struct Used;
+struct Unused;
+
+impl Trait for Used {}
+
+fn requires_trait<T: Trait>() {}
+
+#[rustc_clean(cfg = "rpass2")]
+fn uses_trait() {
+    requires_trait::<Used>();
+}
+
+#[cfg(rpass2)]
+impl Trait for Unused {}
+
+fn main() {
+    uses_trait();
+}
@@ -0,0 +1,59 @@
+//@ compile-flags: -Znext-solver
+
+// Check that the synthetic auto trait impls are computed using the proof trees of
+// the new solver when it is enabled, and that they only have the where-clauses
+// which are necessary for the auto trait to be implemented.
+
+#![crate_name = "foo"]
+
+// @has foo/struct.Basic.html
+// @has - '//h3[@class="code-header"]' 'impl<T> Send for Basic<T>where T: Send'
+// @has - '//h3[@class="code-header"]' 'impl<T> Sync for Basic<T>where T: Sync'
+pub struct Basic<T> {
+    field: T,
+}
+
+// @has foo/struct.Negative.html
+// @has - '//*[@id="synthetic-implementations-list"]//*[@class="impl"]//h3[@class="code-header"]' \
+// "impl<T> !Send for Negative<T>"
+pub struct Negative<T> {
+    field: *mut T,
+}
+
+pub struct NeedsCopy<T> {
+    field: T,
+}
+
+unsafe impl<T: Copy> Send for NeedsCopy<T> {}
+
+pub struct NeedsClone<T> {
+    field: T,
+}
+
+unsafe impl<T: Clone> Send for NeedsClone<T> {}
+
+// `T: Clone` is implied by `T: Copy`, so it's not necessary.
+// @has foo/struct.Minimal.html
+// @has - '//*[@id="synthetic-implementations-list"]//*[@class="impl"]//h3[@class="code-header"]' \
+// "impl<T> Send for Minimal<T>where T: Copy"
+pub struct Minimal<T> {
+    copy: NeedsCopy<T>,
+    clone: NeedsClone<T>,
+}
+
+pub trait MyTrait {
+    type MyItem;
+}
+
+pub struct NeedsItem<T> {
+    field: T,
+}
+
+unsafe impl<T: MyTrait<MyItem = bool>> Send for NeedsItem<T> {}
+
+// @has foo/struct.Projection.html
+// @has - '//*[@id="synthetic-implementations-list"]//*[@class="impl"]//h3[@class="code-header"]' \
+// "impl<T> Send for Projection<T>where T: MyTrait<MyItem = bool>"
+pub struct Projection<T> {
+    inner: NeedsItem<T>,
+}
@@ -0,0 +1,89 @@
+//@ run-pass
+// Test that cancelling the `SolverCancellationToken` of a fulfillment context stops
+// the new trait solver from evaluating its obligations, which then stay pending.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_hir;
+extern crate rustc_infer;
+extern crate rustc_interface;
+extern crate rustc_middle;
+extern crate rustc_session;
+extern crate rustc_span;
+extern crate rustc_trait_selection;
+
+use rustc_driver::Compilation;
+use rustc_hir::LangItem;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_infer::traits::{Obligation, ObligationCause, TraitEngine};
+use rustc_interface::{interface, Queries};
+use rustc_middle::ty;
+use rustc_session::config::Input;
+use rustc_span::FileName;
+use rustc_trait_selection::solve::{FulfillmentCtxt, SolverCancellationToken};
+
+struct CancelSolver;
+
+impl rustc_driver::Callbacks for CancelSolver {
+    fn config(&mut self, config: &mut interface::Config) {
+        config.input = Input::Str {
+            name: FileName::Custom("input".to_string()),
+            input: "pub fn main() {}".to_string(),
+        };
+    }
+
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        queries.global_ctxt().unwrap().enter(|tcx| {
+            let infcx = tcx.infer_ctxt().with_next_trait_solver(true).build();
+            let copy_trait = tcx.require_lang_item(LangItem::Copy, None);
+            let obligation = Obligation::new(
+                tcx,
+                ObligationCause::dummy(),
+                ty::ParamEnv::empty(),
+                ty::TraitRef::new(tcx, copy_trait, [tcx.types.u32]),
+            );
+
+            // Without cancellation, `u32: Copy` is proven right away.
+            let mut fulfill_cx = FulfillmentCtxt::new(&infcx);
+            fulfill_cx.set_cancellation_token(SolverCancellationToken::new());
+            fulfill_cx.register_predicate_obligation(&infcx, obligation.clone());
+            assert!(fulfill_cx.select_where_possible(&infcx).is_empty());
+            assert!(fulfill_cx.pending_obligations().is_empty());
+            assert!(!fulfill_cx.was_interrupted());
+
+            // Once the token has been cancelled, the obligation is not evaluated and
+            // must not be reported as an error.
+            let cancellation = SolverCancellationToken::new();
+            let mut fulfill_cx = FulfillmentCtxt::new(&infcx);
+            fulfill_cx.set_cancellation_token(cancellation.clone());
+            cancellation.cancel();
+            fulfill_cx.register_predicate_obligation(&infcx, obligation);
+            assert!(fulfill_cx.select_where_possible(&infcx).is_empty());
+            assert_eq!(fulfill_cx.pending_obligations().len(), 1);
+            assert!(fulfill_cx.was_interrupted());
+        });
+        Compilation::Stop
+    }
+}
+
+fn main() {
+    let args = vec![
+        "cancellation-token".to_string(),
+        "-Znext-solver".to_string(),
+        "--crate-type=lib".to_string(),
+        "input.rs".to_string(),
+    ];
+    rustc_driver::catch_fatal_errors(|| {
+        rustc_driver::RunCompiler::new(&args, &mut CancelSolver).run().unwrap();
+    })
+    .unwrap();
+}
@@ -0,0 +1,108 @@
+//@ run-pass
+// Test that the evaluation timing inspector is invoked with the result of each
+// root obligation evaluated by the new trait solver.
+
+//@ ignore-stage1
+//@ ignore-cross-compile
+//@ ignore-remote
+
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_hir;
+extern crate rustc_infer;
+extern crate rustc_interface;
+extern crate rustc_middle;
+extern crate rustc_session;
+extern crate rustc_span;
+extern crate rustc_trait_selection;
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+use rustc_driver::Compilation;
+use rustc_hir::LangItem;
+use rustc_infer::infer::{InferCtxt, TyCtxtInferExt};
+use rustc_infer::traits::{Obligation, ObligationCause, PredicateObligation, TraitEngine};
+use rustc_interface::{interface, Queries};
+use rustc_middle::traits::query::NoSolution;
+use rustc_middle::traits::solve::Certainty;
+use rustc_middle::ty::{self, Ty};
+use rustc_session::config::Input;
+use rustc_span::FileName;
+use rustc_trait_selection::solve::FulfillmentCtxt;
+
+thread_local! {
+    /// The self type of each evaluated `Copy` obligation, `true` for `&'static mut u32`
+    /// and `false` for `u32`, together with its result.
+    static EVALUATIONS: RefCell<Vec<(bool, Result<Certainty, NoSolution>)>> =
+        RefCell::new(Vec::new());
+}
+
+fn record_evaluation<'tcx>(
+    _infcx: &InferCtxt<'tcx>,
+    obligation: &PredicateObligation<'tcx>,
+    result: Result<Certainty, NoSolution>,
+    _duration: Duration,
+) {
+    let trait_pred = obligation.predicate.as_trait_clause().unwrap();
+    let is_ref = trait_pred.self_ty().skip_binder().is_ref();
+    EVALUATIONS.with(|evaluations| evaluations.borrow_mut().push((is_ref, result)));
+}
+
+struct InspectTimings;
+
+impl rustc_driver::Callbacks for InspectTimings {
+    fn config(&mut self, config: &mut interface::Config) {
+        config.input = Input::Str {
+            name: FileName::Custom("input".to_string()),
+            input: "pub fn main() {}".to_string(),
+        };
+    }
+
+    fn after_analysis<'tcx>(
+        &mut self,
+        _compiler: &interface::Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        queries.global_ctxt().unwrap().enter(|tcx| {
+            let infcx = tcx.infer_ctxt().with_next_trait_solver(true).build();
+            infcx.attach_evaluation_timing_inspector(record_evaluation);
+
+            let copy_trait = tcx.require_lang_item(LangItem::Copy, None);
+            let mut fulfill_cx = FulfillmentCtxt::new(&infcx);
+            let mut_ref = Ty::new_mut_ref(tcx, tcx.lifetimes.re_static, tcx.types.u32);
+            for ty in [tcx.types.u32, mut_ref] {
+                fulfill_cx.register_predicate_obligation(
+                    &infcx,
+                    Obligation::new(
+                        tcx,
+                        ObligationCause::dummy(),
+                        ty::ParamEnv::empty(),
+                        ty::TraitRef::new(tcx, copy_trait, [ty]),
+                    ),
+                );
+            }
+            let errors = fulfill_cx.select_where_possible(&infcx);
+            assert_eq!(errors.len(), 1);
+        });
+        Compilation::Stop
+    }
+}
+
+fn main() {
+    let args = vec![
+        "evaluation-timing-inspector".to_string(),
+        "-Znext-solver".to_string(),
+        "--crate-type=lib".to_string(),
+        "input.rs".to_string(),
+    ];
+    rustc_driver::catch_fatal_errors(|| {
+        rustc_driver::RunCompiler::new(&args, &mut InspectTimings).run().unwrap();
+    })
+    .unwrap();
+
+    let mut evaluations = EVALUATIONS.with(|evaluations| evaluations.take());
+    evaluations.sort_by_key(|&(is_ref, _)| is_ref);
+    assert_eq!(evaluations, [(false, Ok(Certainty::Yes)), (true, Err(NoSolution))]);
+}
@@ -0,0 +1,27 @@
+// Check that never type fallback falls back to `()` if this causes a pending goal
+// mentioning the diverging type variable to hold, even if that variable is not the
+// self type of the goal. This is only done with the new solver.
+//
+//@ revisions: current next
+//@ ignore-compare-mode-next-solver (explicit revisions)
+//@[next] compile-flags: -Znext-solver
+//@[next] check-pass
+
+#![feature(never_type_fallback)]
+
+struct Wrap<T>(T);
+
+trait Bar {}
+impl Bar for Wrap<()> {}
+impl Bar for Wrap<u32> {}
+
+fn foo<R>(_: impl Fn() -> R)
+where
+    Wrap<R>: Bar,
+{
+}
+
+fn main() {
+    foo(|| panic!());
+    //[current]~^ ERROR the trait bound `Wrap<!>: Bar` is not satisfied
+}
@@ -0,0 +1,37 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we note what both sides of a failing `AliasRelate` goal
+// normalize to, even if one of them is rigid.
+
+trait Trait {
+    type Assoc;
+}
+
+impl Trait for u8 {
+    type Assoc = u32;
+}
+
+impl Trait for u16 {
+    type Assoc = u32;
+}
+
+trait Other {
+    type Out;
+}
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn assoc<T: Trait>(_: T) -> T::Assoc {
+    todo!()
+}
+
+fn rigid<U: Other>() {
+    let x = mk();
+    let _: U::Out = assoc(x);
+    //~^ ERROR type mismatch resolving `<u8 as Trait>::Assoc == <U as Other>::Out`
+    let _: u8 = x;
+}
+
+fn main() {}
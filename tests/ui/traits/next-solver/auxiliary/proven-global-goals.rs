@@ -0,0 +1,25 @@
+//@ compile-flags: -Znext-solver -Zsolver-metadata-cache
+
+pub trait Trait {}
+
+pub trait Step0 {}
+pub trait Step1 {}
+pub trait Step2 {}
+pub trait Step3 {}
+pub trait Step4 {}
+pub trait Step5 {}
+
+// Proving `u8: Trait` requires proving each of the steps.
+impl Trait for u8 where (): Step0 {}
+impl Step0 for () where (): Step1 {}
+impl Step1 for () where (): Step2 {}
+impl Step2 for () where (): Step3 {}
+impl Step3 for () where (): Step4 {}
+impl Step4 for () where (): Step5 {}
+impl Step5 for () {}
+
+pub fn requires_trait<T: Trait>() {}
+
+pub fn prove() {
+    requires_trait::<u8>();
+}
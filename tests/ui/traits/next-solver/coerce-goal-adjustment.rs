@@ -0,0 +1,16 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we mention the adjustment which would have been required
+// once a `Coerce` goal between two inference variables fails.
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn main() {
+    let a = mk();
+    let b = a;
+    //~^ ERROR mismatched types
+    let p = (a, b);
+    let _: (&[u8; 4], &[u8]) = p;
+}
@@ -0,0 +1,16 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we report the types the inference variables of a failing
+// `Coerce` goal have been constrained to.
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn main() {
+    let a = mk();
+    let b = a;
+    //~^ ERROR mismatched types
+    let p = (a, b);
+    let _: (u8, i8) = p;
+}
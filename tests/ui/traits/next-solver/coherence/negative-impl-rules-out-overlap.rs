@@ -0,0 +1,17 @@
+//@ compile-flags: -Znext-solver
+//@ check-pass
+
+// Check that an explicit negative impl rules out the overlap
+// between two impls when using the new solver.
+
+#![feature(negative_impls)]
+#![feature(with_negative_coherence)]
+
+struct Foo;
+impl !Send for Foo {}
+
+trait Trait {}
+impl<T: Send> Trait for T {}
+impl Trait for Foo {}
+
+fn main() {}
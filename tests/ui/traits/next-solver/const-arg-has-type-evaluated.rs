@@ -0,0 +1,14 @@
+//@ compile-flags: -Znext-solver
+#![crate_type = "lib"]
+
+trait Q {
+    const ASSOC: usize;
+}
+
+impl<const N: u64> Q for [u8; N] {
+    //~^ ERROR mismatched types
+    const ASSOC: usize = 1;
+}
+
+pub fn test() -> [u8; <[u8; 6 + 7] as Q>::ASSOC] { todo!() }
+//~^ ERROR the constant `6 + 7` evaluates to `13`, but a value of type `u64` is required
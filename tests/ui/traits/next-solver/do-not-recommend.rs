@@ -0,0 +1,22 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we don't point at the where-bounds of impls marked
+// with `#[do_not_recommend]` when reporting errors.
+
+#![feature(do_not_recommend)]
+
+pub trait Foo {}
+
+impl Foo for i32 {}
+
+pub trait Bar {}
+
+#[do_not_recommend]
+impl<T: Foo> Bar for T {}
+
+fn stuff<T: Bar>(_: T) {}
+
+fn main() {
+    stuff(1u8);
+    //~^ ERROR the trait bound `u8: Bar` is not satisfied
+}
@@ -0,0 +1,22 @@
+//@ compile-flags: -Znext-solver
+
+// Check how we explain the selection of candidates with the new solver,
+// see `explain_selection_in_new_trait_solver`.
+
+#![feature(rustc_attrs)]
+
+trait Trait {}
+impl<T: Copy> Trait for T {}
+
+#[rustc_evaluate_where_clauses]
+fn requires<T: ?Sized + Trait>() {}
+
+fn with_env<T: Copy + Trait>() {
+    requires::<T>();
+    //~^ ERROR evaluate
+}
+
+fn main() {
+    requires::<String>();
+    //~^ ERROR evaluate
+}
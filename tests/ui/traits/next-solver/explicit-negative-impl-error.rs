@@ -0,0 +1,13 @@
+//@ compile-flags: -Znext-solver
+
+#![feature(negative_impls)]
+
+struct Foo;
+impl !Send for Foo {}
+
+fn needs_send<T: Send>(_: T) {}
+
+fn main() {
+    needs_send(Foo);
+    //~^ ERROR `Foo` cannot be sent between threads safely
+}
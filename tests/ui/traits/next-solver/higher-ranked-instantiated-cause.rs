@@ -0,0 +1,19 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we note the higher-ranked bound if it does not hold
+// after instantiating its bound variables.
+
+trait Foo {}
+
+struct S;
+
+fn needs_foo<T>()
+where
+    for<'a> &'a T: Foo,
+{
+}
+
+fn main() {
+    needs_foo::<S>();
+    //~^ ERROR the trait bound `&'a S: Foo` is not satisfied
+}
@@ -0,0 +1,15 @@
+// Check that fully concrete goals which have been proven by an upstream crate are
+// not evaluated again with `-Zsolver-metadata-cache`. Proving `u8: Trait` takes more
+// steps than allowed by `-Zsolver-step-limit`, so this would fail if we did.
+
+//@ aux-build:proven-global-goals.rs
+//@ compile-flags: -Znext-solver -Zsolver-metadata-cache -Zsolver-step-limit=3
+//@ check-pass
+
+extern crate proven_global_goals;
+
+use proven_global_goals::requires_trait;
+
+fn main() {
+    requires_trait::<u8>();
+}
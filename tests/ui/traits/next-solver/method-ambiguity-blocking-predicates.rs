@@ -0,0 +1,34 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we explain which goals keep ambiguous method candidates
+// from definitely applying when reporting an ambiguity error.
+
+trait A {
+    fn foo(&self);
+}
+
+trait B {
+    fn foo(&self);
+}
+
+trait Bound1 {}
+trait Bound2 {}
+impl Bound1 for u32 {}
+impl Bound2 for u32 {}
+
+struct W<T: ?Sized>(T);
+
+impl<T: ?Sized + Bound1> A for W<T> {
+    fn foo(&self) {}
+}
+
+impl<T: ?Sized + Bound2> B for W<T> {
+    fn foo(&self) {}
+}
+
+fn main() {
+    let x = W(Default::default());
+    x.foo();
+    //~^ ERROR multiple applicable items in scope
+    let _: W<u32> = x;
+}
@@ -0,0 +1,41 @@
+//@ revisions: current next
+//@ ignore-compare-mode-next-solver (explicit revisions)
+//@[next] compile-flags: -Znext-solver
+//@[next] check-pass
+
+// Check that the new solver prefers method candidates which definitely apply
+// over candidates which are still ambiguous. The old solver considers both
+// candidates to apply, resulting in an ambiguity error.
+
+trait A {
+    fn foo(&self) -> u8;
+}
+
+trait B {
+    fn foo(&self) -> u16;
+}
+
+trait Bound {}
+impl Bound for u32 {}
+impl Bound for i32 {}
+
+struct W<T>(T);
+
+impl<T: Bound> A for W<T> {
+    fn foo(&self) -> u8 {
+        0
+    }
+}
+
+impl<T> B for W<T> {
+    fn foo(&self) -> u16 {
+        0
+    }
+}
+
+fn main() {
+    let x = W(Default::default());
+    let _: u16 = x.foo();
+    //[current]~^ ERROR multiple applicable items in scope
+    let _: W<u32> = x;
+}
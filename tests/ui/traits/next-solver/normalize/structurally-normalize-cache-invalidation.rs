@@ -0,0 +1,26 @@
+//@ compile-flags: -Znext-solver
+//@ check-pass
+
+// Structurally normalizing `<?0 as Id>::Assoc` while `?0` is still unknown results
+// in an inference variable. Check that the cached result is not used once that
+// variable has been resolved, as method lookup needs the resolved type.
+
+trait Id {
+    type Assoc;
+}
+impl<T> Id for T {
+    type Assoc = T;
+}
+
+fn id<T>(x: T) -> <T as Id>::Assoc {
+    x
+}
+
+fn main() {
+    let x = Default::default();
+    let y = id(x);
+    let _ = &y;
+    let z: u32 = y;
+    let _ = y.count_ones();
+    let _ = z.leading_zeros();
+}
@@ -0,0 +1,31 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we report the normalized and the expected type when
+// normalizing an alias fails after its self type has been inferred.
+
+trait Trait {
+    type Assoc;
+}
+
+impl Trait for u8 {
+    type Assoc = u32;
+}
+
+impl Trait for u16 {
+    type Assoc = u32;
+}
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn assoc<T: Trait>(_: T) -> T::Assoc {
+    todo!()
+}
+
+fn main() {
+    let x = mk();
+    let _: i32 = assoc(x);
+    //~^ ERROR type mismatch resolving `<u8 as Trait>::Assoc == i32`
+    let _: u8 = x;
+}
@@ -0,0 +1,19 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we mention the supertraits elaborated from the principal trait
+// of an object type if a bound of its object candidate does not hold.
+
+trait Super {
+    type Assoc: Copy;
+}
+
+trait Middle: Super {}
+
+trait Sub: Middle {}
+
+fn needs_super<T: Super + ?Sized>() {}
+
+fn main() {
+    needs_super::<dyn Sub<Assoc = String>>();
+    //~^ ERROR the trait bound `String: Copy` is not satisfied in `dyn Sub<Assoc = String>`
+}
@@ -0,0 +1,17 @@
+//@ compile-flags: -Znext-solver=dump-tree -Zdump-solver-proof-tree-format=stable
+//@ check-pass
+// Only keep the opaque type constraints of the dumped proof trees.
+//@ normalize-stdout-test "(?m)^ *(proof-tree|goal|candidate|negative impl|impls shadowed) .*\n" -> ""
+//@ normalize-stdout-test "(?m)^ +" -> ""
+
+// Check that opaque types constrained while proving a goal are
+// recorded in its proof tree.
+
+#![feature(type_alias_impl_trait)]
+#![crate_type = "lib"]
+
+type Tait = impl Sized;
+
+fn define() -> Tait {
+    1u32
+}
@@ -0,0 +1,18 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we note the chain of nested goals which caused an overflow.
+#![recursion_limit = "4"]
+
+trait Trait {}
+
+struct Inc<T>(T);
+
+impl<T: Trait> Trait for Inc<T> {}
+impl Trait for () {}
+
+fn impls_trait<T: Trait>() {}
+
+fn main() {
+    impls_trait::<Inc<Inc<Inc<Inc<Inc<()>>>>>>();
+    //~^ ERROR overflow evaluating the requirement `(): Trait`
+}
@@ -0,0 +1,31 @@
+//@ compile-flags: -Znext-solver -Zsolver-step-limit=3
+
+// Check that using the global cache does not change whether we reach
+// `-Zsolver-step-limit`. Proving `u8: Step1` requires 3 goals and succeeds,
+// but later reusing its cached result while proving `u8: Trait` still has to
+// account for these goals.
+
+trait Trait {}
+
+trait Step0 {}
+trait Step1 {}
+trait Step2 {}
+trait Step3 {}
+
+impl<T: Step0> Trait for T {}
+impl<T: Step1> Step0 for T {}
+impl<T: Step2> Step1 for T {}
+impl<T: Step3> Step2 for T {}
+impl<T> Step3 for T {}
+
+fn needs_step1<T: Step1>() {}
+fn needs_trait<T: Trait>() {}
+
+fn warm_cache() {
+    needs_step1::<u8>();
+}
+
+fn main() {
+    needs_trait::<u8>();
+    //~^ ERROR the requirement `u8: Step1` is too complex to solve
+}
@@ -0,0 +1,24 @@
+//@ compile-flags: -Znext-solver -Zsolver-step-limit=3
+
+// Check that we give up on goals which require computing more goals than
+// allowed by `-Zsolver-step-limit`, even if they do not reach the recursion limit.
+
+trait Trait {}
+
+trait Step0 {}
+trait Step1 {}
+trait Step2 {}
+trait Step3 {}
+
+impl<T: Step0> Trait for T {}
+impl<T: Step1> Step0 for T {}
+impl<T: Step2> Step1 for T {}
+impl<T: Step3> Step2 for T {}
+impl<T> Step3 for T {}
+
+fn needs_trait<T: Trait>() {}
+
+fn main() {
+    needs_trait::<u8>();
+    //~^ ERROR the requirement `u8: Step2` is too complex to solve
+}
@@ -0,0 +1,16 @@
+//@ compile-flags: -Znext-solver
+
+// The lub coercion of the second array element results in two `Coerce` goals
+// sharing the same span. Check that we only report one of them once both fail.
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn main() {
+    let a = mk();
+    let b = mk();
+    let p = (a, b, [a, b]);
+    //~^ ERROR mismatched types
+    let _: (u8, i8, [u16; 2]) = p;
+}
@@ -0,0 +1,28 @@
+//@ revisions: depth_first shallowest
+//@ compile-flags: -Znext-solver
+//@[shallowest] compile-flags: -Znext-solver-shallowest-errors
+
+// Check that `-Znext-solver-shallowest-errors` reports the shallowest failing
+// nested goal instead of the first one found when searching depth-first.
+
+trait Foo {}
+trait Bar {}
+trait Baz {}
+trait Qux {}
+
+impl Baz for i32 {}
+impl Qux for i32 {}
+
+struct W<T>(T);
+
+impl<T: Baz> Bar for W<T> {}
+
+impl<T: Bar + Qux> Foo for T {}
+
+fn needs_foo<T: Foo>() {}
+
+fn main() {
+    needs_foo::<W<u8>>();
+    //[depth_first]~^ ERROR the trait bound `u8: Baz` is not satisfied
+    //[shallowest]~^^ ERROR the trait bound `W<u8>: Qux` is not satisfied
+}
@@ -0,0 +1,20 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we report the bound of a trait alias which does not hold
+// instead of the trait alias itself.
+
+#![feature(trait_alias)]
+
+trait Foo {}
+trait Bar {}
+
+impl Foo for u8 {}
+
+trait Alias = Foo + Bar;
+
+fn needs_alias<T: Alias>() {}
+
+fn main() {
+    needs_alias::<u8>();
+    //~^ ERROR the trait bound `u8: Bar` is not satisfied
+}
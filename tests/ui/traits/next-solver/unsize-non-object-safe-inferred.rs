@@ -0,0 +1,24 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we explain why the trait is not object safe when unsizing to its
+// trait object, even if the source type is only inferred after the coercion.
+
+trait Bar {
+    fn bar<T>(&self, t: T);
+}
+
+impl Bar for u8 {
+    fn bar<T>(&self, _: T) {}
+}
+
+fn mk<T>() -> T {
+    todo!()
+}
+
+fn main() {
+    let x = mk();
+    let _: &dyn Bar = &x;
+    //~^ ERROR the trait `Bar` cannot be made into an object
+    //~| ERROR the trait `Bar` cannot be made into an object
+    let _: u8 = x;
+}
@@ -0,0 +1,22 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we point at the tail field of a struct if unsizing
+// the struct fails because its tail field cannot be unsized.
+
+#![feature(unsize)]
+
+use std::marker::Unsize;
+
+trait Trait {}
+
+struct W<T: ?Sized> {
+    header: u8,
+    tail: T,
+}
+
+fn needs_unsize<T: ?Sized + Unsize<U>, U: ?Sized>() {}
+
+fn main() {
+    needs_unsize::<W<u8>, W<dyn Trait>>();
+    //~^ ERROR the trait bound `u8: Trait` is not satisfied
+}
@@ -0,0 +1,15 @@
+//@ compile-flags: -Znext-solver
+
+// Check that we point out the unsatisfied where-bound of a field type
+// instead of only reporting that the whole type is not well-formed.
+
+struct IsCopy<T: Copy> {
+    value: T,
+}
+
+struct SomeStruct<A> {
+    data: IsCopy<A>,
+    //~^ ERROR the trait bound `A: Copy` is not satisfied
+}
+
+fn main() {}
@@ -3,6 +3,9 @@
 // This test makes sure that multiple marker (method-less) traits can reuse the
 // same pointer for upcasting.
 //
+//@ revisions: current next
+//@ ignore-compare-mode-next-solver (explicit revisions)
+//@[next] compile-flags: -Znext-solver=globally -Zverify-next-solver-vtables
 //@ build-fail
 #![crate_type = "lib"]
 #![feature(rustc_attrs)]
@@ -1,3 +1,6 @@
+//@ revisions: current next
+//@ ignore-compare-mode-next-solver (explicit revisions)
+//@[next] compile-flags: -Znext-solver=globally -Zverify-next-solver-vtables
 //@ build-fail
 #![feature(rustc_attrs)]
 